@@ -0,0 +1,216 @@
+use std::{env, fmt::Write, fs, path::Path};
+
+// turns src/blocks.json, the block report of the vanilla data generator
+// (java -DbundlerMainClass=net.minecraft.data.Main -jar server.jar --reports), into the BLOCKS table of src/block.rs
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=src/blocks.json");
+
+    let report = fs::read_to_string("src/blocks.json").expect("src/blocks.json is readable");
+    let mut parser = Parser { input: report.as_bytes(), position: 0 };
+    let Json::Object(blocks) = parser.value() else {
+        panic!("the block report is not an object")
+    };
+
+    let mut table = String::from("pub const BLOCKS: &[Block] = &[\n");
+    let mut names = Vec::with_capacity(blocks.len());
+    let mut next = 0;
+
+    for (index, (name, block)) in blocks.iter().enumerate() {
+        let properties = match block.get("properties") {
+            Some(Json::Object(properties)) => properties.iter()
+                .map(|(property, values)| (property.as_str(), values.array().iter().map(Json::string).collect::<Vec<_>>()))
+                .collect(),
+            _ => vec![]
+        };
+        let states = block.get("states").map_or(&[][..], Json::array);
+        let count: usize = properties.iter().map(|(_, values)| values.len()).product();
+
+        assert!(properties.windows(2).all(|pair| pair[0].0 < pair[1].0), "{name} has unsorted properties");
+        assert_eq!(states.len(), count, "{name} has the wrong number of states");
+
+        let base = states[0].get("id").map_or(0, Json::number);
+        let mut default = None;
+
+        assert_eq!(base, next, "{name} does not follow the block before it");
+
+        for (offset, state) in states.iter().enumerate() {
+            assert_eq!(state.get("id").map(Json::number), Some(base + offset), "{name} has states out of order");
+
+            // the first property changes the slowest
+            let mut rest = offset;
+
+            for (property, values) in properties.iter().rev() {
+                let value = state.get("properties").and_then(|state| state.get(property)).map(Json::string);
+                assert_eq!(value, Some(values[rest % values.len()]), "{name} has states out of order");
+                rest /= values.len();
+            }
+
+            if let Some(Json::Bool(true)) = state.get("default") {
+                default = Some(offset);
+            }
+        }
+
+        let default = default.unwrap_or_else(|| panic!("{name} has no default state"));
+        let properties: Vec<String> = properties.iter()
+            .map(|(property, values)| format!("Property {{ name: {property:?}, values: &{values:?} }}"))
+            .collect();
+
+        writeln!(table, "    Block {{ name: {name:?}, base: {base}, default: {default}, properties: &[{}] }},", properties.join(", ")).unwrap();
+        names.push((name.as_str(), index));
+        next = base + count;
+    }
+
+    assert!(next <= 0x8000, "block state ids run into the unknown states");
+
+    names.sort();
+    table.push_str("];\n\n// indices into BLOCKS, sorted by name\nconst BLOCKS_BY_NAME: &[u16] = &[");
+
+    for (position, (_, index)) in names.iter().enumerate() {
+        let separator = if position % 32 == 0 { "\n    " } else { " " };
+        write!(table, "{separator}{index},").unwrap();
+    }
+
+    table.push_str("\n];\n");
+    fs::write(Path::new(&env::var("OUT_DIR").unwrap()).join("blocks.rs"), table).unwrap();
+}
+
+enum Json {
+    Object(Vec<(String, Json)>),
+    Array(Vec<Json>),
+    String(String),
+    Number(usize),
+    Bool(bool)
+}
+
+impl Json {
+    fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Self::Object(entries) => entries.iter().find(|(name, _)| name == key).map(|(_, value)| value),
+            _ => None
+        }
+    }
+
+    fn array(&self) -> &[Json] {
+        match self {
+            Self::Array(values) => values,
+            _ => panic!("expected an array in the block report")
+        }
+    }
+
+    fn string(&self) -> &str {
+        match self {
+            Self::String(value) => value,
+            _ => panic!("expected a string in the block report")
+        }
+    }
+
+    fn number(&self) -> usize {
+        match self {
+            Self::Number(value) => *value,
+            _ => panic!("expected a number in the block report")
+        }
+    }
+}
+
+// just enough json for the report: no escapes beyond quotes and backslashes, no negative or fractional numbers
+struct Parser<'a> {
+    input: &'a [u8],
+    position: usize
+}
+
+impl Parser<'_> {
+    fn skip_whitespace(&mut self) {
+        while self.input.get(self.position).is_some_and(u8::is_ascii_whitespace) {
+            self.position += 1;
+        }
+    }
+
+    fn expect(&mut self, byte: u8) {
+        self.skip_whitespace();
+        assert_eq!(self.input.get(self.position), Some(&byte), "expected {:?} at byte {} of the block report", byte as char, self.position);
+        self.position += 1;
+    }
+
+    // consumes `byte` if it comes next
+    fn eat(&mut self, byte: u8) -> bool {
+        self.skip_whitespace();
+        let found = self.input.get(self.position) == Some(&byte);
+        self.position += found as usize;
+        found
+    }
+
+    fn value(&mut self) -> Json {
+        self.skip_whitespace();
+
+        match self.input.get(self.position) {
+            Some(b'{') => {
+                self.position += 1;
+                let mut entries = vec![];
+
+                while !self.eat(b'}') {
+                    if !entries.is_empty() {
+                        self.expect(b',');
+                    }
+
+                    let key = self.string();
+                    self.expect(b':');
+                    entries.push((key, self.value()));
+                }
+
+                Json::Object(entries)
+            },
+            Some(b'[') => {
+                self.position += 1;
+                let mut values = vec![];
+
+                while !self.eat(b']') {
+                    if !values.is_empty() {
+                        self.expect(b',');
+                    }
+
+                    values.push(self.value());
+                }
+
+                Json::Array(values)
+            },
+            Some(b'"') => Json::String(self.string()),
+            Some(b't') | Some(b'f') => {
+                let value = self.input[self.position..].starts_with(b"true");
+                self.position += if value { 4 } else { 5 };
+                Json::Bool(value)
+            },
+            _ => {
+                let start = self.position;
+
+                while self.input.get(self.position).is_some_and(u8::is_ascii_digit) {
+                    self.position += 1;
+                }
+
+                let digits = std::str::from_utf8(&self.input[start..self.position]).unwrap();
+                Json::Number(digits.parse().unwrap_or_else(|_| panic!("expected a value at byte {start} of the block report")))
+            }
+        }
+    }
+
+    fn string(&mut self) -> String {
+        self.expect(b'"');
+        let mut value = vec![];
+
+        loop {
+            match self.input[self.position] {
+                b'"' => break,
+                b'\\' => {
+                    self.position += 1;
+                    value.push(self.input[self.position]);
+                },
+                byte => value.push(byte)
+            }
+
+            self.position += 1;
+        }
+
+        self.position += 1;
+        String::from_utf8(value).unwrap()
+    }
+}
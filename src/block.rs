@@ -0,0 +1,380 @@
+use std::{collections::BTreeMap, fmt::{self, Debug, Formatter}, sync::Mutex};
+
+use crate::nbt::{Compound, Tag};
+
+const HORIZONTAL_DIRECTIONS: [&str; 4] = ["north", "east", "south", "west"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Rotation {
+    #[default]
+    None,
+    Clockwise90,
+    Clockwise180,
+    Counterclockwise90
+}
+
+impl Rotation {
+    pub fn quarter_turns(self) -> usize {
+        match self {
+            Self::None => 0,
+            Self::Clockwise90 => 1,
+            Self::Clockwise180 => 2,
+            Self::Counterclockwise90 => 3
+        }
+    }
+}
+
+// LeftRight flips the z axis, FrontBack the x axis
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Mirror {
+    #[default]
+    None,
+    LeftRight,
+    FrontBack
+}
+
+pub struct Property {
+    pub name: &'static str,
+    pub values: &'static [&'static str]
+}
+
+pub struct Block {
+    pub name: &'static str,
+    pub base: u16,
+    pub default: u16,
+    // sorted by name, the first property changes the state id the slowest
+    pub properties: &'static [Property]
+}
+
+impl Block {
+    pub fn state_count(&self) -> u16 {
+        self.properties.iter().map(|property| property.values.len() as u16).product()
+    }
+
+    pub fn default_state(&self) -> BlockState {
+        BlockState(self.base + self.default)
+    }
+}
+
+// BLOCKS and BLOCKS_BY_NAME, generated by build.rs from the vanilla block report in src/blocks.json
+include!(concat!(env!("OUT_DIR"), "/blocks.rs"));
+
+const NON_BLOCKING: &[&str] = &[
+    "minecraft:oak_sapling", "minecraft:spruce_sapling", "minecraft:birch_sapling", "minecraft:jungle_sapling",
+    "minecraft:acacia_sapling", "minecraft:cherry_sapling", "minecraft:dark_oak_sapling", "minecraft:cobweb",
+    "minecraft:grass", "minecraft:fern", "minecraft:dead_bush", "minecraft:dandelion", "minecraft:torchflower",
+    "minecraft:poppy", "minecraft:blue_orchid", "minecraft:allium", "minecraft:azure_bluet", "minecraft:red_tulip",
+    "minecraft:orange_tulip", "minecraft:white_tulip", "minecraft:pink_tulip", "minecraft:oxeye_daisy",
+    "minecraft:cornflower", "minecraft:wither_rose", "minecraft:lily_of_the_valley", "minecraft:brown_mushroom",
+    "minecraft:red_mushroom", "minecraft:sugar_cane"
+];
+
+// blocks that keep players out of less than the whole cube, besides the ones that can be waterlogged which all do
+// FIXME: collision shapes are not known, so movement checks treat these as passable instead of guessing
+const PARTIAL_COLLISION: &[&str] = &[
+    "minecraft:snow", "minecraft:soul_sand", "minecraft:farmland", "minecraft:dirt_path", "minecraft:cactus", "minecraft:cake",
+    "minecraft:honey_block", "minecraft:mud", "minecraft:hopper", "minecraft:brewing_stand", "minecraft:enchanting_table",
+    "minecraft:end_portal_frame", "minecraft:daylight_detector", "minecraft:bell", "minecraft:grindstone", "minecraft:lectern",
+    "minecraft:stonecutter", "minecraft:flower_pot", "minecraft:fire", "minecraft:soul_fire", "minecraft:nether_portal",
+    "minecraft:end_portal", "minecraft:end_gateway", "minecraft:kelp", "minecraft:seagrass", "minecraft:tall_seagrass",
+    "minecraft:bamboo", "minecraft:composter", "minecraft:piston_head", "minecraft:moving_piston", "minecraft:chorus_plant",
+    "minecraft:chorus_flower", "minecraft:cocoa", "minecraft:frogspawn", "minecraft:powder_snow", "minecraft:tripwire",
+    "minecraft:tripwire_hook", "minecraft:lever", "minecraft:end_rod", "minecraft:redstone_wire", "minecraft:repeater",
+    "minecraft:comparator", "minecraft:wheat", "minecraft:carrots", "minecraft:potatoes", "minecraft:beetroots",
+    "minecraft:spore_blossom", "minecraft:pink_petals", "minecraft:structure_void", "minecraft:tall_grass",
+    "minecraft:large_fern", "minecraft:sunflower", "minecraft:lilac", "minecraft:peony", "minecraft:lily_pad",
+    "minecraft:azalea", "minecraft:flowering_azalea", "minecraft:crimson_fungus", "minecraft:warped_fungus",
+    "minecraft:crimson_roots", "minecraft:warped_roots", "minecraft:nether_sprouts", "minecraft:torchflower_crop",
+    "minecraft:cauldron", "minecraft:water_cauldron", "minecraft:lava_cauldron", "minecraft:powder_snow_cauldron",
+    "minecraft:anvil", "minecraft:chipped_anvil", "minecraft:damaged_anvil", "minecraft:turtle_egg", "minecraft:dragon_egg",
+    "minecraft:nether_wart", "minecraft:bubble_column"
+];
+const PARTIAL_COLLISION_SUFFIXES: &[&str] = &[
+    "_door", "_bed", "_carpet", "_pressure_plate", "_button", "torch", "_banner", "_head", "_skull", "_stem", "_bush",
+    "vine", "vines", "_sapling", "_plant"
+];
+
+// block states missing from BLOCKS, from worlds of newer versions or with mods, get ids from here on
+// so chunks can hold them and write them back out unchanged
+const UNKNOWN_BASE: u16 = 0x8000;
+
+struct UnknownState {
+    // the palette entry it was read from
+    nbt: Compound,
+    stand_in: BlockState
+}
+
+// the same palette entry always gets the same id, so unknown states compare like known ones
+struct UnknownStates {
+    ids: BTreeMap<String, u16>,
+    states: Vec<UnknownState>
+}
+
+static UNKNOWN_STATES: Mutex<UnknownStates> = Mutex::new(UnknownStates { ids: BTreeMap::new(), states: Vec::new() });
+
+// the known block an unknown state looks like, its block when only the properties are unknown
+fn stand_in(name: &str) -> BlockState {
+    match block_by_name(name) {
+        Some(block) => block.default_state(),
+        None if name.ends_with("air") => BlockState::AIR,
+        None => BlockState::STONE
+    }
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct BlockState(pub u16);
+
+impl BlockState {
+    pub const AIR: Self = Self(0);
+    pub const STONE: Self = Self(1);
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        block_by_name(name).map(Block::default_state)
+    }
+
+    pub fn from_properties(name: &str, properties: &[(&str, &str)]) -> Option<Self> {
+        let block = block_by_name(name)?;
+        let defaults = block.default_state().properties();
+        let mut offset = 0;
+
+        for (property, (_, default)) in block.properties.iter().zip(defaults) {
+            let value = properties.iter()
+                .find(|(key, _)| *key == property.name)
+                .map_or(default, |(_, value)| *value);
+            let index = property.values.iter().position(|candidate| *candidate == value)?;
+
+            offset = offset * property.values.len() as u16 + index as u16;
+        }
+
+        Some(BlockState(block.base + offset))
+    }
+
+    // a palette entry as found in structures and chunk sections: Name and an optional Properties compound of strings
+    pub fn from_nbt(entry: &Compound) -> Option<Self> {
+        let properties: Vec<(&str, &str)> = entry.get_compound("Properties")
+            .map(|properties| properties.0.iter().filter_map(|(key, value)| Some((key.as_str(), value.as_str()?))).collect())
+            .unwrap_or_default();
+
+        let name = entry.get_str("Name")?;
+        let block = block_by_name(name)?;
+
+        // a property the block lacks comes from another version, reading past it would lose it on saving
+        if properties.iter().any(|(key, _)| !block.properties.iter().any(|property| property.name == *key)) {
+            return None
+        }
+
+        Self::from_properties(name, &properties)
+    }
+
+    // like from_nbt, but a state missing from BLOCKS becomes an unknown state instead of none
+    pub fn from_nbt_or_unknown(entry: &Compound) -> Result<Self, String> {
+        let name = entry.get_str("Name").ok_or("block state without a name")?;
+
+        if let Some(state) = Self::from_nbt(entry) {
+            return Ok(state)
+        }
+
+        let mut key = name.to_string();
+
+        for (property, value) in entry.get_compound("Properties").map(|properties| &properties.0).into_iter().flatten() {
+            key.push_str(&format!(",{}={}", property, value.as_str().unwrap_or_default()));
+        }
+
+        let mut unknown = UNKNOWN_STATES.lock().unwrap();
+
+        if let Some(id) = unknown.ids.get(&key) {
+            return Ok(Self(*id))
+        }
+
+        let id = u16::try_from(unknown.states.len()).ok()
+            .and_then(|index| index.checked_add(UNKNOWN_BASE))
+            .ok_or("too many unknown block states")?;
+
+        unknown.ids.insert(key, id);
+        unknown.states.push(UnknownState { nbt: entry.clone(), stand_in: stand_in(name) });
+        Ok(Self(id))
+    }
+
+    pub fn is_unknown(self) -> bool {
+        self.0 >= UNKNOWN_BASE
+    }
+
+    // what clients are sent and what unknown states act like everywhere but in saves, known states stand in for themselves
+    pub fn stand_in(self) -> Self {
+        if !self.is_unknown() {
+            return self
+        }
+
+        UNKNOWN_STATES.lock().unwrap().states.get((self.0 - UNKNOWN_BASE) as usize).map_or(Self::STONE, |state| state.stand_in)
+    }
+
+    // unknown states give back the palette entry they were read from
+    pub fn to_nbt(self) -> Compound {
+        if self.is_unknown() {
+            if let Some(state) = UNKNOWN_STATES.lock().unwrap().states.get((self.0 - UNKNOWN_BASE) as usize) {
+                return state.nbt.clone()
+            }
+        }
+
+        let properties = self.properties();
+        let mut entry = Compound::new().with("Name", Tag::String(self.name().to_string()));
+
+        if !properties.is_empty() {
+            entry.insert("Properties", Tag::Compound(Compound(
+                properties.into_iter().map(|(key, value)| (key.to_string(), Tag::String(value.to_string()))).collect()
+            )));
+        }
+
+        entry
+    }
+
+    // BLOCKS is sorted by base id
+    pub fn block(self) -> Option<&'static Block> {
+        let index = BLOCKS.partition_point(|block| block.base <= self.0).checked_sub(1)?;
+        let block = &BLOCKS[index];
+
+        (self.0 < block.base + block.state_count()).then_some(block)
+    }
+
+    pub fn name(self) -> &'static str {
+        self.stand_in().block().map_or("minecraft:air", |block| block.name)
+    }
+
+    pub fn properties(self) -> Vec<(&'static str, &'static str)> {
+        let state = self.stand_in();
+        let Some(block) = state.block() else {
+            return vec![]
+        };
+
+        let mut offset = state.0 - block.base;
+        let mut properties = Vec::with_capacity(block.properties.len());
+
+        for property in block.properties.iter().rev() {
+            let count = property.values.len() as u16;
+            properties.push((property.name, property.values[(offset % count) as usize]));
+            offset /= count;
+        }
+
+        properties.reverse();
+        properties
+    }
+
+    // the same block with every property value passed through `map`, values it returns have to be valid for the property
+    fn map_properties(self, map: impl Fn(&Property, usize) -> usize) -> Self {
+        let Some(block) = self.block() else {
+            return self
+        };
+
+        let mut offset = self.0 - block.base;
+        let mut indices = Vec::with_capacity(block.properties.len());
+
+        for property in block.properties.iter().rev() {
+            let count = property.values.len() as u16;
+            indices.push(map(property, (offset % count) as usize));
+            offset /= count;
+        }
+
+        let offset = block.properties.iter()
+            .zip(indices.into_iter().rev())
+            .fold(0, |offset, (property, index)| offset * property.values.len() as u16 + index as u16);
+
+        BlockState(block.base + offset)
+    }
+
+    pub fn rotate(self, rotation: Rotation) -> Self {
+        let turns = rotation.quarter_turns();
+
+        if turns == 0 {
+            return self
+        }
+
+        self.map_properties(|property, index| match (property.name, property.values[index]) {
+            ("facing", value) if HORIZONTAL_DIRECTIONS.contains(&value) => {
+                let direction = HORIZONTAL_DIRECTIONS[(HORIZONTAL_DIRECTIONS.iter().position(|direction| *direction == value).unwrap() + turns) % 4];
+                property.values.iter().position(|value| *value == direction).unwrap_or(index)
+            },
+            ("axis", "x" | "z") if turns % 2 == 1 => {
+                let axis = if property.values[index] == "x" { "z" } else { "x" };
+                property.values.iter().position(|value| *value == axis).unwrap_or(index)
+            },
+            // sixteen steps around, as on signs and banners
+            ("rotation", _) => (index + turns * 4) % 16,
+            _ => index
+        })
+    }
+
+    pub fn mirror(self, mirror: Mirror) -> Self {
+        let swap = match mirror {
+            Mirror::None => return self,
+            Mirror::LeftRight => ("north", "south"),
+            Mirror::FrontBack => ("east", "west")
+        };
+
+        self.map_properties(|property, index| match (property.name, property.values[index]) {
+            ("facing", value) if value == swap.0 || value == swap.1 => {
+                let direction = if value == swap.0 { swap.1 } else { swap.0 };
+                property.values.iter().position(|value| *value == direction).unwrap_or(index)
+            },
+            ("rotation", _) => match mirror {
+                Mirror::LeftRight => (24 - index) % 16,
+                _ => (16 - index) % 16
+            },
+            _ => index
+        })
+    }
+
+    pub fn is_air(self) -> bool {
+        matches!(self.name(), "minecraft:air" | "minecraft:cave_air" | "minecraft:void_air")
+    }
+
+    pub fn is_liquid(self) -> bool {
+        matches!(self.name(), "minecraft:water" | "minecraft:lava")
+    }
+
+    // what the MOTION_BLOCKING heightmap tracks, fluids included
+    pub fn blocks_motion(self) -> bool {
+        !self.is_air() && !NON_BLOCKING.contains(&self.name())
+    }
+
+    // whether nothing can be inside the block, for movement checks
+    pub fn has_full_collision(self) -> bool {
+        let name = self.name();
+
+        self.blocks_motion()
+            && !self.is_liquid()
+            && !name.starts_with("minecraft:potted_")
+            && !PARTIAL_COLLISION.contains(&name)
+            && !PARTIAL_COLLISION_SUFFIXES.iter().any(|suffix| name.ends_with(suffix))
+            && !self.stand_in().block().is_some_and(|block| block.properties.iter().any(|property| property.name == "waterlogged"))
+    }
+}
+
+impl Debug for BlockState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.is_unknown() {
+            true => write!(f, "BlockState({}, unknown {:?})", self.0, self.to_nbt().get_str("Name")),
+            false => write!(f, "BlockState({}, {})", self.0, self.name())
+        }
+    }
+}
+
+pub fn block_by_name(name: &str) -> Option<&'static Block> {
+    let name = name.strip_prefix("minecraft:").unwrap_or(name);
+    let index = BLOCKS_BY_NAME.binary_search_by(|index| BLOCKS[*index as usize].name["minecraft:".len()..].cmp(name)).ok()?;
+
+    Some(&BLOCKS[BLOCKS_BY_NAME[index] as usize])
+}
+
+// registry order of minecraft:block_entity_type in 1.19.4
+pub const BLOCK_ENTITY_TYPES: &[&str] = &[
+    "furnace", "chest", "trapped_chest", "ender_chest", "jukebox", "dispenser", "dropper", "sign", "hanging_sign",
+    "mob_spawner", "piston", "brewing_stand", "enchanting_table", "end_portal", "beacon", "skull", "daylight_detector",
+    "hopper", "comparator", "banner", "structure_block", "end_gateway", "command_block", "shulker_box", "bed", "conduit",
+    "barrel", "smoker", "blast_furnace", "lectern", "bell", "jigsaw", "campfire", "beehive", "sculk_sensor",
+    "sculk_catalyst", "sculk_shrieker", "chiseled_bookshelf", "suspicious_sand", "decorated_pot"
+];
+
+pub fn block_entity_type_id(name: &str) -> Option<i32> {
+    let name = name.strip_prefix("minecraft:").unwrap_or(name);
+    BLOCK_ENTITY_TYPES.iter().position(|kind| *kind == name).map(|id| id as i32)
+}
@@ -0,0 +1,68 @@
+use crate::{player::Player, text};
+
+use self::session::TrustRoot;
+
+pub mod session;
+
+// the longest message a client is allowed to send
+pub const MAX_MESSAGE_LEN: usize = 256;
+
+// how chat from players goes out to the others
+#[derive(Debug, Clone)]
+pub enum ChatMode {
+    // unsigned, the clients still format it with the chat type like a player message
+    Disguised,
+    // formatted on the server and sent as a plain system message
+    System,
+    // vanilla secure chat, every message is signed with a key the trust root signed and relayed with its signature
+    Secure(TrustRoot)
+}
+
+// what a client asked to see in its chat, from the client information
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChatVisibility {
+    Full,
+    System,
+    Hidden
+}
+
+impl ChatVisibility {
+    pub fn of(player: &Player) -> Self {
+        match player.client_information.as_ref().map(|information| i32::from(information.chat_mode.clone())) {
+            Some(1) => Self::System,
+            Some(2) => Self::Hidden,
+            _ => Self::Full
+        }
+    }
+}
+
+// vanilla refuses the section sign and control characters in chat
+pub fn is_valid_message(message: &str) -> bool {
+    message.chars().count() <= MAX_MESSAGE_LEN && message.chars().all(|character| character != '§' && character >= ' ' && character != '\u{7f}')
+}
+
+// decides how names and messages are shown, every text it returns is a text component
+pub trait ChatFormatter: Send {
+    fn name(&self, player: &Player) -> String {
+        player.display_name.clone().unwrap_or_else(|| text::plain(&player.username))
+    }
+
+    // None drops the message, in secure chat anything but the plain message goes out as unsigned content next to the signed one
+    fn message(&self, _player: &Player, message: &str) -> Option<String> {
+        Some(text::plain(message))
+    }
+
+    // one of the chat types in the registry codec
+    fn chat_type(&self, _player: &Player) -> &str {
+        "minecraft:chat"
+    }
+
+    // the whole line for ChatMode::System, the way minecraft:chat shows it
+    fn line(&self, name: &str, message: &str) -> String {
+        text::translate("chat.type.text", &[name, message])
+    }
+}
+
+pub struct DefaultFormatter;
+
+impl ChatFormatter for DefaultFormatter {}
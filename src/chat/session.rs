@@ -0,0 +1,394 @@
+use std::{collections::VecDeque, fmt::{self, Display, Formatter}, sync::Arc, time::{SystemTime, UNIX_EPOCH}};
+
+use rsa::{pkcs8::DecodePublicKey, Pkcs1v15Sign, RsaPublicKey};
+use sha1::{Digest, Sha1};
+use sha2::Sha256;
+
+use crate::types::{ChatSession, UUID};
+
+// how many messages a client acknowledges at once
+pub const LAST_SEEN_WINDOW: usize = 20;
+// signed messages a client has not acknowledged yet, a client past that is not reading its chat
+const MAX_PENDING: usize = 4096;
+// the version of the message signature format
+const SIGNATURE_VERSION: i32 = 1;
+
+#[derive(Debug)]
+pub enum ChatError {
+    InvalidKey,
+    ExpiredKey,
+    InvalidKeySignature,
+    Unsigned,
+    InvalidSignature,
+    OutOfOrder,
+    InvalidAcknowledgements(&'static str),
+    TooManyPending
+}
+
+impl Display for ChatError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidKey => write!(f, "Invalid profile public key"),
+            Self::ExpiredKey => write!(f, "Chat disabled due to expired profile public key. Please try reconnecting."),
+            Self::InvalidKeySignature => write!(f, "Invalid signature for profile public key. Try restarting your game."),
+            Self::Unsigned => write!(f, "Received chat packet with missing or invalid signature."),
+            Self::InvalidSignature => write!(f, "Chat message validation failure"),
+            Self::OutOfOrder => write!(f, "Out-of-order chat packet received. Did your system time change?"),
+            Self::InvalidAcknowledgements(reason) => write!(f, "Chat message validation failure: {}", reason),
+            Self::TooManyPending => write!(f, "Too many unacknowledged chat messages")
+        }
+    }
+}
+
+// the key that signs the profile keys of players, Mojang's session key for vanilla clients
+#[derive(Clone)]
+pub struct TrustRoot(Arc<RsaPublicKey>);
+
+impl TrustRoot {
+    pub fn new(key: RsaPublicKey) -> Self {
+        Self(Arc::new(key))
+    }
+
+    // X.509 encoded, like the yggdrasil_session_pubkey.der that ships with the vanilla server
+    pub fn from_der(der: &[u8]) -> Result<Self, ChatError> {
+        RsaPublicKey::from_public_key_der(der).map(Self::new).map_err(|_| ChatError::InvalidKey)
+    }
+
+    pub fn from_pem(pem: &str) -> Result<Self, ChatError> {
+        RsaPublicKey::from_public_key_pem(pem).map(Self::new).map_err(|_| ChatError::InvalidKey)
+    }
+}
+
+impl fmt::Debug for TrustRoot {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("TrustRoot").finish_non_exhaustive()
+    }
+}
+
+fn now_millis() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as i64
+}
+
+// a chat session the trust root vouched for, and where the chain of messages signed with it is
+pub struct SignedSession {
+    pub data: ChatSession,
+    key: RsaPublicKey,
+    next_index: i32,
+    last_timestamp: i64
+}
+
+impl SignedSession {
+    pub fn validate(player: UUID, data: ChatSession, trust_root: &TrustRoot) -> Result<Self, ChatError> {
+        if data.expires_at < now_millis() {
+            return Err(ChatError::ExpiredKey)
+        }
+
+        let key = RsaPublicKey::from_public_key_der(&data.public_key).map_err(|_| ChatError::InvalidKey)?;
+
+        let mut payload = Vec::with_capacity(24 + data.public_key.len());
+        payload.extend_from_slice(&player.0.to_be_bytes());
+        payload.extend_from_slice(&data.expires_at.to_be_bytes());
+        payload.extend_from_slice(&data.public_key);
+
+        trust_root.0.verify(Pkcs1v15Sign::new::<Sha1>(), &Sha1::digest(&payload), &data.key_signature)
+            .map_err(|_| ChatError::InvalidKeySignature)?;
+
+        Ok(Self {
+            data,
+            key,
+            next_index: 0,
+            last_timestamp: i64::MIN
+        })
+    }
+
+    // checks the signature against the sender and everything the client said it saw, and returns the index of the message in the chain
+    pub fn verify(&mut self, sender: UUID, message: &str, timestamp: i64, salt: i64, signature: &[u8; 256], last_seen: &[[u8; 256]]) -> Result<i32, ChatError> {
+        if self.data.expires_at < now_millis() {
+            return Err(ChatError::ExpiredKey)
+        }
+
+        if timestamp < self.last_timestamp {
+            return Err(ChatError::OutOfOrder)
+        }
+
+        let index = self.next_index;
+
+        let mut payload = Vec::with_capacity(72 + message.len() + last_seen.len() * 256);
+        payload.extend_from_slice(&SIGNATURE_VERSION.to_be_bytes());
+        payload.extend_from_slice(&sender.0.to_be_bytes());
+        payload.extend_from_slice(&self.data.session_id.0.to_be_bytes());
+        payload.extend_from_slice(&index.to_be_bytes());
+        payload.extend_from_slice(&salt.to_be_bytes());
+        // signed in seconds, sent in milliseconds
+        payload.extend_from_slice(&timestamp.div_euclid(1000).to_be_bytes());
+        payload.extend_from_slice(&(message.len() as i32).to_be_bytes());
+        payload.extend_from_slice(message.as_bytes());
+        payload.extend_from_slice(&(last_seen.len() as i32).to_be_bytes());
+
+        for signature in last_seen {
+            payload.extend_from_slice(signature);
+        }
+
+        self.key.verify(Pkcs1v15Sign::new::<Sha256>(), &Sha256::digest(&payload), signature)
+            .map_err(|_| ChatError::InvalidSignature)?;
+
+        self.next_index += 1;
+        self.last_timestamp = timestamp;
+
+        Ok(index)
+    }
+}
+
+struct Tracked {
+    signature: [u8; 256],
+    // sent, but not acknowledged yet
+    pending: bool
+}
+
+// the signed messages sent to a player, which the client acknowledges with every message of its own
+pub struct LastSeenMessages {
+    tracked: VecDeque<Option<Tracked>>,
+    last_pending: Option<[u8; 256]>
+}
+
+impl Default for LastSeenMessages {
+    fn default() -> Self {
+        Self {
+            tracked: (0..LAST_SEEN_WINDOW).map(|_| None).collect(),
+            last_pending: None
+        }
+    }
+}
+
+impl LastSeenMessages {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_pending(&mut self, signature: [u8; 256]) -> Result<(), ChatError> {
+        if self.last_pending != Some(signature) {
+            self.tracked.push_back(Some(Tracked { signature, pending: true }));
+            self.last_pending = Some(signature);
+        }
+
+        if self.tracked.len() - LAST_SEEN_WINDOW > MAX_PENDING {
+            return Err(ChatError::TooManyPending)
+        }
+
+        Ok(())
+    }
+
+    // the client moved its window by that many messages
+    pub fn apply_offset(&mut self, offset: i32) -> Result<(), ChatError> {
+        let offset = usize::try_from(offset).map_err(|_| ChatError::InvalidAcknowledgements("negative offset"))?;
+
+        if offset > self.tracked.len() - LAST_SEEN_WINDOW {
+            return Err(ChatError::InvalidAcknowledgements("offset past the sent messages"))
+        }
+
+        self.tracked.drain(..offset);
+        Ok(())
+    }
+
+    // returns the signatures the client acknowledged, oldest first, which is the order it signed them in
+    pub fn apply_update(&mut self, offset: i32, acknowledged: [u8; 3]) -> Result<Vec<[u8; 256]>, ChatError> {
+        // the bitset has room for 24 messages, but the window only covers 20
+        if acknowledged[LAST_SEEN_WINDOW / 8] >> (LAST_SEEN_WINDOW % 8) != 0 {
+            return Err(ChatError::InvalidAcknowledgements("acknowledged past the last seen window"))
+        }
+
+        self.apply_offset(offset)?;
+
+        let mut last_seen = vec![];
+
+        for index in 0..LAST_SEEN_WINDOW {
+            let slot = &mut self.tracked[index];
+
+            if acknowledged[index / 8] >> (index % 8) & 1 == 1 {
+                let Some(tracked) = slot else {
+                    return Err(ChatError::InvalidAcknowledgements("acknowledged a message that was never sent"))
+                };
+
+                tracked.pending = false;
+                last_seen.push(tracked.signature);
+            } else {
+                if slot.as_ref().is_some_and(|tracked| !tracked.pending) {
+                    return Err(ChatError::InvalidAcknowledgements("dropped a message it acknowledged before"))
+                }
+
+                *slot = None;
+            }
+        }
+
+        Ok(last_seen)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rsa::{pkcs8::{DecodePrivateKey, EncodePublicKey}, RsaPrivateKey};
+
+    use super::*;
+
+    // generated for these tests only, they never signed anything outside of them
+    const TRUST_ROOT_KEY: &str = include_str!("testdata/trust_root.pem");
+    const PLAYER_KEY: &str = include_str!("testdata/player.pem");
+
+    const PLAYER: UUID = UUID(0x0123_4567_89AB_CDEF_0123_4567_89AB_CDEF);
+    const SESSION_ID: UUID = UUID(42);
+
+    fn private_key(pem: &str) -> RsaPrivateKey {
+        RsaPrivateKey::from_pkcs8_pem(pem).unwrap()
+    }
+
+    fn trust_root() -> TrustRoot {
+        TrustRoot::new(private_key(TRUST_ROOT_KEY).to_public_key())
+    }
+
+    // the player key as the trust root would hand it out, signed by `signer`
+    fn chat_session(expires_at: i64, signer: &str) -> ChatSession {
+        let public_key = private_key(PLAYER_KEY).to_public_key().to_public_key_der().unwrap().as_bytes().to_vec();
+
+        let mut payload = vec![];
+        payload.extend_from_slice(&PLAYER.0.to_be_bytes());
+        payload.extend_from_slice(&expires_at.to_be_bytes());
+        payload.extend_from_slice(&public_key);
+
+        let key_signature = private_key(signer).sign(Pkcs1v15Sign::new::<Sha1>(), &Sha1::digest(&payload)).unwrap();
+        ChatSession { session_id: SESSION_ID, expires_at, public_key, key_signature }
+    }
+
+    fn session() -> SignedSession {
+        SignedSession::validate(PLAYER, chat_session(now_millis() + 60_000, TRUST_ROOT_KEY), &trust_root()).unwrap()
+    }
+
+    // what the client signs for a message, in the same layout as the vanilla SignedMessageBody
+    fn sign(index: i32, message: &str, timestamp: i64, salt: i64, last_seen: &[[u8; 256]]) -> [u8; 256] {
+        let mut payload = vec![];
+        payload.extend_from_slice(&1i32.to_be_bytes());
+        payload.extend_from_slice(&PLAYER.0.to_be_bytes());
+        payload.extend_from_slice(&SESSION_ID.0.to_be_bytes());
+        payload.extend_from_slice(&index.to_be_bytes());
+        payload.extend_from_slice(&salt.to_be_bytes());
+        payload.extend_from_slice(&(timestamp / 1000).to_be_bytes());
+        payload.extend_from_slice(&(message.len() as i32).to_be_bytes());
+        payload.extend_from_slice(message.as_bytes());
+        payload.extend_from_slice(&(last_seen.len() as i32).to_be_bytes());
+
+        for signature in last_seen {
+            payload.extend_from_slice(signature);
+        }
+
+        private_key(PLAYER_KEY).sign(Pkcs1v15Sign::new::<Sha256>(), &Sha256::digest(&payload)).unwrap().try_into().unwrap()
+    }
+
+    #[test]
+    fn accepts_keys_signed_by_the_trust_root() {
+        let session = session();
+        assert_eq!(session.data.session_id, SESSION_ID);
+    }
+
+    #[test]
+    fn rejects_expired_keys() {
+        let result = SignedSession::validate(PLAYER, chat_session(now_millis() - 1, TRUST_ROOT_KEY), &trust_root());
+        assert!(matches!(result, Err(ChatError::ExpiredKey)));
+    }
+
+    #[test]
+    fn rejects_keys_signed_by_anyone_else() {
+        let result = SignedSession::validate(PLAYER, chat_session(now_millis() + 60_000, PLAYER_KEY), &trust_root());
+        assert!(matches!(result, Err(ChatError::InvalidKeySignature)));
+
+        // the signature covers the player, so a key is no good for anyone else
+        let result = SignedSession::validate(UUID(1), chat_session(now_millis() + 60_000, TRUST_ROOT_KEY), &trust_root());
+        assert!(matches!(result, Err(ChatError::InvalidKeySignature)));
+    }
+
+    #[test]
+    fn verifies_the_chain_of_messages() {
+        let mut session = session();
+        let timestamp = now_millis();
+        let first = sign(0, "hello", timestamp, 7, &[]);
+
+        assert_eq!(session.verify(PLAYER, "hello", timestamp, 7, &first, &[]).unwrap(), 0);
+
+        let second = sign(1, "again", timestamp + 1000, -3, &[first]);
+        assert_eq!(session.verify(PLAYER, "again", timestamp + 1000, -3, &second, &[first]).unwrap(), 1);
+    }
+
+    #[test]
+    fn rejects_tampered_messages() {
+        let mut session = session();
+        let timestamp = now_millis();
+        let signature = sign(0, "hello", timestamp, 7, &[]);
+
+        assert!(matches!(session.verify(PLAYER, "hellO", timestamp, 7, &signature, &[]), Err(ChatError::InvalidSignature)));
+        assert!(matches!(session.verify(PLAYER, "hello", timestamp, 8, &signature, &[]), Err(ChatError::InvalidSignature)));
+        assert!(matches!(session.verify(PLAYER, "hello", timestamp, 7, &signature, &[signature]), Err(ChatError::InvalidSignature)));
+
+        // a failed message does not move the chain along
+        assert_eq!(session.verify(PLAYER, "hello", timestamp, 7, &signature, &[]).unwrap(), 0);
+    }
+
+    #[test]
+    fn rejects_messages_out_of_order() {
+        let mut session = session();
+        let timestamp = now_millis();
+
+        session.verify(PLAYER, "later", timestamp, 0, &sign(0, "later", timestamp, 0, &[]), &[]).unwrap();
+
+        let earlier = sign(1, "earlier", timestamp - 1, 0, &[]);
+        assert!(matches!(session.verify(PLAYER, "earlier", timestamp - 1, 0, &earlier, &[]), Err(ChatError::OutOfOrder)));
+    }
+
+    // the last three of the twenty slots
+    const LAST_THREE: [u8; 3] = [0, 0, 0b1110];
+
+    fn last_seen(signatures: &[u8]) -> LastSeenMessages {
+        let mut last_seen = LastSeenMessages::new();
+
+        for signature in signatures {
+            last_seen.add_pending([*signature; 256]).unwrap();
+        }
+
+        last_seen
+    }
+
+    #[test]
+    fn acknowledges_oldest_first() {
+        let mut last_seen = last_seen(&[1, 2, 2, 3]);
+        assert_eq!(last_seen.apply_update(3, LAST_THREE).unwrap(), [[1; 256], [2; 256], [3; 256]]);
+
+        // the same window again, and then moved by one with nothing new
+        assert_eq!(last_seen.apply_update(0, LAST_THREE).unwrap(), [[1; 256], [2; 256], [3; 256]]);
+        assert!(matches!(last_seen.apply_offset(1), Err(ChatError::InvalidAcknowledgements(_))));
+    }
+
+    #[test]
+    fn rejects_bad_acknowledgements() {
+        let mut fresh = last_seen(&[]);
+        assert!(matches!(fresh.apply_update(0, [1, 0, 0]), Err(ChatError::InvalidAcknowledgements(_))));
+
+        let mut sent = last_seen(&[1, 2]);
+        assert!(matches!(sent.apply_offset(-1), Err(ChatError::InvalidAcknowledgements(_))));
+        assert!(matches!(sent.apply_offset(3), Err(ChatError::InvalidAcknowledgements(_))));
+
+        // bits 20 to 23 are outside the window, and are refused before the window moves
+        for bit in 4..8 {
+            assert!(matches!(sent.apply_update(2, [0, 0, 1 << bit]), Err(ChatError::InvalidAcknowledgements(_))));
+        }
+
+        // the window moved by two and the client took back the older of the two
+        sent.apply_update(2, [0, 0, 0b1100]).unwrap();
+        assert!(matches!(sent.apply_update(0, [0, 0, 0b1000]), Err(ChatError::InvalidAcknowledgements(_))));
+    }
+
+    #[test]
+    fn offsets_move_the_window() {
+        let mut last_seen = last_seen(&[1, 2, 3]);
+        last_seen.apply_offset(2).unwrap();
+
+        // the first of the three is the second to last slot now, the third one is still beyond the window
+        assert_eq!(last_seen.apply_update(1, [0, 0, 0b1100]).unwrap(), [[2; 256], [3; 256]]);
+    }
+}
@@ -0,0 +1,603 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{block::{Mirror, Rotation}, player::Player, types::{Pos, UUID}, world::World};
+
+use super::{reader::StringReader, CommandError, CommandSource};
+
+pub const GAMEMODES: [&str; 4] = ["survival", "creative", "adventure", "spectator"];
+pub const TEMPLATE_ROTATIONS: [(&str, Rotation); 4] = [
+    ("none", Rotation::None),
+    ("clockwise_90", Rotation::Clockwise90),
+    ("180", Rotation::Clockwise180),
+    ("counterclockwise_90", Rotation::Counterclockwise90)
+];
+pub const TEMPLATE_MIRRORS: [(&str, Mirror); 3] = [("none", Mirror::None), ("left_right", Mirror::LeftRight), ("front_back", Mirror::FrontBack)];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StringKind {
+    SingleWord,
+    // a single word, or anything in quotes
+    QuotablePhrase,
+    // the rest of the command
+    GreedyPhrase
+}
+
+// how an argument is read, the client gets the same so it can highlight the command as it is typed
+#[derive(Debug, Clone)]
+pub enum ArgumentParser {
+    Bool,
+    Float { min: Option<f32>, max: Option<f32> },
+    Double { min: Option<f64>, max: Option<f64> },
+    Integer { min: Option<i32>, max: Option<i32> },
+    Long { min: Option<i64>, max: Option<i64> },
+    String(StringKind),
+    Entity { single: bool, players_only: bool },
+    GameProfile,
+    BlockPos,
+    Vec3,
+    ItemStack,
+    Message,
+    Gamemode,
+    // in ticks, the units d, s and t are understood
+    Time { min: i32 },
+    ResourceLocation,
+    TemplateMirror,
+    TemplateRotation
+}
+
+#[derive(Debug, Clone)]
+pub enum Argument {
+    Bool(bool),
+    Float(f32),
+    Double(f64),
+    Integer(i32),
+    Long(i64),
+    String(String),
+    Entity(EntitySelector),
+    GameProfile(EntitySelector),
+    BlockPos(Coordinates),
+    Vec3(Coordinates),
+    // the item id, with the namespace
+    ItemStack(String),
+    Message(String),
+    Gamemode(u8),
+    Time(i32),
+    // with the namespace
+    ResourceLocation(String),
+    Mirror(Mirror),
+    Rotation(Rotation)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EntitySelector {
+    Name(String),
+    Uuid(UUID),
+    // @p
+    NearestPlayer,
+    // @a
+    AllPlayers,
+    // @r
+    RandomPlayer,
+    // @s
+    Executor,
+    // @e, players are the only entities there are
+    AllEntities
+}
+
+impl EntitySelector {
+    // the entity ids of the players it selects, nearest first where that matters
+    pub fn resolve(&self, world: &World, source: &CommandSource) -> Vec<i32> {
+        let (x, y, z) = source.position;
+
+        match self {
+            Self::Name(name) => world.players().filter(|player| player.username.eq_ignore_ascii_case(name)).map(|player| player.entity_id).collect(),
+            Self::Uuid(uuid) => world.players().filter(|player| player.uuid == *uuid).map(|player| player.entity_id).collect(),
+            Self::NearestPlayer => world.players()
+                .min_by(|a, b| {
+                    let distance = |player: &&Player| (player.x - x).powi(2) + (player.y - y).powi(2) + (player.z - z).powi(2);
+                    distance(a).total_cmp(&distance(b))
+                })
+                .map(|player| player.entity_id)
+                .into_iter()
+                .collect(),
+            Self::AllPlayers | Self::AllEntities => world.players().map(|player| player.entity_id).collect(),
+            Self::RandomPlayer => {
+                let players: Vec<i32> = world.players().map(|player| player.entity_id).collect();
+
+                // good enough for picking a player, there is no random source on the world
+                let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().subsec_nanos() as usize;
+                players.get(nanos % players.len().max(1)).copied().into_iter().collect()
+            }
+            Self::Executor => source.sender.entity_id().into_iter().collect()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Coordinate {
+    // relative to the position of the source, written with ~
+    pub relative: bool,
+    pub value: f64
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Coordinates {
+    World([Coordinate; 3]),
+    // left, up and forwards from where the source looks, written with ^
+    Local([f64; 3])
+}
+
+impl Coordinates {
+    pub fn resolve(&self, source: &CommandSource) -> (f64, f64, f64) {
+        let (x, y, z) = source.position;
+
+        match self {
+            Self::World([cx, cy, cz]) => {
+                let resolve = |coordinate: &Coordinate, origin: f64| coordinate.value + if coordinate.relative { origin } else { 0.0 };
+                (resolve(cx, x), resolve(cy, y), resolve(cz, z))
+            }
+            Self::Local([left, up, forwards]) => {
+                let (yaw, pitch) = ((source.rotation.0 + 90.0).to_radians() as f64, (-source.rotation.1).to_radians() as f64);
+                let up_pitch = (-source.rotation.1 + 90.0).to_radians() as f64;
+
+                let forward = (yaw.cos() * pitch.cos(), pitch.sin(), yaw.sin() * pitch.cos());
+                let upward = (yaw.cos() * up_pitch.cos(), up_pitch.sin(), yaw.sin() * up_pitch.cos());
+                // forward x upward points to the right
+                let leftward = (
+                    -(forward.1 * upward.2 - forward.2 * upward.1),
+                    -(forward.2 * upward.0 - forward.0 * upward.2),
+                    -(forward.0 * upward.1 - forward.1 * upward.0)
+                );
+
+                (
+                    x + forward.0 * forwards + upward.0 * up + leftward.0 * left,
+                    y + forward.1 * forwards + upward.1 * up + leftward.1 * left,
+                    z + forward.2 * forwards + upward.2 * up + leftward.2 * left
+                )
+            }
+        }
+    }
+
+    pub fn block(&self, source: &CommandSource) -> Pos {
+        let (x, y, z) = self.resolve(source);
+        Pos { x: x.floor() as i32, y: y.floor() as i16, z: z.floor() as i32 }
+    }
+}
+
+fn read_coordinates(reader: &mut StringReader, integer: bool, center: bool) -> Result<Coordinates, CommandError> {
+    let start = reader.cursor();
+
+    if reader.peek() == Some('^') {
+        let mut values = [0.0; 3];
+
+        for (index, value) in values.iter_mut().enumerate() {
+            if index > 0 {
+                expect_separator(reader, start)?;
+            }
+
+            if reader.peek() != Some('^') {
+                return Err(reader.error("Cannot mix world & local coordinates (everything must either use ^ or not)"))
+            }
+
+            reader.skip();
+
+            if reader.peek().is_some_and(|character| character != ' ') {
+                *value = reader.read_double()?;
+            }
+        }
+
+        return Ok(Coordinates::Local(values))
+    }
+
+    let mut coordinates = [Coordinate { relative: false, value: 0.0 }; 3];
+
+    for (index, coordinate) in coordinates.iter_mut().enumerate() {
+        if index > 0 {
+            expect_separator(reader, start)?;
+        }
+
+        match reader.peek() {
+            Some('^') => return Err(reader.error("Cannot mix world & local coordinates (everything must either use ^ or not)")),
+            Some('~') => {
+                reader.skip();
+                coordinate.relative = true;
+
+                if reader.peek().is_some_and(|character| character != ' ') {
+                    coordinate.value = reader.read_double()?;
+                }
+            }
+            Some(_) if integer => coordinate.value = reader.read_int()? as f64,
+            Some(_) => {
+                let number_start = reader.cursor();
+                coordinate.value = reader.read_double()?;
+
+                // whole numbers point at the middle of the block, except for the height
+                if center && index != 1 && !reader.input()[number_start..reader.cursor()].contains('.') {
+                    coordinate.value += 0.5;
+                }
+            }
+            None => {
+                reader.set_cursor(start);
+                return Err(reader.error("Incomplete (expected 3 coordinates)"))
+            }
+        }
+    }
+
+    Ok(Coordinates::World(coordinates))
+}
+
+fn expect_separator(reader: &mut StringReader, start: usize) -> Result<(), CommandError> {
+    if reader.peek() != Some(' ') {
+        reader.set_cursor(start);
+        return Err(reader.error("Incomplete (expected 3 coordinates)"))
+    }
+
+    reader.skip();
+    Ok(())
+}
+
+fn read_uuid(text: &str) -> Option<UUID> {
+    let parts: Vec<&str> = text.split('-').collect();
+
+    if parts.len() != 5 {
+        return None
+    }
+
+    u128::from_str_radix(&parts.concat(), 16).ok().filter(|_| parts.concat().len() == 32).map(UUID)
+}
+
+fn read_selector(reader: &mut StringReader, single: bool, players_only: bool) -> Result<EntitySelector, CommandError> {
+    let start = reader.cursor();
+
+    if reader.peek() == Some('@') {
+        reader.skip();
+
+        let selector = match reader.peek() {
+            Some('p') => EntitySelector::NearestPlayer,
+            Some('a') => EntitySelector::AllPlayers,
+            Some('r') => EntitySelector::RandomPlayer,
+            Some('s') => EntitySelector::Executor,
+            Some('e') => EntitySelector::AllEntities,
+            _ => return Err(reader.error("Unknown selector type"))
+        };
+
+        reader.skip();
+
+        if reader.peek() == Some('[') {
+            return Err(reader.error("Selector arguments are not supported"))
+        }
+
+        if single && matches!(selector, EntitySelector::AllPlayers | EntitySelector::AllEntities) {
+            reader.set_cursor(start);
+            return Err(reader.error(match players_only {
+                true => "Only one player is allowed, but the provided selector allows more than one",
+                false => "Only one entity is allowed, but the provided selector allows more than one"
+            }))
+        }
+
+        if players_only && selector == EntitySelector::AllEntities {
+            reader.set_cursor(start);
+            return Err(reader.error("Only players may be affected by this command, but the provided selector includes entities"))
+        }
+
+        return Ok(selector)
+    }
+
+    let name = reader.read_while(|character| character != ' ');
+
+    if let Some(uuid) = read_uuid(name) {
+        return Ok(EntitySelector::Uuid(uuid))
+    }
+
+    if name.is_empty() || name.chars().count() > 16 {
+        reader.set_cursor(start);
+        return Err(reader.error("Invalid name or UUID"))
+    }
+
+    Ok(EntitySelector::Name(name.to_string()))
+}
+
+fn check_range<T: PartialOrd + std::fmt::Display>(reader: &mut StringReader, start: usize, value: T, min: Option<T>, max: Option<T>, kind: &str) -> Result<T, CommandError> {
+    if let Some(min) = min.filter(|min| value < *min) {
+        reader.set_cursor(start);
+        return Err(reader.error(format!("{} must not be less than {}, found {}", kind, min, value)))
+    }
+
+    if let Some(max) = max.filter(|max| value > *max) {
+        reader.set_cursor(start);
+        return Err(reader.error(format!("{} must not be more than {}, found {}", kind, max, value)))
+    }
+
+    Ok(value)
+}
+
+fn is_resource_character(character: char) -> bool {
+    character.is_ascii_lowercase() || character.is_ascii_digit() || matches!(character, '_' | '-' | '.' | '/' | ':')
+}
+
+// one of the names, as vanilla's string representable arguments read them
+fn read_enum<T: Copy>(reader: &mut StringReader, values: &[(&str, T)]) -> Result<T, CommandError> {
+    let start = reader.cursor();
+    let name = reader.read_unquoted();
+
+    match values.iter().find(|(candidate, _)| *candidate == name) {
+        Some((_, value)) => Ok(*value),
+        None => {
+            reader.set_cursor(start);
+            Err(reader.error(format!("Invalid value \"{}\"", name)))
+        }
+    }
+}
+
+impl ArgumentParser {
+    // the registry id of the argument type in 1.19.4
+    pub fn id(&self) -> i32 {
+        match self {
+            Self::Bool => 0,
+            Self::Float { .. } => 1,
+            Self::Double { .. } => 2,
+            Self::Integer { .. } => 3,
+            Self::Long { .. } => 4,
+            Self::String(_) => 5,
+            Self::Entity { .. } => 6,
+            Self::GameProfile => 7,
+            Self::BlockPos => 8,
+            Self::Vec3 => 10,
+            Self::ItemStack => 14,
+            Self::Message => 18,
+            Self::ResourceLocation => 33,
+            Self::Gamemode => 39,
+            Self::Time { .. } => 40,
+            Self::TemplateMirror => 45,
+            Self::TemplateRotation => 46
+        }
+    }
+
+    pub fn parse(&self, reader: &mut StringReader) -> Result<Argument, CommandError> {
+        let start = reader.cursor();
+
+        Ok(match self {
+            Self::Bool => Argument::Bool(reader.read_bool()?),
+            Self::Float { min, max } => {
+                let value = reader.read_float()?;
+                Argument::Float(check_range(reader, start, value, *min, *max, "Float")?)
+            }
+            Self::Double { min, max } => {
+                let value = reader.read_double()?;
+                Argument::Double(check_range(reader, start, value, *min, *max, "Double")?)
+            }
+            Self::Integer { min, max } => {
+                let value = reader.read_int()?;
+                Argument::Integer(check_range(reader, start, value, *min, *max, "Integer")?)
+            }
+            Self::Long { min, max } => {
+                let value = reader.read_long()?;
+                Argument::Long(check_range(reader, start, value, *min, *max, "Long")?)
+            }
+            Self::String(StringKind::SingleWord) => Argument::String(reader.read_unquoted().to_string()),
+            Self::String(StringKind::QuotablePhrase) => Argument::String(reader.read_string()?),
+            Self::String(StringKind::GreedyPhrase) | Self::Message => {
+                let text = reader.remaining().to_string();
+                reader.set_cursor(reader.input().len());
+
+                match self {
+                    Self::Message => Argument::Message(text),
+                    _ => Argument::String(text)
+                }
+            }
+            Self::Entity { single, players_only } => Argument::Entity(read_selector(reader, *single, *players_only)?),
+            Self::GameProfile => Argument::GameProfile(read_selector(reader, false, true)?),
+            Self::BlockPos => Argument::BlockPos(read_coordinates(reader, true, false)?),
+            Self::Vec3 => Argument::Vec3(read_coordinates(reader, false, true)?),
+            Self::ItemStack => {
+                let id = reader.read_while(is_resource_character);
+
+                if id.is_empty() {
+                    return Err(reader.error("Expected item"))
+                }
+
+                if reader.peek() == Some('{') {
+                    return Err(reader.error("Item NBT is not supported"))
+                }
+
+                Argument::ItemStack(match id.contains(':') {
+                    true => id.to_string(),
+                    false => format!("minecraft:{}", id)
+                })
+            }
+            Self::Gamemode => {
+                let name = reader.read_unquoted();
+
+                match GAMEMODES.iter().position(|gamemode| *gamemode == name) {
+                    Some(gamemode) => Argument::Gamemode(gamemode as u8),
+                    None => {
+                        reader.set_cursor(start);
+                        return Err(reader.error(format!("Unknown game mode: {}", name)))
+                    }
+                }
+            }
+            Self::Time { min } => {
+                let value = reader.read_float()?;
+
+                let multiplier = match reader.peek() {
+                    Some('d') => 24000.0,
+                    Some('s') => 20.0,
+                    Some('t') => 1.0,
+                    Some(' ') | None => 1.0,
+                    Some(_) => return Err(reader.error("Invalid unit"))
+                };
+
+                if reader.peek().is_some_and(|character| character != ' ') {
+                    reader.skip();
+                }
+
+                let ticks = (value * multiplier).round() as i32;
+                Argument::Time(check_range(reader, start, ticks, Some(*min), None, "Tick count")?)
+            }
+            Self::ResourceLocation => {
+                let id = reader.read_while(is_resource_character);
+
+                match id.split_once(':') {
+                    _ if id.is_empty() => return Err(reader.error("Invalid ID")),
+                    Some((_, path)) if path.contains(':') => {
+                        reader.set_cursor(start);
+                        return Err(reader.error("Invalid ID"))
+                    }
+                    Some(_) => Argument::ResourceLocation(id.to_string()),
+                    None => Argument::ResourceLocation(format!("minecraft:{}", id))
+                }
+            }
+            Self::TemplateMirror => Argument::Mirror(read_enum(reader, &TEMPLATE_MIRRORS)?),
+            Self::TemplateRotation => Argument::Rotation(read_enum(reader, &TEMPLATE_ROTATIONS)?)
+        })
+    }
+
+    // what fits at this argument, before it is filtered by what was already typed
+    pub fn suggestions(&self, world: &World) -> Vec<String> {
+        let players = || world.players().map(|player| player.username.clone());
+
+        match self {
+            Self::Bool => vec!["true".to_string(), "false".to_string()],
+            Self::Entity { single, players_only } => {
+                let selectors: &[&str] = match (single, players_only) {
+                    (true, _) => &["@p", "@r", "@s"],
+                    (false, true) => &["@a", "@p", "@r", "@s"],
+                    (false, false) => &["@a", "@e", "@p", "@r", "@s"]
+                };
+
+                players().chain(selectors.iter().map(|selector| selector.to_string())).collect()
+            }
+            Self::GameProfile => players().chain(["@a", "@p", "@r", "@s"].map(String::from)).collect(),
+            Self::BlockPos | Self::Vec3 => vec!["~ ~ ~".to_string()],
+            Self::Gamemode => GAMEMODES.map(String::from).to_vec(),
+            Self::TemplateMirror => TEMPLATE_MIRRORS.map(|(name, _)| name.to_string()).to_vec(),
+            Self::TemplateRotation => TEMPLATE_ROTATIONS.map(|(name, _)| name.to_string()).to_vec(),
+            _ => vec![]
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the argument and how far it read
+    fn parse(parser: ArgumentParser, input: &str) -> Result<(Argument, usize), CommandError> {
+        let mut reader = StringReader::new(input);
+        parser.parse(&mut reader).map(|argument| (argument, reader.cursor()))
+    }
+
+    fn error(parser: ArgumentParser, input: &str) -> (String, Option<usize>) {
+        let error = parse(parser, input).unwrap_err();
+        let cursor = error.cursor();
+        (error.message, cursor)
+    }
+
+    fn source() -> CommandSource {
+        CommandSource {
+            sender: super::super::CommandSender::Console,
+            name: "Server".to_string(),
+            permission_level: 4,
+            position: (10.0, 64.0, -20.0),
+            rotation: (0.0, 0.0)
+        }
+    }
+
+    fn coordinates(parser: ArgumentParser, input: &str) -> Coordinates {
+        match parse(parser, input).unwrap().0 {
+            Argument::BlockPos(coordinates) | Argument::Vec3(coordinates) => coordinates,
+            argument => panic!("not coordinates: {:?}", argument)
+        }
+    }
+
+    #[test]
+    fn numbers_stay_in_range() {
+        let integer = || ArgumentParser::Integer { min: Some(1), max: Some(64) };
+
+        assert!(matches!(parse(integer(), "12 rest").unwrap(), (Argument::Integer(12), 2)));
+        assert_eq!(error(integer(), "65"), ("Integer must not be more than 64, found 65".to_string(), Some(0)));
+        assert_eq!(error(integer(), "0"), ("Integer must not be less than 1, found 0".to_string(), Some(0)));
+        assert_eq!(error(integer(), "1.5"), ("Invalid integer '1.5'".to_string(), Some(0)));
+        assert_eq!(error(integer(), "x"), ("Expected integer".to_string(), Some(0)));
+
+        assert!(matches!(parse(ArgumentParser::Double { min: None, max: None }, "-2.5").unwrap(), (Argument::Double(value), 4) if value == -2.5));
+        assert!(matches!(parse(ArgumentParser::Long { min: None, max: None }, "-9000000000").unwrap(), (Argument::Long(-9000000000), 11)));
+    }
+
+    #[test]
+    fn reads_bools_and_strings() {
+        assert!(matches!(parse(ArgumentParser::Bool, "false").unwrap(), (Argument::Bool(false), 5)));
+        assert_eq!(error(ArgumentParser::Bool, "yes"), ("Invalid bool, expected true or false but found 'yes'".to_string(), Some(0)));
+
+        let word = parse(ArgumentParser::String(StringKind::SingleWord), "one two").unwrap();
+        assert!(matches!(word, (Argument::String(value), 3) if value == "one"));
+
+        let quoted = parse(ArgumentParser::String(StringKind::QuotablePhrase), "\"one \\\"two\\\"\" three").unwrap();
+        assert!(matches!(quoted, (Argument::String(value), 13) if value == "one \"two\""));
+
+        let greedy = parse(ArgumentParser::String(StringKind::GreedyPhrase), "one two").unwrap();
+        assert!(matches!(greedy, (Argument::String(value), 7) if value == "one two"));
+
+        let message = parse(ArgumentParser::Message, "hi there").unwrap();
+        assert!(matches!(message, (Argument::Message(value), 8) if value == "hi there"));
+    }
+
+    #[test]
+    fn reads_selectors() {
+        let entity = |single, players_only| ArgumentParser::Entity { single, players_only };
+        let selector = |parser, input| match parse(parser, input).unwrap().0 {
+            Argument::Entity(selector) | Argument::GameProfile(selector) => selector,
+            argument => panic!("not a selector: {:?}", argument)
+        };
+
+        assert_eq!(selector(entity(false, false), "@e"), EntitySelector::AllEntities);
+        assert_eq!(selector(entity(true, true), "@s"), EntitySelector::Executor);
+        assert_eq!(selector(entity(true, true), "Notch"), EntitySelector::Name("Notch".to_string()));
+        assert_eq!(selector(entity(true, true), "069a79f4-44e9-4726-a5be-fca90e38aaf5"), EntitySelector::Uuid(UUID(0x069a79f444e94726a5befca90e38aaf5)));
+        assert_eq!(selector(ArgumentParser::GameProfile, "@a"), EntitySelector::AllPlayers);
+
+        assert_eq!(error(entity(true, true), "@a").0, "Only one player is allowed, but the provided selector allows more than one");
+        assert_eq!(error(entity(false, true), "@e").0, "Only players may be affected by this command, but the provided selector includes entities");
+        assert_eq!(error(entity(false, false), "@x"), ("Unknown selector type".to_string(), Some(1)));
+        assert_eq!(error(entity(false, false), "@a[limit=1]").0, "Selector arguments are not supported");
+        assert_eq!(error(entity(false, false), "a_name_much_too_long").0, "Invalid name or UUID");
+    }
+
+    #[test]
+    fn reads_coordinates() {
+        let source = source();
+
+        // whole numbers are the middle of the block for positions, but not for the height
+        assert_eq!(coordinates(ArgumentParser::Vec3, "1 2 3").resolve(&source), (1.5, 2.0, 3.5));
+        assert_eq!(coordinates(ArgumentParser::Vec3, "1.0 2 -3.25").resolve(&source), (1.0, 2.0, -3.25));
+        assert_eq!(coordinates(ArgumentParser::Vec3, "~ ~1 ~-0.5").resolve(&source), (10.0, 65.0, -20.5));
+        assert_eq!(coordinates(ArgumentParser::BlockPos, "~1 70 ~").block(&source), Pos { x: 11, y: 70, z: -20 });
+
+        // looking along +z with no rotation, forwards is +z and left is +x, the rotation is only f32 precise
+        let (x, y, z) = coordinates(ArgumentParser::Vec3, "^1 ^ ^2").resolve(&source);
+        assert!((x - 11.0).abs() < 1e-6 && (y - 64.0).abs() < 1e-6 && (z + 18.0).abs() < 1e-6);
+
+        assert_eq!(error(ArgumentParser::Vec3, "1 2").0, "Incomplete (expected 3 coordinates)");
+        assert_eq!(error(ArgumentParser::Vec3, "~ ^ ~").0, "Cannot mix world & local coordinates (everything must either use ^ or not)");
+        assert_eq!(error(ArgumentParser::BlockPos, "1.5 2 3").0, "Invalid integer '1.5'");
+    }
+
+    #[test]
+    fn reads_items_gamemodes_and_time() {
+        assert!(matches!(parse(ArgumentParser::ItemStack, "stone 5").unwrap(), (Argument::ItemStack(id), 5) if id == "minecraft:stone"));
+        assert!(matches!(parse(ArgumentParser::ItemStack, "mod:thing").unwrap(), (Argument::ItemStack(id), 9) if id == "mod:thing"));
+        assert_eq!(error(ArgumentParser::ItemStack, "stone{Damage:1}").0, "Item NBT is not supported");
+
+        assert!(matches!(parse(ArgumentParser::Gamemode, "spectator").unwrap(), (Argument::Gamemode(3), 9)));
+        assert_eq!(error(ArgumentParser::Gamemode, "hardcore"), ("Unknown game mode: hardcore".to_string(), Some(0)));
+
+        let time = |input| match parse(ArgumentParser::Time { min: 0 }, input).unwrap() {
+            (Argument::Time(ticks), _) => ticks,
+            (argument, _) => panic!("not a time: {:?}", argument)
+        };
+
+        assert_eq!([time("1d"), time("0.5d"), time("2s"), time("5t"), time("5")], [24000, 12000, 40, 5, 5]);
+        assert_eq!(error(ArgumentParser::Time { min: 0 }, "1x").0, "Invalid unit");
+        assert_eq!(error(ArgumentParser::Time { min: 0 }, "-1").0, "Tick count must not be less than 0, found -1");
+    }
+}
@@ -0,0 +1,742 @@
+use std::io;
+
+use crate::info;
+
+use crate::{text, types::Pos, world::{level::{Weather, GAME_RULES}, structure::PlacementSettings, World}};
+
+use super::{argument, argument::{ArgumentParser, EntitySelector, GAMEMODES}, literal, CommandContext, CommandDispatcher, CommandError, CommandResult, CommandSender, NodeBuilder, MAX_PERMISSION_LEVEL};
+
+const DIFFICULTIES: [&str; 4] = ["peaceful", "easy", "normal", "hard"];
+// in ticks, for /weather without a duration
+const DEFAULT_WEATHER_DURATION: i32 = 6000;
+const MAX_GIVE_COUNT: i32 = 6400;
+
+// the commands every server has, the ones that change the world need operators
+pub fn register(dispatcher: &mut CommandDispatcher) {
+    dispatcher.register(gamemode());
+    dispatcher.register(teleport());
+    dispatcher.register(literal("tp").requires(2).redirect("teleport"));
+    dispatcher.register(give());
+    dispatcher.register(time());
+    dispatcher.register(weather());
+    dispatcher.register(kick());
+    dispatcher.register(ban());
+    dispatcher.register(pardon());
+    dispatcher.register(op());
+    dispatcher.register(deop());
+    dispatcher.register(whitelist());
+    dispatcher.register(list());
+    dispatcher.register(say());
+    dispatcher.register(msg());
+    dispatcher.register(literal("tell").redirect("msg"));
+    dispatcher.register(literal("w").redirect("msg"));
+    dispatcher.register(stop());
+    dispatcher.register(difficulty());
+    dispatcher.register(gamerule());
+    dispatcher.register(setworldspawn());
+    dispatcher.register(seed());
+    dispatcher.register(place());
+}
+
+fn players(single: bool) -> ArgumentParser {
+    ArgumentParser::Entity { single, players_only: true }
+}
+
+fn entities(single: bool) -> ArgumentParser {
+    ArgumentParser::Entity { single, players_only: false }
+}
+
+// whoever runs the command, for the forms without a target
+fn executor(context: &CommandContext) -> Result<i32, CommandError> {
+    context.source.sender.entity_id().ok_or_else(|| CommandError::new("A player is required to run this command here"))
+}
+
+// the entity ids the argument selects, never none
+fn targets(world: &World, context: &CommandContext, argument: &str) -> Result<Vec<i32>, CommandError> {
+    let targets = context.selector(argument)?.resolve(world, &context.source);
+
+    match targets.is_empty() {
+        true => Err(CommandError::new("No player was found")),
+        false => Ok(targets)
+    }
+}
+
+// for the commands that work on players who are not online, a plain name is taken as it is then
+fn profiles(world: &World, context: &CommandContext, argument: &str) -> Result<Vec<String>, CommandError> {
+    let names: Vec<String> = match context.selector(argument)? {
+        EntitySelector::Name(name) if world.player_by_name(name).is_none() => vec![name.clone()],
+        selector => selector.resolve(world, &context.source).into_iter().map(|entity_id| name(world, entity_id)).collect()
+    };
+
+    match names.is_empty() {
+        true => Err(CommandError::new("Unknown player")),
+        false => Ok(names)
+    }
+}
+
+fn name(world: &World, entity_id: i32) -> String {
+    world.player(entity_id).map_or_else(String::new, |player| player.username.clone())
+}
+
+// the name for a single player, how many there are otherwise
+fn describe(world: &World, targets: &[i32]) -> String {
+    match targets {
+        [entity_id] => name(world, *entity_id),
+        _ => format!("{} players", targets.len())
+    }
+}
+
+fn capitalize(name: &str) -> String {
+    let mut characters = name.chars();
+    characters.next().map_or_else(String::new, |first| first.to_uppercase().chain(characters).collect())
+}
+
+fn gamemode() -> NodeBuilder {
+    literal("gamemode").requires(2)
+        .then(argument("gamemode", ArgumentParser::Gamemode)
+            .executes(|world, context| {
+                let target = executor(context)?;
+                set_gamemode(world, context, &[target])
+            })
+            .then(argument("target", players(false))
+                .executes(|world, context| {
+                    let targets = targets(world, context, "target")?;
+                    set_gamemode(world, context, &targets)
+                })))
+}
+
+fn set_gamemode(world: &mut World, context: &CommandContext, targets: &[i32]) -> CommandResult {
+    let gamemode = context.gamemode("gamemode")?;
+    let mode = capitalize(GAMEMODES[gamemode as usize]);
+
+    for entity_id in targets.iter().copied() {
+        world.set_gamemode(entity_id, gamemode);
+
+        if context.source.sender.entity_id() == Some(entity_id) {
+            world.reply(context.source.sender, &format!("Set own game mode to {} Mode", mode));
+        } else {
+            world.send_system_message(entity_id, &text::plain(&format!("Your game mode has been updated to {} Mode", mode)));
+            world.reply(context.source.sender, &format!("Set {}'s game mode to {} Mode", name(world, entity_id), mode));
+        }
+    }
+
+    Ok(targets.len() as i32)
+}
+
+// vanilla has the same four forms, relative coordinates start from whoever runs the command and not the targets
+fn teleport() -> NodeBuilder {
+    literal("teleport").requires(2)
+        .then(argument("location", ArgumentParser::Vec3)
+            .executes(|world, context| {
+                let target = executor(context)?;
+                teleport_to_location(world, context, &[target])
+            }))
+        .then(argument("destination", entities(true))
+            .executes(|world, context| {
+                let target = executor(context)?;
+                teleport_to_entity(world, context, &[target])
+            }))
+        .then(argument("targets", entities(false))
+            .then(argument("location", ArgumentParser::Vec3)
+                .executes(|world, context| {
+                    let targets = targets(world, context, "targets")?;
+                    teleport_to_location(world, context, &targets)
+                }))
+            .then(argument("destination", entities(true))
+                .executes(|world, context| {
+                    let targets = targets(world, context, "targets")?;
+                    teleport_to_entity(world, context, &targets)
+                })))
+}
+
+fn teleport_to_location(world: &mut World, context: &CommandContext, targets: &[i32]) -> CommandResult {
+    let (x, y, z) = context.coordinates("location")?.resolve(&context.source);
+
+    for entity_id in targets.iter().copied() {
+        let Some((yaw, pitch)) = world.player(entity_id).map(|player| (player.yaw, player.pitch)) else {
+            continue
+        };
+
+        world.teleport(entity_id, x, y, z, yaw, pitch);
+    }
+
+    world.reply(context.source.sender, &format!("Teleported {} to {:.2}, {:.2}, {:.2}", describe(world, targets), x, y, z));
+    Ok(targets.len() as i32)
+}
+
+fn teleport_to_entity(world: &mut World, context: &CommandContext, targets: &[i32]) -> CommandResult {
+    let destination = self::targets(world, context, "destination")?[0];
+    let player = world.player(destination).unwrap();
+    let (x, y, z, yaw, pitch) = (player.x, player.y, player.z, player.yaw, player.pitch);
+
+    for entity_id in targets.iter().copied() {
+        world.teleport(entity_id, x, y, z, yaw, pitch);
+    }
+
+    world.reply(context.source.sender, &format!("Teleported {} to {}", describe(world, targets), name(world, destination)));
+    Ok(targets.len() as i32)
+}
+
+fn give() -> NodeBuilder {
+    literal("give").requires(2)
+        .then(argument("targets", players(false))
+            .then(argument("item", ArgumentParser::ItemStack)
+                .executes(|world, context| give_item(world, context, 1))
+                .then(argument("count", ArgumentParser::Integer { min: Some(1), max: Some(MAX_GIVE_COUNT) })
+                    .executes(|world, context| {
+                        let count = context.integer("count")?;
+                        give_item(world, context, count)
+                    }))))
+}
+
+// FIXME: vanilla drops what does not fit, without item entities it is lost
+fn give_item(world: &mut World, context: &CommandContext, count: i32) -> CommandResult {
+    let targets = targets(world, context, "targets")?;
+    let item = context.string("item")?;
+
+    for entity_id in targets.iter().copied() {
+        world.give(entity_id, item, count as u32);
+    }
+
+    world.reply(context.source.sender, &format!("Gave {} [{}] to {}", count, item, describe(world, &targets)));
+    Ok(targets.len() as i32)
+}
+
+fn time() -> NodeBuilder {
+    let mut set = literal("set");
+
+    for (name, time) in [("day", 1000), ("noon", 6000), ("night", 13000), ("midnight", 18000)] {
+        set = set.then(literal(name).executes(move |world, context| set_time(world, context, time)));
+    }
+
+    let query = |name: &str, query: fn(&World) -> i64| literal(name).executes(move |world, context| {
+        let time = query(world);
+        world.reply(context.source.sender, &format!("The time is {}", time));
+        Ok(time as i32)
+    });
+
+    literal("time").requires(2)
+        .then(set
+            .then(argument("time", ArgumentParser::Time { min: 0 })
+                .executes(|world, context| {
+                    let time = context.time("time")?;
+                    set_time(world, context, time as i64)
+                })))
+        .then(literal("add")
+            .then(argument("time", ArgumentParser::Time { min: 0 })
+                .executes(|world, context| {
+                    let time = world.level().day_time + context.time("time")? as i64;
+                    set_time(world, context, time)
+                })))
+        .then(literal("query")
+            .then(query("daytime", |world| world.level().day_time % 24000))
+            .then(query("gametime", |world| world.level().time % i32::MAX as i64))
+            .then(query("day", |world| world.level().day_time / 24000 % i32::MAX as i64)))
+}
+
+fn set_time(world: &mut World, context: &CommandContext, time: i64) -> CommandResult {
+    world.set_day_time(time);
+    world.reply(context.source.sender, &format!("Set the time to {}", time));
+    Ok((time % 24000) as i32)
+}
+
+fn weather() -> NodeBuilder {
+    let mut command = literal("weather").requires(2);
+
+    for (name, weather) in [("clear", Weather::Clear), ("rain", Weather::Rain), ("thunder", Weather::Thunder)] {
+        command = command.then(literal(name)
+            .executes(move |world, context| set_weather(world, context, weather, DEFAULT_WEATHER_DURATION))
+            // in seconds
+            .then(argument("duration", ArgumentParser::Integer { min: Some(0), max: Some(1_000_000) })
+                .executes(move |world, context| {
+                    let duration = context.integer("duration")?;
+                    set_weather(world, context, weather, duration * 20)
+                })));
+    }
+
+    command
+}
+
+fn set_weather(world: &mut World, context: &CommandContext, weather: Weather, duration: i32) -> CommandResult {
+    world.set_weather(weather, duration);
+
+    let name = match weather {
+        Weather::Clear => "clear",
+        Weather::Rain => "rain",
+        Weather::Thunder => "rain & thunder"
+    };
+
+    world.reply(context.source.sender, &format!("Set the weather to {}", name));
+    Ok(duration)
+}
+
+fn kick() -> NodeBuilder {
+    literal("kick").requires(3)
+        .then(argument("targets", players(false))
+            .executes(|world, context| kick_players(world, context, "Kicked by an operator"))
+            .then(argument("reason", ArgumentParser::Message)
+                .executes(|world, context| kick_players(world, context, context.string("reason")?))))
+}
+
+fn kick_players(world: &mut World, context: &CommandContext, reason: &str) -> CommandResult {
+    let targets = targets(world, context, "targets")?;
+
+    for entity_id in targets.iter().copied() {
+        let name = name(world, entity_id);
+        world.kick(entity_id, reason);
+        world.reply(context.source.sender, &format!("Kicked {}: {}", name, reason));
+    }
+
+    Ok(targets.len() as i32)
+}
+
+fn ban() -> NodeBuilder {
+    literal("ban").requires(3)
+        .then(argument("targets", ArgumentParser::GameProfile)
+            .executes(|world, context| ban_players(world, context, "Banned by an operator"))
+            .then(argument("reason", ArgumentParser::Message)
+                .executes(|world, context| ban_players(world, context, context.string("reason")?))))
+}
+
+fn ban_players(world: &mut World, context: &CommandContext, reason: &str) -> CommandResult {
+    let ban = |world: &mut World, name: &str| world.ban(name, reason);
+    for_profiles(world, context, ban, |name| format!("Banned {}: {}", name, reason), "Nothing changed. The player is already banned")
+}
+
+// runs the change for every name it selects, and fails when nothing changed for any of them
+fn for_profiles(world: &mut World, context: &CommandContext, change: impl Fn(&mut World, &str) -> bool, success: impl Fn(&str) -> String, unchanged: &str) -> CommandResult {
+    let mut changed = 0;
+
+    for name in profiles(world, context, "targets")? {
+        if change(world, &name) {
+            world.reply(context.source.sender, &success(&name));
+            changed += 1;
+        }
+    }
+
+    match changed {
+        0 => Err(CommandError::new(unchanged)),
+        changed => Ok(changed)
+    }
+}
+
+fn pardon() -> NodeBuilder {
+    literal("pardon").requires(3)
+        .then(argument("targets", ArgumentParser::GameProfile)
+            .suggests(|world, _| world.access().bans().map(|ban| ban.name.clone()).collect())
+            .executes(|world, context| {
+                for_profiles(world, context, World::pardon, |name| format!("Unbanned {}", name), "Nothing changed. The player isn't banned")
+            }))
+}
+
+fn op() -> NodeBuilder {
+    literal("op").requires(3)
+        .then(argument("targets", ArgumentParser::GameProfile)
+            .suggests(|world, _| world.players()
+                .filter(|player| world.access().operator(&player.username).is_none())
+                .map(|player| player.username.clone())
+                .collect())
+            .executes(|world, context| {
+                let op = |world: &mut World, name: &str| world.access().operator(name).is_none() && world.op(name, MAX_PERMISSION_LEVEL);
+                for_profiles(world, context, op, |name| format!("Made {} a server operator", name), "Nothing changed. The player already is an operator")
+            }))
+}
+
+fn deop() -> NodeBuilder {
+    literal("deop").requires(3)
+        .then(argument("targets", ArgumentParser::GameProfile)
+            .suggests(|world, _| world.access().operators().map(|operator| operator.name.clone()).collect())
+            .executes(|world, context| {
+                for_profiles(world, context, World::deop, |name| format!("Made {} no longer a server operator", name), "Nothing changed. The player is not an operator")
+            }))
+}
+
+fn whitelist() -> NodeBuilder {
+    let toggle = |name: &str, enabled: bool| literal(name).executes(move |world, context| {
+        let state = if enabled { "on" } else { "off" };
+
+        if world.access().whitelist_enabled == enabled {
+            return Err(CommandError::new(format!("Whitelist is already turned {}", state)))
+        }
+
+        world.access_mut().whitelist_enabled = enabled;
+        world.reply(context.source.sender, &format!("Whitelist is now turned {}", state));
+        Ok(1)
+    });
+
+    literal("whitelist").requires(3)
+        .then(toggle("on", true))
+        .then(toggle("off", false))
+        .then(literal("list")
+            .executes(|world, context| {
+                let names: Vec<&str> = world.access().whitelisted().collect();
+
+                match names.is_empty() {
+                    true => world.reply(context.source.sender, "There are no whitelisted players"),
+                    false => world.reply(context.source.sender, &format!("There are {} whitelisted players: {}", names.len(), names.join(", ")))
+                }
+
+                Ok(names.len() as i32)
+            }))
+        .then(literal("add")
+            .then(argument("targets", ArgumentParser::GameProfile)
+                .suggests(|world, _| world.players()
+                    .filter(|player| !world.access().is_whitelisted(&player.username))
+                    .map(|player| player.username.clone())
+                    .collect())
+                .executes(|world, context| {
+                    let add = |world: &mut World, name: &str| world.access_mut().whitelist(name);
+                    for_profiles(world, context, add, |name| format!("Added {} to the whitelist", name), "Player is already whitelisted")
+                })))
+        .then(literal("remove")
+            .then(argument("targets", ArgumentParser::GameProfile)
+                .suggests(|world, _| world.access().whitelisted().map(String::from).collect())
+                .executes(|world, context| {
+                    let remove = |world: &mut World, name: &str| world.access_mut().unwhitelist(name);
+                    for_profiles(world, context, remove, |name| format!("Removed {} from the whitelist", name), "Player is not whitelisted")
+                })))
+}
+
+fn list() -> NodeBuilder {
+    literal("list")
+        .executes(|world, context| {
+            let names: Vec<&str> = world.players().map(|player| player.username.as_str()).collect();
+            let message = format!("There are {} of a max of {} players online: {}", names.len(), world.max_players(), names.join(", "));
+
+            world.reply(context.source.sender, &message);
+            Ok(names.len() as i32)
+        })
+}
+
+fn say() -> NodeBuilder {
+    literal("say").requires(2)
+        .then(argument("message", ArgumentParser::Message)
+            .executes(|world, context| {
+                let message = context.string("message")?;
+
+                info!("[{}] {}", context.source.name, message);
+                world.broadcast_system_message(&text::translate("chat.type.announcement", &[&text::plain(&context.source.name), &text::plain(message)]));
+                Ok(1)
+            }))
+}
+
+fn msg() -> NodeBuilder {
+    literal("msg")
+        .then(argument("targets", players(false))
+            .then(argument("message", ArgumentParser::Message)
+                .executes(|world, context| {
+                    let targets = targets(world, context, "targets")?;
+                    let message = context.string("message")?;
+                    let sender = text::plain(&context.source.name);
+
+                    for entity_id in targets.iter().copied() {
+                        let target = name(world, entity_id);
+
+                        world.send_system_message(entity_id, &text::translate("commands.message.display.incoming", &[&sender, &text::plain(message)]));
+
+                        match context.source.sender {
+                            CommandSender::Player(sender) => {
+                                world.send_system_message(sender, &text::translate("commands.message.display.outgoing", &[&text::plain(&target), &text::plain(message)]));
+                            }
+                            CommandSender::Console => info!("You whisper to {}: {}", target, message)
+                        }
+                    }
+
+                    Ok(targets.len() as i32)
+                })))
+}
+
+fn stop() -> NodeBuilder {
+    literal("stop").requires(4)
+        .executes(|world, context| {
+            world.reply(context.source.sender, "Stopping the server");
+            world.stop();
+            Ok(1)
+        })
+}
+
+fn difficulty() -> NodeBuilder {
+    let mut command = literal("difficulty").requires(2)
+        .executes(|world, context| {
+            let difficulty = world.level().difficulty;
+            world.reply(context.source.sender, &format!("The difficulty is {}", capitalize(DIFFICULTIES[difficulty as usize])));
+            Ok(difficulty as i32)
+        });
+
+    for (difficulty, name) in DIFFICULTIES.into_iter().enumerate() {
+        command = command.then(literal(name).executes(move |world, context| {
+            if world.level().difficulty as usize == difficulty {
+                return Err(CommandError::new(format!("The difficulty did not change; it is already set to {}", name)))
+            }
+
+            world.set_difficulty(difficulty as u8);
+            world.reply(context.source.sender, &format!("The difficulty has been set to {}", capitalize(name)));
+            Ok(difficulty as i32)
+        }));
+    }
+
+    command
+}
+
+// the rules are integers when they are not true or false
+fn rule_result(value: &str) -> i32 {
+    value.parse().unwrap_or((value == "true") as i32)
+}
+
+fn gamerule() -> NodeBuilder {
+    let mut command = literal("gamerule").requires(2);
+
+    for (rule, default) in GAME_RULES.iter().copied() {
+        let boolean = matches!(default, "true" | "false");
+
+        let parser = match boolean {
+            true => ArgumentParser::Bool,
+            false => ArgumentParser::Integer { min: None, max: None }
+        };
+
+        command = command.then(literal(rule)
+            .executes(move |world, context| {
+                let value = world.level().game_rule_or_default(rule).unwrap_or(default).to_string();
+                world.reply(context.source.sender, &format!("Gamerule {} is currently set to: {}", rule, value));
+                Ok(rule_result(&value))
+            })
+            .then(argument("value", parser)
+                .executes(move |world, context| {
+                    let value = match boolean {
+                        true => context.bool("value")?.to_string(),
+                        false => context.integer("value")?.to_string()
+                    };
+
+                    world.set_game_rule(rule, &value);
+                    world.reply(context.source.sender, &format!("Gamerule {} is now set to: {}", rule, value));
+                    Ok(rule_result(&value))
+                })));
+    }
+
+    command
+}
+
+fn setworldspawn() -> NodeBuilder {
+    literal("setworldspawn").requires(2)
+        .executes(|world, context| {
+            let (x, y, z) = context.source.position;
+            set_world_spawn(world, context, Pos { x: x.floor() as i32, y: y.floor() as i16, z: z.floor() as i32 }, 0.0)
+        })
+        .then(argument("pos", ArgumentParser::BlockPos)
+            .executes(|world, context| {
+                let pos = context.coordinates("pos")?.block(&context.source);
+                set_world_spawn(world, context, pos, 0.0)
+            })
+            .then(argument("angle", ArgumentParser::Float { min: None, max: None })
+                .executes(|world, context| {
+                    let pos = context.coordinates("pos")?.block(&context.source);
+                    set_world_spawn(world, context, pos, context.double("angle")? as f32)
+                })))
+}
+
+fn set_world_spawn(world: &mut World, context: &CommandContext, pos: Pos, angle: f32) -> CommandResult {
+    world.level_mut().spawn_angle = angle;
+    world.set_spawn(pos);
+    world.reply(context.source.sender, &format!("Set the world spawn point to {}, {}, {} [{}]", pos.x, pos.y, pos.z, angle));
+    Ok(1)
+}
+
+fn seed() -> NodeBuilder {
+    literal("seed").requires(2)
+        .executes(|world, context| {
+            let seed = world.seed();
+            world.reply(context.source.sender, &format!("Seed: [{}]", seed));
+            Ok(seed as i32)
+        })
+}
+
+// only templates for now, saved by structure blocks into the world's generated directory
+fn place() -> NodeBuilder {
+    literal("place").requires(2)
+        .then(literal("template")
+            .then(argument("template", ArgumentParser::ResourceLocation)
+                .suggests(|world, _| world.structure_names())
+                .executes(|world, context| {
+                    let (x, y, z) = context.source.position;
+                    let pos = Pos { x: x.floor() as i32, y: y.floor() as i16, z: z.floor() as i32 };
+                    place_template(world, context, pos, PlacementSettings::default())
+                })
+                .then(argument("pos", ArgumentParser::BlockPos)
+                    .executes(|world, context| {
+                        let pos = context.coordinates("pos")?.block(&context.source);
+                        place_template(world, context, pos, PlacementSettings::default())
+                    })
+                    .then(argument("rotation", ArgumentParser::TemplateRotation)
+                        .executes(|world, context| {
+                            let pos = context.coordinates("pos")?.block(&context.source);
+                            let settings = PlacementSettings { rotation: context.rotation("rotation")?, ..Default::default() };
+                            place_template(world, context, pos, settings)
+                        })
+                        .then(argument("mirror", ArgumentParser::TemplateMirror)
+                            .executes(|world, context| {
+                                let pos = context.coordinates("pos")?.block(&context.source);
+                                let settings = PlacementSettings {
+                                    rotation: context.rotation("rotation")?,
+                                    mirror: context.mirror("mirror")?,
+                                    ..Default::default()
+                                };
+                                place_template(world, context, pos, settings)
+                            }))))))
+}
+
+fn place_template(world: &mut World, context: &CommandContext, pos: Pos, settings: PlacementSettings) -> CommandResult {
+    let name = context.string("template")?;
+
+    let template = match world.load_structure(name) {
+        Ok(template) => template,
+        Err(error) if matches!(error.kind(), io::ErrorKind::NotFound | io::ErrorKind::InvalidInput) => {
+            return Err(CommandError::new(format!("There is no template with id \"{}\"", name)))
+        },
+        Err(error) => return Err(CommandError::new(format!("Failed to load template \"{}\": {}", name, error)))
+    };
+
+    world.place_structure(&template, pos, settings);
+    world.reply(context.source.sender, &format!("Loaded structure \"{}\" at {}, {}, {}", name, pos.x, pos.y, pos.z));
+    Ok(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::tests::{confirm_teleport, flat_world, join, remove};
+
+    fn position(world: &World, entity_id: i32) -> (f64, f64, f64) {
+        let player = world.player(entity_id).unwrap();
+        (player.x, player.y, player.z)
+    }
+
+    #[tokio::test]
+    async fn changes_gamemodes_from_the_console_and_players() {
+        let (mut world, directory) = flat_world("command-gamemode");
+        let (alex, _alex) = join(&mut world, "Alex").await;
+        let (steve, _steve) = join(&mut world, "Steve").await;
+
+        assert_eq!(world.execute_command(CommandSender::Console, "gamemode creative Alex").unwrap(), 1);
+        assert_eq!(world.player(alex).unwrap().gamemode, 1);
+
+        // the console has no gamemode of its own, and players need to be operators
+        assert!(world.execute_command(CommandSender::Console, "gamemode creative").is_err());
+        assert!(world.execute_command(CommandSender::Player(steve), "gamemode creative").is_err());
+        assert_eq!(world.player(steve).unwrap().gamemode, 0);
+
+        world.op("Steve", MAX_PERMISSION_LEVEL);
+        assert_eq!(world.execute_command(CommandSender::Player(steve), "gamemode spectator").unwrap(), 1);
+        assert_eq!(world.execute_command(CommandSender::Player(steve), "gamemode adventure @a").unwrap(), 2);
+        assert_eq!(world.player(steve).unwrap().gamemode, 2);
+        assert_eq!(world.player(alex).unwrap().gamemode, 2);
+
+        remove(world, directory);
+    }
+
+    #[tokio::test]
+    async fn teleports_relative_to_whoever_runs_the_command() {
+        let (mut world, directory) = flat_world("command-teleport");
+        let (alex, _alex) = join(&mut world, "Alex").await;
+        let (steve, _steve) = join(&mut world, "Steve").await;
+        confirm_teleport(&mut world, alex);
+        confirm_teleport(&mut world, steve);
+        world.op("Steve", MAX_PERMISSION_LEVEL);
+
+        // the console starts from the middle of the spawn block
+        let spawn = world.level().spawn;
+        world.execute_command(CommandSender::Console, "tp Alex ~ ~2 ~-3").unwrap();
+        assert_eq!(position(&world, alex), (spawn.x as f64 + 0.5, spawn.y as f64 + 2.0, spawn.z as f64 - 2.5));
+
+        // a player starts from where they stand, not from the targets
+        let (x, y, z) = position(&world, steve);
+        world.execute_command(CommandSender::Player(steve), "tp ~10 ~ ~0.5").unwrap();
+        assert_eq!(position(&world, steve), (x + 10.0, y, z + 0.5));
+
+        world.execute_command(CommandSender::Player(steve), "teleport Alex ~-1 ~ ~").unwrap();
+        assert_eq!(position(&world, alex), (x + 9.0, y, z + 0.5));
+
+        remove(world, directory);
+    }
+
+    #[tokio::test]
+    async fn ops_and_deops_players() {
+        let (mut world, directory) = flat_world("command-op");
+        let (alex, _alex) = join(&mut world, "Alex").await;
+        let (steve, _steve) = join(&mut world, "Steve").await;
+
+        assert!(world.execute_command(CommandSender::Player(alex), "op Alex").is_err());
+        assert_eq!(world.player(alex).unwrap().permission_level, 0);
+
+        world.execute_command(CommandSender::Console, "op Alex").unwrap();
+        assert_eq!(world.player(alex).unwrap().permission_level, MAX_PERMISSION_LEVEL);
+        assert!(world.execute_command(CommandSender::Console, "op Alex").is_err());
+
+        // an operator can op others, and offline players by their name
+        world.execute_command(CommandSender::Player(alex), "op Steve").unwrap();
+        world.execute_command(CommandSender::Player(alex), "op Notch").unwrap();
+        assert_eq!(world.player(steve).unwrap().permission_level, MAX_PERMISSION_LEVEL);
+        assert!(world.access().operator("Notch").is_some());
+
+        world.execute_command(CommandSender::Player(steve), "deop Alex").unwrap();
+        assert_eq!(world.player(alex).unwrap().permission_level, 0);
+        assert!(world.execute_command(CommandSender::Player(alex), "deop Steve").is_err());
+        assert!(world.execute_command(CommandSender::Console, "deop Alex").is_err());
+
+        world.execute_command(CommandSender::Console, "deop Steve").unwrap();
+        assert_eq!(world.player(steve).unwrap().permission_level, 0);
+
+        remove(world, directory);
+    }
+
+    #[tokio::test]
+    async fn refuses_banned_players() {
+        let (mut world, directory) = flat_world("command-ban");
+        let (alex, _alex) = join(&mut world, "Alex").await;
+        let (steve, _steve) = join(&mut world, "Steve").await;
+        world.op("Steve", MAX_PERMISSION_LEVEL);
+
+        assert!(world.execute_command(CommandSender::Player(alex), "ban Steve").is_err());
+
+        // the player who is online is kicked
+        world.execute_command(CommandSender::Player(steve), "ban Alex griefing").unwrap();
+        assert!(world.player(alex).is_none());
+        assert_eq!(world.access().ban_of("Alex").unwrap().reason, "griefing");
+        assert_eq!(join(&mut world, "Alex").await.0, -1);
+
+        world.execute_command(CommandSender::Console, "ban Herobrine").unwrap();
+        assert!(world.execute_command(CommandSender::Console, "ban Herobrine").is_err());
+        assert_eq!(join(&mut world, "Herobrine").await.0, -1);
+
+        world.execute_command(CommandSender::Console, "pardon Alex").unwrap();
+        assert_ne!(join(&mut world, "Alex").await.0, -1);
+
+        remove(world, directory);
+    }
+
+    #[tokio::test]
+    async fn keeps_players_off_the_whitelist_out() {
+        let (mut world, directory) = flat_world("command-whitelist");
+        let (steve, _steve) = join(&mut world, "Steve").await;
+        world.op("Steve", MAX_PERMISSION_LEVEL);
+
+        world.execute_command(CommandSender::Console, "whitelist on").unwrap();
+        assert!(world.access().whitelist_enabled);
+        assert!(world.execute_command(CommandSender::Player(steve), "whitelist on").is_err());
+        assert_eq!(join(&mut world, "Alex").await.0, -1);
+
+        world.execute_command(CommandSender::Player(steve), "whitelist add Alex").unwrap();
+        assert!(world.execute_command(CommandSender::Console, "whitelist add Alex").is_err());
+        assert_eq!(world.execute_command(CommandSender::Console, "whitelist list").unwrap(), 1);
+
+        let (alex, _alex) = join(&mut world, "Alex").await;
+        assert_ne!(alex, -1);
+        assert!(world.execute_command(CommandSender::Player(alex), "whitelist off").is_err());
+
+        world.execute_command(CommandSender::Player(steve), "whitelist remove Alex").unwrap();
+        world.execute_command(CommandSender::Console, "whitelist off").unwrap();
+        assert_eq!(world.execute_command(CommandSender::Player(steve), "whitelist list").unwrap(), 0);
+        assert_ne!(join(&mut world, "Notch").await.0, -1);
+
+        remove(world, directory);
+    }
+}
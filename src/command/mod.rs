@@ -0,0 +1,738 @@
+use std::{collections::HashMap, fmt::{self, Display, Formatter}, sync::Arc};
+
+use crate::{block::{Mirror, Rotation}, types::{CommandNode, CommandNodeKind, Identifier}, world::World};
+
+use self::{argument::{Argument, ArgumentParser, Coordinates, EntitySelector}, reader::StringReader};
+
+pub mod argument;
+pub mod builtin;
+pub mod reader;
+
+// what the console and operators get, every level includes the ones below it
+pub const MAX_PERMISSION_LEVEL: u8 = 4;
+
+pub type CommandResult = Result<i32, CommandError>;
+pub type Executor = Arc<dyn Fn(&mut World, &CommandContext) -> CommandResult + Send + Sync>;
+pub type SuggestionProvider = Arc<dyn Fn(&World, &CommandSource) -> Vec<String> + Send + Sync>;
+
+#[derive(Debug, Clone)]
+pub struct CommandError {
+    pub message: String,
+    // the command and where in it the error is, for syntax errors
+    context: Option<(String, usize)>
+}
+
+impl CommandError {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self { message: message.into(), context: None }
+    }
+
+    pub fn at(message: impl Into<String>, input: &str, cursor: usize) -> Self {
+        Self { message: message.into(), context: Some((input.to_string(), cursor)) }
+    }
+
+    pub fn cursor(&self) -> Option<usize> {
+        self.context.as_ref().map(|(_, cursor)| *cursor)
+    }
+}
+
+impl Display for CommandError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)?;
+
+        // like vanilla, up to ten characters before the error, then the rest of the command that was not understood
+        if let Some((input, cursor)) = &self.context {
+            let before = &input[..*cursor];
+            let shown: String = before.chars().rev().take(10).collect::<Vec<char>>().into_iter().rev().collect();
+            let ellipsis = if shown.len() < before.len() { "..." } else { "" };
+
+            write!(f, "\n{}{}{}<--[HERE]", ellipsis, shown, &input[*cursor..])?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommandSender {
+    Console,
+    Player(i32)
+}
+
+impl CommandSender {
+    pub fn entity_id(&self) -> Option<i32> {
+        match self {
+            Self::Console => None,
+            Self::Player(entity_id) => Some(*entity_id)
+        }
+    }
+}
+
+// who runs a command and from where, relative coordinates and selectors start from here
+#[derive(Debug, Clone)]
+pub struct CommandSource {
+    pub sender: CommandSender,
+    pub name: String,
+    pub permission_level: u8,
+    pub position: (f64, f64, f64),
+    pub rotation: (f32, f32)
+}
+
+pub struct CommandContext {
+    pub source: CommandSource,
+    pub input: String,
+    arguments: HashMap<String, Argument>
+}
+
+impl CommandContext {
+    pub fn argument(&self, name: &str) -> Option<&Argument> {
+        self.arguments.get(name)
+    }
+
+    fn missing(name: &str) -> CommandError {
+        CommandError::new(format!("Missing argument '{}'", name))
+    }
+
+    pub fn bool(&self, name: &str) -> Result<bool, CommandError> {
+        match self.argument(name) {
+            Some(Argument::Bool(value)) => Ok(*value),
+            _ => Err(Self::missing(name))
+        }
+    }
+
+    pub fn integer(&self, name: &str) -> Result<i32, CommandError> {
+        match self.argument(name) {
+            Some(Argument::Integer(value)) => Ok(*value),
+            _ => Err(Self::missing(name))
+        }
+    }
+
+    pub fn long(&self, name: &str) -> Result<i64, CommandError> {
+        match self.argument(name) {
+            Some(Argument::Long(value)) => Ok(*value),
+            _ => Err(Self::missing(name))
+        }
+    }
+
+    pub fn double(&self, name: &str) -> Result<f64, CommandError> {
+        match self.argument(name) {
+            Some(Argument::Double(value)) => Ok(*value),
+            Some(Argument::Float(value)) => Ok(*value as f64),
+            _ => Err(Self::missing(name))
+        }
+    }
+
+    // strings, messages, item ids and resource locations
+    pub fn string(&self, name: &str) -> Result<&str, CommandError> {
+        match self.argument(name) {
+            Some(Argument::String(value) | Argument::Message(value) | Argument::ItemStack(value) | Argument::ResourceLocation(value)) => Ok(value),
+            _ => Err(Self::missing(name))
+        }
+    }
+
+    pub fn selector(&self, name: &str) -> Result<&EntitySelector, CommandError> {
+        match self.argument(name) {
+            Some(Argument::Entity(selector) | Argument::GameProfile(selector)) => Ok(selector),
+            _ => Err(Self::missing(name))
+        }
+    }
+
+    pub fn coordinates(&self, name: &str) -> Result<Coordinates, CommandError> {
+        match self.argument(name) {
+            Some(Argument::BlockPos(coordinates) | Argument::Vec3(coordinates)) => Ok(*coordinates),
+            _ => Err(Self::missing(name))
+        }
+    }
+
+    pub fn gamemode(&self, name: &str) -> Result<u8, CommandError> {
+        match self.argument(name) {
+            Some(Argument::Gamemode(gamemode)) => Ok(*gamemode),
+            _ => Err(Self::missing(name))
+        }
+    }
+
+    // in ticks
+    pub fn time(&self, name: &str) -> Result<i32, CommandError> {
+        match self.argument(name) {
+            Some(Argument::Time(ticks)) => Ok(*ticks),
+            _ => Err(Self::missing(name))
+        }
+    }
+
+    pub fn mirror(&self, name: &str) -> Result<Mirror, CommandError> {
+        match self.argument(name) {
+            Some(Argument::Mirror(mirror)) => Ok(*mirror),
+            _ => Err(Self::missing(name))
+        }
+    }
+
+    pub fn rotation(&self, name: &str) -> Result<Rotation, CommandError> {
+        match self.argument(name) {
+            Some(Argument::Rotation(rotation)) => Ok(*rotation),
+            _ => Err(Self::missing(name))
+        }
+    }
+}
+
+enum NodeKind {
+    Root,
+    Literal(String),
+    Argument { name: String, parser: ArgumentParser }
+}
+
+struct Node {
+    kind: NodeKind,
+    children: Vec<usize>,
+    executor: Option<Executor>,
+    permission_level: u8,
+    // the node whose children and executor stand in for this one's
+    redirect: Option<usize>,
+    suggestions: Option<SuggestionProvider>
+}
+
+pub struct NodeBuilder {
+    kind: NodeKind,
+    children: Vec<NodeBuilder>,
+    executor: Option<Executor>,
+    permission_level: u8,
+    redirect: Option<String>,
+    suggestions: Option<SuggestionProvider>
+}
+
+fn node(kind: NodeKind) -> NodeBuilder {
+    NodeBuilder {
+        kind,
+        children: vec![],
+        executor: None,
+        permission_level: 0,
+        redirect: None,
+        suggestions: None
+    }
+}
+
+pub fn literal(name: &str) -> NodeBuilder {
+    node(NodeKind::Literal(name.to_string()))
+}
+
+pub fn argument(name: &str, parser: ArgumentParser) -> NodeBuilder {
+    node(NodeKind::Argument { name: name.to_string(), parser })
+}
+
+impl NodeBuilder {
+    pub fn then(mut self, child: NodeBuilder) -> Self {
+        self.children.push(child);
+        self
+    }
+
+    pub fn executes(mut self, executor: impl Fn(&mut World, &CommandContext) -> CommandResult + Send + Sync + 'static) -> Self {
+        self.executor = Some(Arc::new(executor));
+        self
+    }
+
+    // the lowest permission level that can see and use the node
+    pub fn requires(mut self, permission_level: u8) -> Self {
+        self.permission_level = permission_level;
+        self
+    }
+
+    // continues like the root command of that name, for aliases
+    pub fn redirect(mut self, command: &str) -> Self {
+        self.redirect = Some(command.to_string());
+        self
+    }
+
+    // the client asks the server for these instead of suggesting on its own
+    pub fn suggests(mut self, suggestions: impl Fn(&World, &CommandSource) -> Vec<String> + Send + Sync + 'static) -> Self {
+        self.suggestions = Some(Arc::new(suggestions));
+        self
+    }
+}
+
+// a command that parsed, ready to run
+pub struct ParsedCommand {
+    pub context: CommandContext,
+    executor: Executor
+}
+
+impl ParsedCommand {
+    pub fn execute(self, world: &mut World) -> CommandResult {
+        (self.executor)(world, &self.context)
+    }
+}
+
+// the suggestions for the last word, and where it starts
+pub struct Suggestions {
+    pub start: usize,
+    pub matches: Vec<String>
+}
+
+// the tree of every command, modelled on Brigadier
+pub struct CommandDispatcher {
+    nodes: Vec<Node>
+}
+
+impl Default for CommandDispatcher {
+    fn default() -> Self {
+        Self {
+            nodes: vec![Node {
+                kind: NodeKind::Root,
+                children: vec![],
+                executor: None,
+                permission_level: 0,
+                redirect: None,
+                suggestions: None
+            }]
+        }
+    }
+}
+
+impl CommandDispatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // registering a command that already exists adds to it
+    pub fn register(&mut self, command: NodeBuilder) {
+        self.insert(0, command);
+    }
+
+    fn insert(&mut self, parent: usize, builder: NodeBuilder) -> usize {
+        let redirect = builder.redirect.as_deref().map(|command| {
+            self.find(0, command).unwrap_or_else(|| panic!("redirect to the unknown command '{}'", command))
+        });
+
+        let existing = self.nodes[parent].children.iter().copied().find(|child| match (&self.nodes[*child].kind, &builder.kind) {
+            (NodeKind::Literal(a), NodeKind::Literal(b)) => a == b,
+            (NodeKind::Argument { name: a, .. }, NodeKind::Argument { name: b, .. }) => a == b,
+            _ => false
+        });
+
+        let index = match existing {
+            Some(index) => {
+                let node = &mut self.nodes[index];
+                node.executor = builder.executor.or(node.executor.take());
+                node.redirect = redirect.or(node.redirect);
+                node.suggestions = builder.suggestions.or(node.suggestions.take());
+                node.permission_level = builder.permission_level;
+                index
+            }
+            None => {
+                self.nodes.push(Node {
+                    kind: builder.kind,
+                    children: vec![],
+                    executor: builder.executor,
+                    permission_level: builder.permission_level,
+                    redirect,
+                    suggestions: builder.suggestions
+                });
+
+                let index = self.nodes.len() - 1;
+                self.nodes[parent].children.push(index);
+                index
+            }
+        };
+
+        for child in builder.children {
+            self.insert(index, child);
+        }
+
+        index
+    }
+
+    fn find(&self, parent: usize, literal: &str) -> Option<usize> {
+        self.nodes[parent].children.iter().copied().find(|child| matches!(&self.nodes[*child].kind, NodeKind::Literal(name) if name == literal))
+    }
+
+    // the names of the commands the source can use
+    pub fn commands(&self, source: &CommandSource) -> Vec<&str> {
+        self.nodes[0].children.iter()
+            .map(|child| &self.nodes[*child])
+            .filter(|node| node.permission_level <= source.permission_level)
+            .filter_map(|node| match &node.kind {
+                NodeKind::Literal(name) => Some(name.as_str()),
+                _ => None
+            })
+            .collect()
+    }
+
+    fn children(&self, index: usize) -> &[usize] {
+        let node = &self.nodes[index];
+        &self.nodes[node.redirect.unwrap_or(index)].children
+    }
+
+    fn executor(&self, index: usize) -> Option<&Executor> {
+        let node = &self.nodes[index];
+        node.executor.as_ref().or_else(|| node.redirect.and_then(|redirect| self.nodes[redirect].executor.as_ref()))
+    }
+
+    // reads one node off the reader, literals have to be followed by a space or the end
+    fn parse_node(&self, index: usize, reader: &mut StringReader, arguments: &mut HashMap<String, Argument>) -> Result<(), CommandError> {
+        match &self.nodes[index].kind {
+            NodeKind::Root => Ok(()),
+            NodeKind::Literal(name) => {
+                let start = reader.cursor();
+                let word = reader.read_while(|character| character != ' ');
+
+                if word != name {
+                    reader.set_cursor(start);
+                    return Err(reader.error("Unknown or incomplete command, see below for error"))
+                }
+
+                Ok(())
+            }
+            NodeKind::Argument { name, parser } => {
+                let argument = parser.parse(reader)?;
+                arguments.insert(name.clone(), argument);
+                Ok(())
+            }
+        }
+    }
+
+    // the node the input ends at and the arguments on the way, the path that got furthest wins when none fits
+    fn parse_from(&self, index: usize, reader: &StringReader, source: &CommandSource, arguments: &HashMap<String, Argument>) -> Result<(usize, HashMap<String, Argument>), CommandError> {
+        if !reader.can_read() {
+            return Ok((index, arguments.clone()))
+        }
+
+        let children: Vec<usize> = self.children(index).iter().copied()
+            .filter(|child| self.nodes[*child].permission_level <= source.permission_level)
+            .collect();
+
+        // a literal that matches takes the word, no argument gets to try it then
+        let word = reader.remaining().split(' ').next().unwrap_or_default();
+        let literals: Vec<usize> = children.iter().copied()
+            .filter(|child| matches!(&self.nodes[*child].kind, NodeKind::Literal(name) if name == word))
+            .collect();
+
+        let candidates = match literals.is_empty() {
+            true => children.into_iter().filter(|child| matches!(self.nodes[*child].kind, NodeKind::Argument { .. })).collect(),
+            false => literals
+        };
+
+        let mut best: Option<CommandError> = None;
+
+        for child in candidates {
+            let mut reader = reader.clone();
+            let mut arguments = arguments.clone();
+
+            let result = self.parse_node(child, &mut reader, &mut arguments).and_then(|_| {
+                match reader.peek() {
+                    None => Ok(()),
+                    Some(' ') => {
+                        reader.skip();
+                        Ok(())
+                    }
+                    Some(_) => Err(reader.error("Expected whitespace to end one argument, but found trailing data"))
+                }
+            }).and_then(|_| self.parse_from(child, &reader, source, &arguments));
+
+            match result {
+                Ok(parsed) => return Ok(parsed),
+                Err(error) => {
+                    if best.as_ref().is_none_or(|best| error.cursor() > best.cursor()) {
+                        best = Some(error);
+                    }
+                }
+            }
+        }
+
+        Err(best.unwrap_or_else(|| match index {
+            0 => reader.error("Unknown or incomplete command, see below for error"),
+            _ => reader.error("Incorrect argument for command")
+        }))
+    }
+
+    // the input comes without the slash
+    pub fn parse(&self, source: &CommandSource, input: &str) -> Result<ParsedCommand, CommandError> {
+        let reader = StringReader::new(input);
+        let (index, arguments) = self.parse_from(0, &reader, source, &HashMap::new())?;
+
+        let Some(executor) = self.executor(index).filter(|_| index != 0) else {
+            return Err(CommandError::at("Unknown or incomplete command, see below for error", input, input.len()))
+        };
+
+        Ok(ParsedCommand {
+            context: CommandContext {
+                source: source.clone(),
+                input: input.to_string(),
+                arguments
+            },
+            executor: executor.clone()
+        })
+    }
+
+    fn node_suggestions(&self, index: usize, world: &World, source: &CommandSource) -> Vec<String> {
+        let node = &self.nodes[index];
+
+        match (&node.kind, &node.suggestions) {
+            (NodeKind::Literal(name), _) => vec![name.clone()],
+            (_, Some(suggestions)) => suggestions(world, source),
+            (NodeKind::Argument { parser, .. }, None) => parser.suggestions(world),
+            (NodeKind::Root, None) => vec![]
+        }
+    }
+
+    fn suggest_from(&self, index: usize, reader: &StringReader, world: &World, source: &CommandSource, output: &mut Suggestions) {
+        let start = reader.cursor();
+        let partial = reader.remaining();
+
+        for child in self.children(index).iter().copied().filter(|child| self.nodes[*child].permission_level <= source.permission_level) {
+            let mut reader = reader.clone();
+            let parsed = self.parse_node(child, &mut reader, &mut HashMap::new()).is_ok();
+
+            if parsed && reader.peek() == Some(' ') {
+                reader.skip();
+                self.suggest_from(child, &reader, world, source, output);
+                continue
+            }
+
+            // the word under the cursor is this node, whether it parses yet or not
+            if (parsed && !reader.can_read()) || !partial.contains(' ') {
+                if start > output.start {
+                    *output = Suggestions { start, matches: vec![] };
+                }
+
+                if start == output.start {
+                    let lowercase = partial.to_lowercase();
+
+                    output.matches.extend(self.node_suggestions(child, world, source).into_iter()
+                        .filter(|suggestion| suggestion.to_lowercase().starts_with(&lowercase) && *suggestion != partial));
+                }
+            }
+        }
+    }
+
+    // what could come at the end of the input, the input comes without the slash
+    pub fn suggest(&self, world: &World, source: &CommandSource, input: &str) -> Suggestions {
+        let mut suggestions = Suggestions { start: 0, matches: vec![] };
+        self.suggest_from(0, &StringReader::new(input), world, source, &mut suggestions);
+
+        suggestions.matches.sort();
+        suggestions.matches.dedup();
+        suggestions
+    }
+
+    // the part of the tree the source may use, flattened the way the Commands packet wants it with the root first
+    pub fn to_nodes(&self, source: &CommandSource) -> Vec<CommandNode> {
+        let mut order = vec![0];
+        let mut indices = HashMap::from([(0, 0)]);
+        let mut next = 0;
+
+        while next < order.len() {
+            for child in self.nodes[order[next]].children.iter().copied() {
+                if self.nodes[child].permission_level <= source.permission_level && !indices.contains_key(&child) {
+                    indices.insert(child, order.len());
+                    order.push(child);
+                }
+            }
+
+            next += 1;
+        }
+
+        order.iter().map(|index| {
+            let node = &self.nodes[*index];
+
+            CommandNode {
+                executable: node.executor.is_some(),
+                children: node.children.iter().filter_map(|child| indices.get(child)).map(|child| (*child as i32).into()).collect(),
+                redirect: node.redirect.and_then(|redirect| indices.get(&redirect)).map(|redirect| (*redirect as i32).into()),
+                kind: match &node.kind {
+                    NodeKind::Root => CommandNodeKind::Root,
+                    NodeKind::Literal(name) => CommandNodeKind::Literal(name.clone()),
+                    NodeKind::Argument { name, parser } => CommandNodeKind::Argument {
+                        name: name.clone(),
+                        parser: parser.clone(),
+                        suggestions: node.suggestions.as_ref().map(|_| Identifier::from("minecraft:ask_server"))
+                    }
+                }
+            }
+        }).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use self::argument::StringKind;
+
+    fn source(permission_level: u8) -> CommandSource {
+        CommandSource {
+            sender: CommandSender::Console,
+            name: "Server".to_string(),
+            permission_level,
+            position: (0.0, 64.0, 0.0),
+            rotation: (0.0, 0.0)
+        }
+    }
+
+    // every executor returns a different number so the tests can tell which one parsed
+    fn dispatcher() -> CommandDispatcher {
+        let mut dispatcher = CommandDispatcher::new();
+
+        dispatcher.register(literal("give").requires(2)
+            .then(argument("targets", ArgumentParser::Entity { single: false, players_only: true })
+                .then(argument("item", ArgumentParser::ItemStack).executes(|_, _| Ok(1))
+                    .then(argument("count", ArgumentParser::Integer { min: Some(1), max: Some(64) }).executes(|_, context| context.integer("count"))))));
+
+        dispatcher.register(literal("time")
+            .then(literal("set").then(argument("time", ArgumentParser::Time { min: 0 }).executes(|_, context| context.time("time"))))
+            .then(literal("query").then(literal("daytime").executes(|_, _| Ok(2)))));
+
+        dispatcher.register(literal("teleport").then(argument("location", ArgumentParser::Vec3).executes(|_, _| Ok(3))));
+        dispatcher.register(literal("tp").redirect("teleport"));
+
+        dispatcher.register(literal("mode")
+            .then(literal("reset").executes(|_, _| Ok(4)))
+            .then(argument("name", ArgumentParser::String(StringKind::SingleWord)).executes(|_, _| Ok(5))));
+
+        dispatcher.register(literal("set")
+            .then(argument("value", ArgumentParser::Integer { min: Some(0), max: Some(10) }).executes(|_, _| Ok(6)))
+            .then(argument("position", ArgumentParser::BlockPos).executes(|_, _| Ok(7))));
+
+        dispatcher
+    }
+
+    // nothing is ever saved, the directory does not have to exist
+    fn world(name: &str) -> World {
+        World::new(std::env::temp_dir().join(format!("mcserver-command-{}-{}", name, std::process::id())), 0)
+    }
+
+    fn parse(input: &str) -> Result<ParsedCommand, CommandError> {
+        dispatcher().parse(&source(4), input)
+    }
+
+    fn error(level: u8, input: &str) -> (String, Option<usize>) {
+        let error = dispatcher().parse(&source(level), input).err().unwrap();
+        let cursor = error.cursor();
+        (error.message, cursor)
+    }
+
+    #[test]
+    fn parses_arguments() {
+        let parsed = parse("give @a stone 12").ok().unwrap();
+        let context = &parsed.context;
+
+        assert_eq!(context.selector("targets").unwrap(), &EntitySelector::AllPlayers);
+        assert!(matches!(context.argument("item"), Some(Argument::ItemStack(item)) if item == "minecraft:stone"));
+        assert_eq!(context.integer("count").unwrap(), 12);
+        assert_eq!(context.input, "give @a stone 12");
+
+        let parsed = parse("time set 2d").ok().unwrap();
+        assert_eq!(parsed.context.time("time").unwrap(), 48000);
+        assert!(parsed.context.argument("count").is_none());
+    }
+
+    #[test]
+    fn runs_the_executor_of_the_last_node() {
+        let mut world = world("execute");
+
+        let run = |world: &mut World, input| parse(input).ok().unwrap().execute(world).unwrap();
+
+        assert_eq!(run(&mut world, "give @a stone"), 1);
+        assert_eq!(run(&mut world, "give @a stone 7"), 7);
+        assert_eq!(run(&mut world, "time query daytime"), 2);
+        // a matching literal takes the word before any argument can
+        assert_eq!(run(&mut world, "mode reset"), 4);
+        assert_eq!(run(&mut world, "mode other"), 5);
+        assert_eq!(run(&mut world, "set 5"), 6);
+        assert_eq!(run(&mut world, "set 1 2 3"), 7);
+    }
+
+    #[test]
+    fn follows_redirects() {
+        let mut world = world("redirect");
+        let parsed = parse("tp 1 2 3").ok().unwrap();
+
+        assert_eq!(parsed.context.coordinates("location").unwrap().resolve(&parsed.context.source), (1.5, 2.0, 3.5));
+        assert_eq!(parsed.execute(&mut world).unwrap(), 3);
+        assert_eq!(error(4, "tp"), ("Unknown or incomplete command, see below for error".to_string(), Some(2)));
+    }
+
+    #[test]
+    fn reports_the_path_that_got_furthest() {
+        // the value reads 5 and then has nothing for 6, the position gives up at the start
+        assert_eq!(error(4, "set 5 6"), ("Incorrect argument for command".to_string(), Some(6)));
+        assert_eq!(error(4, "set 11"), ("Integer must not be more than 10, found 11".to_string(), Some(4)));
+        assert_eq!(error(4, "give @a stone 100"), ("Integer must not be more than 64, found 100".to_string(), Some(14)));
+        assert_eq!(error(4, "time set 1x"), ("Invalid unit".to_string(), Some(10)));
+        assert_eq!(error(4, "time sets"), ("Incorrect argument for command".to_string(), Some(5)));
+        assert_eq!(error(4, "unknown"), ("Unknown or incomplete command, see below for error".to_string(), Some(0)));
+        assert_eq!(error(4, "time"), ("Unknown or incomplete command, see below for error".to_string(), Some(4)));
+    }
+
+    #[test]
+    fn hides_commands_above_the_permission_level() {
+        assert_eq!(error(0, "give @a stone"), ("Unknown or incomplete command, see below for error".to_string(), Some(0)));
+        assert!(dispatcher().parse(&source(2), "give @a stone").is_ok());
+
+        assert_eq!(dispatcher().commands(&source(0)), ["time", "teleport", "tp", "mode", "set"]);
+        assert_eq!(dispatcher().commands(&source(2)), ["give", "time", "teleport", "tp", "mode", "set"]);
+    }
+
+    #[test]
+    fn suggests_the_last_word() {
+        let world = world("suggest");
+        let dispatcher = dispatcher();
+        let suggest = |level, input| {
+            let suggestions = dispatcher.suggest(&world, &source(level), input);
+            (suggestions.start, suggestions.matches)
+        };
+
+        assert_eq!(suggest(4, "t"), (0, vec!["teleport".to_string(), "time".to_string(), "tp".to_string()]));
+        assert_eq!(suggest(4, "time "), (5, vec!["query".to_string(), "set".to_string()]));
+        assert_eq!(suggest(4, "time q"), (5, vec!["query".to_string()]));
+        assert_eq!(suggest(4, "time query "), (11, vec!["daytime".to_string()]));
+        assert_eq!(suggest(4, "tp "), (3, vec!["~ ~ ~".to_string()]));
+        assert!(suggest(4, "give @a stone 3 ").1.is_empty());
+        assert_eq!(suggest(0, "g"), (0, vec![]));
+        assert_eq!(suggest(2, "g"), (0, vec!["give".to_string()]));
+    }
+
+    #[test]
+    fn flattens_the_tree() {
+        let nodes = dispatcher().to_nodes(&source(0));
+        let children = |index: usize| nodes[index].children.iter().map(|child| i32::from(child.clone()) as usize).collect::<Vec<_>>();
+        let literal = |index: usize| match &nodes[index].kind {
+            CommandNodeKind::Literal(name) => name.as_str(),
+            kind => panic!("not a literal: {:?}", kind)
+        };
+
+        assert!(matches!(nodes[0].kind, CommandNodeKind::Root));
+        assert!(!nodes[0].executable);
+
+        // give is left out for level 0, the rest keep their order
+        let commands: Vec<&str> = children(0).into_iter().map(literal).collect();
+        assert_eq!(commands, ["time", "teleport", "tp", "mode", "set"]);
+
+        let teleport = children(0)[1];
+        let tp = children(0)[2];
+        assert!(children(tp).is_empty());
+        assert_eq!(nodes[tp].redirect.clone().map(i32::from), Some(teleport as i32));
+        assert!(nodes[teleport].redirect.is_none());
+
+        let location = children(teleport)[0];
+        assert!(nodes[location].executable);
+        assert!(matches!(&nodes[location].kind, CommandNodeKind::Argument { name, parser: ArgumentParser::Vec3, suggestions: None } if name == "location"));
+
+        // every child comes after its parent
+        for index in 0..nodes.len() {
+            assert!(children(index).into_iter().all(|child| child > index && child < nodes.len()));
+        }
+
+        assert_eq!(dispatcher().to_nodes(&source(2)).len(), nodes.len() + 4);
+    }
+
+    #[test]
+    fn asks_the_server_for_custom_suggestions() {
+        let mut dispatcher = CommandDispatcher::new();
+        dispatcher.register(literal("warp").then(argument("name", ArgumentParser::String(StringKind::SingleWord))
+            .suggests(|_, _| vec!["home".to_string(), "spawn".to_string()])));
+
+        let nodes = dispatcher.to_nodes(&source(0));
+        assert!(matches!(&nodes[2].kind, CommandNodeKind::Argument { suggestions: Some(identifier), .. } if String::from(identifier) == "minecraft:ask_server"));
+
+        let world = world("custom");
+        assert_eq!(dispatcher.suggest(&world, &source(0), "warp s").matches, ["spawn"]);
+    }
+}
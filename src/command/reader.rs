@@ -0,0 +1,171 @@
+use super::CommandError;
+
+// walks through a command, every parser starts reading where the one before it stopped
+#[derive(Debug, Clone)]
+pub struct StringReader<'a> {
+    input: &'a str,
+    cursor: usize
+}
+
+fn is_unquoted(character: char) -> bool {
+    character.is_ascii_alphanumeric() || matches!(character, '_' | '-' | '.' | '+')
+}
+
+fn is_number(character: char) -> bool {
+    character.is_ascii_digit() || matches!(character, '.' | '-')
+}
+
+impl<'a> StringReader<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Self { input, cursor: 0 }
+    }
+
+    pub fn input(&self) -> &'a str {
+        self.input
+    }
+
+    // in bytes
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn set_cursor(&mut self, cursor: usize) {
+        self.cursor = cursor;
+    }
+
+    pub fn remaining(&self) -> &'a str {
+        &self.input[self.cursor..]
+    }
+
+    pub fn can_read(&self) -> bool {
+        self.cursor < self.input.len()
+    }
+
+    pub fn peek(&self) -> Option<char> {
+        self.remaining().chars().next()
+    }
+
+    pub fn skip(&mut self) {
+        if let Some(character) = self.peek() {
+            self.cursor += character.len_utf8();
+        }
+    }
+
+    pub fn skip_whitespace(&mut self) {
+        while self.peek() == Some(' ') {
+            self.skip();
+        }
+    }
+
+    pub fn error(&self, message: impl Into<String>) -> CommandError {
+        CommandError::at(message, self.input, self.cursor)
+    }
+
+    pub fn expect(&mut self, expected: char) -> Result<(), CommandError> {
+        if self.peek() != Some(expected) {
+            return Err(self.error(format!("Expected '{}'", expected)))
+        }
+
+        self.skip();
+        Ok(())
+    }
+
+    pub fn read_while(&mut self, predicate: impl Fn(char) -> bool) -> &'a str {
+        let start = self.cursor;
+
+        while self.peek().is_some_and(&predicate) {
+            self.skip();
+        }
+
+        &self.input[start..self.cursor]
+    }
+
+    pub fn read_unquoted(&mut self) -> &'a str {
+        self.read_while(is_unquoted)
+    }
+
+    pub fn read_quoted(&mut self) -> Result<String, CommandError> {
+        let Some(quote) = self.peek().filter(|character| matches!(character, '"' | '\'')) else {
+            return Err(self.error("Expected quote to start a string"))
+        };
+
+        self.skip();
+
+        let mut output = String::new();
+        let mut escaped = false;
+
+        while let Some(character) = self.peek() {
+            self.skip();
+
+            if escaped {
+                if character != quote && character != '\\' {
+                    self.cursor -= character.len_utf8();
+                    return Err(self.error(format!("Invalid escape sequence '{}' in quoted string", character)))
+                }
+
+                output.push(character);
+                escaped = false;
+            } else if character == '\\' {
+                escaped = true;
+            } else if character == quote {
+                return Ok(output)
+            } else {
+                output.push(character);
+            }
+        }
+
+        Err(self.error("Unclosed quoted string"))
+    }
+
+    pub fn read_string(&mut self) -> Result<String, CommandError> {
+        match self.peek() {
+            Some('"' | '\'') => self.read_quoted(),
+            _ => Ok(self.read_unquoted().to_string())
+        }
+    }
+
+    pub fn read_bool(&mut self) -> Result<bool, CommandError> {
+        let start = self.cursor;
+
+        match self.read_unquoted() {
+            "true" => Ok(true),
+            "false" => Ok(false),
+            "" => Err(self.error("Expected bool")),
+            value => {
+                self.cursor = start;
+                Err(self.error(format!("Invalid bool, expected true or false but found '{}'", value)))
+            }
+        }
+    }
+
+    // numbers are read as far as they look like one, and only then checked
+    fn read_number<T: std::str::FromStr>(&mut self, kind: &str) -> Result<T, CommandError> {
+        let start = self.cursor;
+        let number = self.read_while(is_number);
+
+        if number.is_empty() {
+            return Err(self.error(format!("Expected {}", kind)))
+        }
+
+        number.parse().map_err(|_| {
+            self.cursor = start;
+            self.error(format!("Invalid {} '{}'", kind, number))
+        })
+    }
+
+    pub fn read_int(&mut self) -> Result<i32, CommandError> {
+        self.read_number("integer")
+    }
+
+    pub fn read_long(&mut self) -> Result<i64, CommandError> {
+        self.read_number("long")
+    }
+
+    pub fn read_float(&mut self) -> Result<f32, CommandError> {
+        self.read_number("float")
+    }
+
+    pub fn read_double(&mut self) -> Result<f64, CommandError> {
+        self.read_number("double")
+    }
+}
@@ -0,0 +1,126 @@
+use std::marker::PhantomData;
+
+use bytes::Bytes;
+use tokio::net::TcpStream;
+
+use crate::{interface::{self, Interface, InterfaceReader, InterfaceWriter}, packets::{handshaking, login, play, status}, protocol::{Decode, DecodeResult, Encode, Packet}};
+
+pub trait State {
+    type Serverbound: Decode + Packet;
+    type Clientbound: Encode + Packet;
+}
+
+pub struct Handshaking;
+pub struct Status;
+pub struct Login;
+pub struct Play;
+
+impl State for Handshaking {
+    type Serverbound = handshaking::serverbound::Packet;
+    type Clientbound = handshaking::clientbound::Packet;
+}
+
+impl State for Status {
+    type Serverbound = status::serverbound::Packet;
+    type Clientbound = status::clientbound::Packet;
+}
+
+impl State for Login {
+    type Serverbound = login::serverbound::Packet;
+    type Clientbound = login::clientbound::Packet;
+}
+
+impl State for Play {
+    type Serverbound = play::serverbound::Packet;
+    type Clientbound = play::clientbound::Packet;
+}
+
+pub struct Connection<S: State> {
+    interface: Interface,
+    state: PhantomData<S>
+}
+
+impl<S: State> Connection<S> {
+    pub async fn receive(&mut self) -> DecodeResult<S::Serverbound> {
+        self.interface.receive().await
+    }
+
+    pub async fn send(&mut self, packet: S::Clientbound) {
+        self.interface.send(packet).await
+    }
+
+    pub async fn disconnect(mut self) {
+        self.interface.disconnect().await
+    }
+
+    fn transition<N: State>(self) -> Connection<N> {
+        Connection {
+            interface: self.interface,
+            state: PhantomData
+        }
+    }
+}
+
+impl Connection<Handshaking> {
+    pub fn new(stream: TcpStream) -> Self {
+        Self {
+            interface: Interface::new(stream),
+            state: PhantomData
+        }
+    }
+
+    pub fn into_status(self) -> Connection<Status> {
+        self.transition()
+    }
+
+    pub fn into_login(self) -> Connection<Login> {
+        self.transition()
+    }
+}
+
+impl Connection<Login> {
+    pub fn into_play(self) -> Connection<Play> {
+        self.transition()
+    }
+}
+
+impl Connection<Play> {
+    pub fn into_split(self) -> (ConnectionReader<Play>, ConnectionWriter<Play>) {
+        let (reader, writer) = self.interface.into_split();
+
+        (
+            ConnectionReader { reader, state: PhantomData },
+            ConnectionWriter { writer, state: PhantomData }
+        )
+    }
+}
+
+pub struct ConnectionReader<S: State> {
+    reader: InterfaceReader,
+    state: PhantomData<S>
+}
+
+impl<S: State> ConnectionReader<S> {
+    pub async fn receive(&mut self) -> DecodeResult<S::Serverbound> {
+        self.reader.receive().await
+    }
+}
+
+pub struct ConnectionWriter<S: State> {
+    writer: InterfaceWriter,
+    state: PhantomData<S>
+}
+
+impl<S: State> ConnectionWriter<S> {
+    pub fn frame(packet: &S::Clientbound) -> Bytes {
+        interface::frame(packet).freeze()
+    }
+
+    pub async fn send_frame(&mut self, frame: &[u8]) -> std::io::Result<()> {
+        self.writer.send_frame(frame).await
+    }
+
+    pub async fn disconnect(&mut self) {
+        self.writer.disconnect().await
+    }
+}
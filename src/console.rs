@@ -0,0 +1,143 @@
+use std::{io::{self, BufRead, IsTerminal}, thread};
+
+use rustyline::{completion::Completer, config::Config, error::ReadlineError, highlight::Highlighter, hint::Hinter, validate::Validator, CompletionType, Context, Editor, Helper};
+use tokio::sync::{mpsc, oneshot};
+
+use crate::{error, log, world::WorldEvent};
+
+const PROMPT: &str = "> ";
+const HISTORY_SIZE: usize = 200;
+
+// completes from the command tree of the world, the editor thread waits for the world to answer
+struct CommandHelper {
+    events: mpsc::UnboundedSender<WorldEvent>
+}
+
+impl Completer for CommandHelper {
+    type Candidate = String;
+
+    fn complete(&self, line: &str, pos: usize, _: &Context<'_>) -> rustyline::Result<(usize, Vec<String>)> {
+        let line = &line[..pos];
+        let command = line.strip_prefix('/').unwrap_or(line);
+        let (reply, answer) = oneshot::channel();
+
+        if self.events.send(WorldEvent::CompleteCommand(command.to_string(), reply)).is_err() {
+            return Ok((pos, vec![]))
+        }
+
+        let (start, matches) = answer.blocking_recv().unwrap_or((command.len(), vec![]));
+        Ok((start + line.len() - command.len(), matches))
+    }
+}
+
+impl Hinter for CommandHelper {
+    type Hint = String;
+}
+
+impl Highlighter for CommandHelper {}
+
+impl Validator for CommandHelper {}
+
+impl Helper for CommandHelper {}
+
+// reads commands from stdin and runs them as the console, with line editing when stdin is a terminal
+pub struct Console {
+    // the terminal settings from before the editor put it in raw mode, the editor is still waiting for a line when the server stops
+    #[cfg(unix)]
+    terminal: Option<nix::sys::termios::Termios>
+}
+
+impl Console {
+    pub fn spawn(events: mpsc::UnboundedSender<WorldEvent>) -> Self {
+        let interactive = io::stdin().is_terminal() && io::stdout().is_terminal();
+
+        #[cfg(unix)]
+        let terminal = interactive.then(|| nix::sys::termios::tcgetattr(io::stdin()).ok()).flatten();
+
+        thread::spawn(move || match interactive {
+            true => read_interactive(events),
+            false => read_lines(events)
+        });
+
+        Self {
+            #[cfg(unix)]
+            terminal
+        }
+    }
+}
+
+impl Drop for Console {
+    fn drop(&mut self) {
+        log::set_printer(None);
+
+        #[cfg(unix)]
+        if let Some(terminal) = &self.terminal {
+            let _ = nix::sys::termios::tcsetattr(io::stdin(), nix::sys::termios::SetArg::TCSANOW, terminal);
+            // the prompt is still on the last line
+            println!();
+        }
+    }
+}
+
+fn submit(events: &mpsc::UnboundedSender<WorldEvent>, line: &str) -> bool {
+    let command = line.trim();
+    let command = command.strip_prefix('/').unwrap_or(command);
+
+    !command.is_empty() && events.send(WorldEvent::ConsoleCommand(command.to_string())).is_ok()
+}
+
+// stdin is a pipe or a file, every line is a command and nothing is echoed
+fn read_lines(events: mpsc::UnboundedSender<WorldEvent>) {
+    for line in io::stdin().lock().lines() {
+        let Ok(line) = line else {
+            break
+        };
+
+        submit(&events, &line);
+    }
+}
+
+fn read_interactive(events: mpsc::UnboundedSender<WorldEvent>) {
+    let config = Config::builder()
+        .max_history_size(HISTORY_SIZE)
+        .and_then(|config| config.history_ignore_dups(true))
+        .map(|config| config.completion_type(CompletionType::List).build());
+
+    let mut editor = match config.and_then(Editor::with_config) {
+        Ok(editor) => editor,
+        Err(error) => {
+            error!("failed to open the console, reading plain lines instead\n{}", error);
+            return read_lines(events)
+        }
+    };
+
+    editor.set_helper(Some(CommandHelper { events: events.clone() }));
+
+    // the printer writes straight to the terminal while no line is being read
+    match editor.create_external_printer() {
+        Ok(printer) => log::set_printer(Some(Box::new(printer))),
+        Err(error) => error!("failed to print above the console prompt\n{}", error)
+    }
+
+    loop {
+        match editor.readline(PROMPT) {
+            Ok(line) => {
+                if submit(&events, &line) {
+                    let _ = editor.add_history_entry(line.trim());
+                }
+            }
+            // ctrl-c never reaches the signal handler while the terminal is in raw mode
+            Err(ReadlineError::Interrupted) => {
+                let _ = events.send(WorldEvent::Shutdown);
+                break
+            }
+            Err(ReadlineError::Eof) => break,
+            Err(error) => {
+                error!("the console stopped reading\n{}", error);
+                break
+            }
+        }
+    }
+
+    log::set_printer(None);
+}
@@ -1,11 +1,15 @@
 use bytes::{BytesMut, Buf, BufMut};
-use paris::{log};
+use crate::log;
 use tokio::net::TcpStream;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 
 use crate::protocol::{DecodeResult, DecodeError, Decode, Encode, Packet};
 use crate::types::VarI32;
 
+// the largest frame vanilla accepts, the length prefix can be at most three bytes long
+const MAX_FRAME_SIZE: i32 = 2097151;
+
 struct Receiver {
     buffer: BytesMut
 }
@@ -17,18 +21,51 @@ impl Receiver {
         }
     }
 
-    fn poll_receive<T: Decode>(&mut self) -> DecodeResult<T> {
+    fn poll_frame(&mut self) -> DecodeResult<BytesMut> {
         let size = VarI32::decode(&mut &self.buffer[..])?;
         let size_len = size.len();
-        let size = i32::from(size) as usize;
+        let size = i32::from(size);
+
+        // checked before anything more gets buffered for the frame
+        if !(0..=MAX_FRAME_SIZE).contains(&size) {
+            return Err(DecodeError::InvalidData("frame length out of range"))
+        }
+
+        let size = size as usize;
 
-        if size > self.buffer.len() {
+        if size_len + size > self.buffer.len() {
             return Err(DecodeError::UnexpectedEOF)
         }
 
         self.buffer.advance(size_len);
 
-        T::decode(&mut &self.buffer.split_to(size)[..])
+        Ok(self.buffer.split_to(size))
+    }
+
+    async fn receive_frame(&mut self, stream: &mut (impl AsyncRead + Unpin)) -> DecodeResult<BytesMut> {
+        loop {
+            match self.poll_frame() {
+                Ok(frame) => return Ok(frame),
+                Err(DecodeError::UnexpectedEOF) => (),
+                Err(error) => return Err(error)
+            }
+
+            // a reset connection is as good as a closed one
+            let read_count = stream.read_buf(&mut self.buffer).await.unwrap_or(0);
+            if read_count == 0 {
+                return Err(DecodeError::UnexpectedEOF);
+            }
+        }
+    }
+
+    async fn receive<T: Decode + Packet>(&mut self, stream: &mut (impl AsyncRead + Unpin)) -> DecodeResult<T> {
+        let frame = self.receive_frame(stream).await?;
+        let packet = T::decode(&mut &frame[..]).map_err(|error| match error {
+            DecodeError::UnexpectedEOF => DecodeError::InvalidData("truncated packet"),
+            error => error
+        })?;
+        log!("  <blue>C</> -> <green>S</> : {}", packet.name());
+        Ok(packet)
     }
 }
 
@@ -42,6 +79,27 @@ impl Sender {
             buffer: BytesMut::new()
         }
     }
+
+    fn frame<T: Encode + Packet>(&mut self, packet: &T) -> BytesMut {
+        let packet_len = packet.encode((&mut self.buffer).writer());
+        let packet_len_var = VarI32::from(packet_len as i32);
+
+        self.buffer.put_bytes(0, packet_len_var.len());
+        self.buffer.copy_within(0..packet_len, packet_len_var.len());
+        packet_len_var.encode(&mut self.buffer[..]);
+
+        self.buffer.split()
+    }
+
+    async fn send<T: Encode + Packet>(&mut self, stream: &mut (impl AsyncWrite + Unpin), packet: T) {
+        let out = self.frame(&packet);
+        stream.write_all(&out).await.unwrap();
+        log!("  <green>S</> -> <blue>C</> : {}", packet.name());
+    }
+}
+
+pub fn frame<T: Encode + Packet>(packet: &T) -> BytesMut {
+    Sender::new().frame(packet)
 }
 
 pub struct Interface {
@@ -60,37 +118,50 @@ impl Interface {
     }
 
     pub async fn receive<T: Decode + Packet>(&mut self) -> DecodeResult<T> {
-        loop {
-            match self.receiver.poll_receive::<T>() {
-                Ok(packet) => {
-                    log!("  <blue>C</> -> <green>S</> : {}", packet.name());
-                    return Ok(packet)
-                },
-                Err(DecodeError::UnexpectedEOF) => ()
-            }
-
-            let read_count = self.stream.read_buf(&mut self.receiver.buffer).await.unwrap();
-            if read_count == 0 {
-                return Err(DecodeError::UnexpectedEOF);
-            }
-        }
+        self.receiver.receive(&mut self.stream).await
     }
 
     pub async fn send<T: Encode + Packet>(&mut self, packet: T) {
-        let packet_len = packet.encode((&mut self.sender.buffer).writer());
-        let packet_len_var = VarI32::from(packet_len as i32);
-        
-        self.sender.buffer.put_bytes(0, packet_len_var.len());
-        self.sender.buffer.copy_within(0..packet_len, packet_len_var.len());
-        packet_len_var.encode(&mut self.sender.buffer[..]);
-        
-        let out = self.sender.buffer.split();
-        self.stream.write_all(&out).await.unwrap();
-        log!("  <green>S</> -> <blue>C</> : {}", packet.name());
+        self.sender.send(&mut self.stream, packet).await
     }
 
     pub async fn disconnect(&mut self) {
         self.stream.shutdown().await.unwrap();
         log!("connection aborted or smth");
     }
-}
\ No newline at end of file
+
+    pub fn into_split(self) -> (InterfaceReader, InterfaceWriter) {
+        let (read_half, write_half) = self.stream.into_split();
+
+        (
+            InterfaceReader { stream: read_half, receiver: self.receiver },
+            InterfaceWriter { stream: write_half }
+        )
+    }
+}
+
+pub struct InterfaceReader {
+    stream: OwnedReadHalf,
+    receiver: Receiver
+}
+
+impl InterfaceReader {
+    pub async fn receive<T: Decode + Packet>(&mut self) -> DecodeResult<T> {
+        self.receiver.receive(&mut self.stream).await
+    }
+}
+
+pub struct InterfaceWriter {
+    stream: OwnedWriteHalf
+}
+
+impl InterfaceWriter {
+    pub async fn send_frame(&mut self, frame: &[u8]) -> std::io::Result<()> {
+        self.stream.write_all(frame).await
+    }
+
+    pub async fn disconnect(&mut self) {
+        // the peer may already be gone, there is nothing left to tell it
+        let _ = self.stream.shutdown().await;
+    }
+}
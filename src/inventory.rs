@@ -0,0 +1,65 @@
+use crate::nbt::{Compound, Tag};
+
+// the hotbar in slots 0 to 8, then the rest of the main inventory
+pub const INVENTORY_SIZE: usize = 36;
+// FIXME: there is no item registry to look the real stack size up in, so everything stacks to 64
+pub const MAX_STACK_SIZE: u32 = 64;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ItemStack {
+    // with the namespace
+    pub id: String,
+    pub count: u8
+}
+
+pub struct Inventory {
+    slots: Vec<Option<ItemStack>>
+}
+
+impl Default for Inventory {
+    fn default() -> Self {
+        Self { slots: vec![None; INVENTORY_SIZE] }
+    }
+}
+
+impl Inventory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn slot(&self, slot: usize) -> Option<&ItemStack> {
+        self.slots.get(slot)?.as_ref()
+    }
+
+    // tops up the stacks of the item first and then takes empty slots, returns how many did not fit
+    pub fn add(&mut self, id: &str, mut count: u32) -> u32 {
+        for stack in self.slots.iter_mut().flatten().filter(|stack| stack.id == id) {
+            let added = count.min(MAX_STACK_SIZE.saturating_sub(stack.count as u32));
+            stack.count += added as u8;
+            count -= added;
+        }
+
+        for slot in self.slots.iter_mut().filter(|slot| slot.is_none()) {
+            if count == 0 {
+                break
+            }
+
+            let added = count.min(MAX_STACK_SIZE);
+            *slot = Some(ItemStack { id: id.to_string(), count: added as u8 });
+            count -= added;
+        }
+
+        count
+    }
+
+    // the Inventory list of the player data
+    pub fn to_nbt(&self) -> Tag {
+        Tag::List(self.slots.iter().enumerate()
+            .filter_map(|(slot, stack)| Some((slot, stack.as_ref()?)))
+            .map(|(slot, stack)| Tag::Compound(Compound::new()
+                .with("Slot", Tag::Byte(slot as i8))
+                .with("id", Tag::String(stack.id.clone()))
+                .with("Count", Tag::Byte(stack.count as i8))))
+            .collect())
+    }
+}
@@ -1,7 +1,18 @@
-pub mod interface;
+mod interface;
 pub mod types;
 pub mod packets;
 pub mod log;
 pub mod macros;
 pub mod protocol;
-pub mod world;
\ No newline at end of file
+pub mod world;
+pub mod nbt;
+pub mod player;
+pub mod plugin;
+pub mod text;
+pub mod connection;
+pub mod block;
+pub mod chat;
+pub mod command;
+pub mod console;
+pub mod inventory;
+pub mod registry;
\ No newline at end of file
@@ -1,15 +1,64 @@
+use std::sync::Mutex;
+
+use rustyline::ExternalPrinter;
+
+// set while the console reads a line, everything logged in the meantime is printed above the prompt instead of through it
+static PRINTER: Mutex<Option<Box<dyn ExternalPrinter + Send>>> = Mutex::new(None);
+
+pub fn set_printer(printer: Option<Box<dyn ExternalPrinter + Send>>) {
+    *PRINTER.lock().unwrap() = printer;
+}
+
+// the paris color keys are replaced here, errors go to stderr unless the console prints them
+pub fn write(message: String, error: bool) {
+    let message = paris::formatter::colorize_string(message);
+
+    if let Some(printer) = PRINTER.lock().unwrap().as_mut() {
+        if printer.print(message.clone()).is_ok() {
+            return
+        }
+    }
+
+    match error {
+        true => eprintln!("{}", message),
+        false => println!("{}", message)
+    }
+}
+
+// the same as the paris macros of the same names, but they leave the console prompt alone
+#[macro_export]
+macro_rules! log {
+    ($($arg: tt)*) => {
+        $crate::log::write(format!($($arg)*), false)
+    };
+}
+
+#[macro_export]
+macro_rules! info {
+    ($($arg: tt)*) => {
+        $crate::log::write(format!("<cyan><info></> {}", format!($($arg)*)), false)
+    };
+}
+
+#[macro_export]
+macro_rules! error {
+    ($($arg: tt)*) => {
+        $crate::log::write(format!("<red><cross></> {}", format!($($arg)*)), true)
+    };
+}
+
 #[macro_export]
 macro_rules! switch {
     ($expression: expr; $ok: literal $(,$arg: expr)*; $err: literal;) => {
         match $expression {
             Ok(value) => {
-                paris::info!($ok, $($arg),*);
+                $crate::info!($ok, $($arg),*);
                 value
             }
             Err(error) => {
-                paris::error!("{}\n{}", $err, error);
+                $crate::error!("{}\n{}", $err, error);
                 panic!();
             }
         }
     };
-}
\ No newline at end of file
+}
@@ -5,7 +5,7 @@ macro_rules! packets {
                 pub mod $bound {
                     use paste::paste;
                     use std::io::{Read, Write};
-                    use crate::{protocol::{self, Decode, DecodeResult, Encode}, types::*};
+                    use crate::{protocol::{self, Decode, DecodeError, DecodeResult, Encode}, types::*};
                     paste!{
                         $(
                             #[derive(Debug)]
@@ -14,6 +14,8 @@ macro_rules! packets {
                             ),*}
                         )*
 
+                        // packets are encoded as soon as they are built, boxing the ones carrying signatures would only add an allocation
+                        #[allow(clippy::large_enum_variant)]
                         #[derive(Debug)]
                         pub enum Packet {$(
                             $packet([<$packet Data>]),
@@ -23,21 +25,23 @@ macro_rules! packets {
                             fn decode(reader: &mut impl Read) -> DecodeResult<Self> {
                                 let id: i32 = VarI32::decode(reader)?.into();
                                 
-                                Ok(match id {
+                                match id {
                                     $(
-                                        $id => Self::$packet([<$packet Data>] {$(
-                                            $field: $type::decode(reader)?
-                                        ),*}),
+                                        $id => Ok(Self::$packet([<$packet Data>] {$(
+                                            $field: <$type>::decode(reader)?
+                                        ),*})),
                                     )*
-                                    _ => unreachable!()
-                                })
+                                    id => Err(DecodeError::UnknownPacket(id))
+                                }
                             }
                         }
 
                         impl Encode for Packet {
+                            // a namespace without packets never writes anything
+                            #[allow(unused_mut, unused_variables)]
                             fn encode(&self, mut writer: impl Write) -> usize {
-                                match self {$(
-                                    Self::$packet(_data) => {
+                                match *self {$(
+                                    Self::$packet(ref _data) => {
                                         VarI32::from($id).encode(&mut writer) $(+ _data.$field.encode(&mut writer))*
                                     },
                                 )*}
@@ -46,7 +50,7 @@ macro_rules! packets {
 
                         impl protocol::Packet for Packet {
                             fn name(&self) -> &'static str {
-                                match &self {$(
+                                match *self {$(
                                     Self::$packet(_) => stringify!($packet),
                                 )*}
                             }
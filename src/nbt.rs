@@ -0,0 +1,273 @@
+use std::collections::BTreeMap;
+use std::io::{self, Read, Write};
+
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+
+const MAX_DEPTH: usize = 512;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Tag {
+    End,
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    ByteArray(Vec<i8>),
+    String(String),
+    List(Vec<Tag>),
+    Compound(Compound),
+    IntArray(Vec<i32>),
+    LongArray(Vec<i64>)
+}
+
+impl Tag {
+    pub fn id(&self) -> u8 {
+        match self {
+            Self::End => 0,
+            Self::Byte(_) => 1,
+            Self::Short(_) => 2,
+            Self::Int(_) => 3,
+            Self::Long(_) => 4,
+            Self::Float(_) => 5,
+            Self::Double(_) => 6,
+            Self::ByteArray(_) => 7,
+            Self::String(_) => 8,
+            Self::List(_) => 9,
+            Self::Compound(_) => 10,
+            Self::IntArray(_) => 11,
+            Self::LongArray(_) => 12
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            Self::Byte(value) => Some(value as i64),
+            Self::Short(value) => Some(value as i64),
+            Self::Int(value) => Some(value as i64),
+            Self::Long(value) => Some(value),
+            _ => None
+        }
+    }
+
+    pub fn as_i32(&self) -> Option<i32> {
+        self.as_i64().map(|value| value as i32)
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            Self::Float(value) => Some(value as f64),
+            Self::Double(value) => Some(value),
+            _ => self.as_i64().map(|value| value as f64)
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(value) => Some(value),
+            _ => None
+        }
+    }
+
+    pub fn as_list(&self) -> Option<&[Tag]> {
+        match self {
+            Self::List(values) => Some(values),
+            _ => None
+        }
+    }
+
+    pub fn as_compound(&self) -> Option<&Compound> {
+        match self {
+            Self::Compound(compound) => Some(compound),
+            _ => None
+        }
+    }
+
+    fn read_payload(id: u8, reader: &mut impl Read, depth: usize) -> io::Result<Self> {
+        if depth > MAX_DEPTH {
+            return Err(invalid_data("nbt nested too deeply"))
+        }
+
+        Ok(match id {
+            0 => Self::End,
+            1 => Self::Byte(reader.read_i8()?),
+            2 => Self::Short(reader.read_i16::<BigEndian>()?),
+            3 => Self::Int(reader.read_i32::<BigEndian>()?),
+            4 => Self::Long(reader.read_i64::<BigEndian>()?),
+            5 => Self::Float(reader.read_f32::<BigEndian>()?),
+            6 => Self::Double(reader.read_f64::<BigEndian>()?),
+            7 => Self::ByteArray((0..read_len(reader)?).map(|_| reader.read_i8()).collect::<io::Result<_>>()?),
+            8 => Self::String(read_string(reader)?),
+            9 => {
+                let element_id = reader.read_u8()?;
+                let len = read_len(reader)?;
+
+                Self::List((0..len).map(|_| Self::read_payload(element_id, reader, depth + 1)).collect::<io::Result<_>>()?)
+            },
+            10 => Self::Compound(Compound::read_payload(reader, depth + 1)?),
+            11 => Self::IntArray((0..read_len(reader)?).map(|_| reader.read_i32::<BigEndian>()).collect::<io::Result<_>>()?),
+            12 => Self::LongArray((0..read_len(reader)?).map(|_| reader.read_i64::<BigEndian>()).collect::<io::Result<_>>()?),
+            _ => return Err(invalid_data("unknown nbt tag id"))
+        })
+    }
+
+    fn write_payload(&self, writer: &mut impl Write) -> io::Result<()> {
+        match self {
+            Self::End => Ok(()),
+            Self::Byte(value) => writer.write_i8(*value),
+            Self::Short(value) => writer.write_i16::<BigEndian>(*value),
+            Self::Int(value) => writer.write_i32::<BigEndian>(*value),
+            Self::Long(value) => writer.write_i64::<BigEndian>(*value),
+            Self::Float(value) => writer.write_f32::<BigEndian>(*value),
+            Self::Double(value) => writer.write_f64::<BigEndian>(*value),
+            Self::ByteArray(values) => {
+                writer.write_i32::<BigEndian>(values.len() as i32)?;
+                values.iter().try_for_each(|value| writer.write_i8(*value))
+            },
+            Self::String(value) => write_string(writer, value),
+            Self::List(values) => {
+                // an empty list has no element type, vanilla writes it as a list of TAG_End
+                writer.write_u8(values.first().map_or(0, Tag::id))?;
+                writer.write_i32::<BigEndian>(values.len() as i32)?;
+                values.iter().try_for_each(|value| value.write_payload(writer))
+            },
+            Self::Compound(compound) => compound.write_payload(writer),
+            Self::IntArray(values) => {
+                writer.write_i32::<BigEndian>(values.len() as i32)?;
+                values.iter().try_for_each(|value| writer.write_i32::<BigEndian>(*value))
+            },
+            Self::LongArray(values) => {
+                writer.write_i32::<BigEndian>(values.len() as i32)?;
+                values.iter().try_for_each(|value| writer.write_i64::<BigEndian>(*value))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Compound(pub BTreeMap<String, Tag>);
+
+impl Compound {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, name: &str, tag: Tag) -> Self {
+        self.insert(name, tag);
+        self
+    }
+
+    pub fn insert(&mut self, name: &str, tag: Tag) {
+        self.0.insert(name.to_string(), tag);
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Tag> {
+        self.0.remove(name)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Tag> {
+        self.0.get(name)
+    }
+
+    pub fn get_i32(&self, name: &str) -> Option<i32> {
+        self.get(name)?.as_i32()
+    }
+
+    pub fn get_i64(&self, name: &str) -> Option<i64> {
+        self.get(name)?.as_i64()
+    }
+
+    pub fn get_str(&self, name: &str) -> Option<&str> {
+        self.get(name)?.as_str()
+    }
+
+    pub fn get_list(&self, name: &str) -> Option<&[Tag]> {
+        self.get(name)?.as_list()
+    }
+
+    pub fn get_compound(&self, name: &str) -> Option<&Compound> {
+        self.get(name)?.as_compound()
+    }
+
+    fn read_payload(reader: &mut impl Read, depth: usize) -> io::Result<Self> {
+        let mut compound = Self::new();
+
+        loop {
+            let id = reader.read_u8()?;
+            if id == 0 {
+                return Ok(compound)
+            }
+
+            let name = read_string(reader)?;
+            compound.0.insert(name, Tag::read_payload(id, reader, depth)?);
+        }
+    }
+
+    fn write_payload(&self, writer: &mut impl Write) -> io::Result<()> {
+        for (name, tag) in &self.0 {
+            writer.write_u8(tag.id())?;
+            write_string(writer, name)?;
+            tag.write_payload(writer)?;
+        }
+
+        writer.write_u8(0)
+    }
+
+    pub fn read(reader: &mut impl Read) -> io::Result<(String, Self)> {
+        match read(reader)? {
+            (name, Tag::Compound(compound)) => Ok((name, compound)),
+            _ => Err(invalid_data("nbt root is not a compound"))
+        }
+    }
+
+    pub fn read_gzip(reader: impl Read) -> io::Result<(String, Self)> {
+        Self::read(&mut GzDecoder::new(reader))
+    }
+
+    pub fn write(&self, writer: &mut impl Write, name: &str) -> io::Result<()> {
+        writer.write_u8(10)?;
+        write_string(writer, name)?;
+        self.write_payload(writer)
+    }
+
+    pub fn write_gzip(&self, writer: impl Write, name: &str) -> io::Result<()> {
+        let mut encoder = GzEncoder::new(writer, Compression::default());
+        self.write(&mut encoder, name)?;
+        encoder.finish()?;
+        Ok(())
+    }
+}
+
+pub fn read(reader: &mut impl Read) -> io::Result<(String, Tag)> {
+    let id = reader.read_u8()?;
+    if id == 0 {
+        return Ok((String::new(), Tag::End))
+    }
+
+    let name = read_string(reader)?;
+    Ok((name, Tag::read_payload(id, reader, 0)?))
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn read_len(reader: &mut impl Read) -> io::Result<usize> {
+    usize::try_from(reader.read_i32::<BigEndian>()?)
+        .map_err(|_| invalid_data("negative nbt length"))
+}
+
+fn read_string(reader: &mut impl Read) -> io::Result<String> {
+    let mut buffer = vec![0; reader.read_u16::<BigEndian>()? as usize];
+    reader.read_exact(&mut buffer)?;
+
+    String::from_utf8(buffer).map_err(|_| invalid_data("nbt string is not utf-8"))
+}
+
+fn write_string(writer: &mut impl Write, value: &str) -> io::Result<()> {
+    // FIXME: NBT strings are modified UTF-8, which differs for '\0' and characters outside of the BMP
+    writer.write_u16::<BigEndian>(value.len() as u16)?;
+    writer.write_all(value.as_bytes())
+}
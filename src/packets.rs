@@ -5,6 +5,7 @@ packets!{
         serverbound {
             0x00 => Handshake { protocol_version: VarI32, server_address: String, server_port: u16, next_state: VarI32 }
         }
+        clientbound { }
     }
     status {
         serverbound {
@@ -21,18 +22,99 @@ packets!{
             0x00 => LoginStart { name: String, uuid: Option::<UUID> }
         }
         clientbound {
-            // FIXME: implement properties properly
-            //        https://wiki.vg/Protocol#Login_Success
-            0x02 => LoginSuccess { uuid: UUID, username: String, number_of_properties: VarI32 }
+            0x02 => LoginSuccess { uuid: UUID, username: String, properties: Vec::<ProfileProperty> }
         }
     }
     play {
+        serverbound {
+            0x00 => ConfirmTeleportation { teleport_id: VarI32 },
+            0x01 => QueryBlockEntityTag { transaction_id: VarI32, location: Pos },
+            0x02 => ChangeDifficulty { new_difficulty: u8 },
+            0x03 => MessageAcknowledgment { message_count: VarI32 },
+            0x04 => ChatCommand { command: String, timestamp: i64, salt: i64, argument_signatures: Vec::<ArgumentSignature>, message_count: VarI32, acknowledged: [u8; 3] },
+            0x05 => ChatMessage { message: String, timestamp: i64, salt: i64, signature: Option::<[u8; 256]>, message_count: VarI32, acknowledged: [u8; 3] },
+            0x06 => PlayerSession { session_id: UUID, expires_at: i64, public_key: Vec::<u8>, key_signature: Vec::<u8> },
+            0x07 => ClientCommand { action_id: VarI32 },
+            0x08 => ClientInformation { locale: String, view_distance: i8, chat_mode: VarI32, chat_colors: bool, displayed_skin_parts: u8, main_hand: VarI32, enable_text_filtering: bool, allow_server_listings: bool },
+            0x09 => CommandSuggestionsRequest { transaction_id: VarI32, text: String },
+            0x0a => ClickContainerButton { window_id: i8, button_id: i8 },
+            0x0b => ClickContainer { window_id: u8, state_id: VarI32, slot: i16, button: i8, mode: VarI32, changed_slots: Vec::<ChangedSlot>, carried_item: Slot },
+            0x0c => CloseContainer { window_id: u8 },
+            0x0d => PluginMessage { channel: Identifier, data: RemainingBytes },
+            0x0e => EditBook { slot: VarI32, entries: Vec::<String>, title: Option::<String> },
+            0x0f => QueryEntityTag { transaction_id: VarI32, entity_id: VarI32 },
+            0x10 => Interact { entity_id: VarI32, action: InteractAction, sneaking: bool },
+            0x11 => JigsawGenerate { location: Pos, levels: VarI32, keep_jigsaws: bool },
+            0x12 => KeepAlive { keep_alive_id: i64 },
+            0x13 => LockDifficulty { locked: bool },
+            0x14 => SetPlayerPosition { x: f64, feet_y: f64, z: f64, on_ground: bool },
+            0x15 => SetPlayerPositionAndRotation { x: f64, feet_y: f64, z: f64, yaw: f32, pitch: f32, on_ground: bool },
+            0x16 => SetPlayerRotation { yaw: f32, pitch: f32, on_ground: bool },
+            0x17 => SetPlayerOnGround { on_ground: bool },
+            0x18 => MoveVehicle { x: f64, y: f64, z: f64, yaw: f32, pitch: f32 },
+            0x19 => PaddleBoat { left_paddle_turning: bool, right_paddle_turning: bool },
+            0x1a => PickItem { slot: VarI32 },
+            0x1b => PlaceRecipe { window_id: i8, recipe: Identifier, make_all: bool },
+            0x1c => PlayerAbilities { flags: i8 },
+            0x1d => PlayerAction { status: VarI32, location: Pos, face: i8, sequence: VarI32 },
+            0x1e => PlayerCommand { entity_id: VarI32, action_id: VarI32, jump_boost: VarI32 },
+            0x1f => PlayerInput { sideways: f32, forward: f32, flags: u8 },
+            0x20 => Pong { id: i32 },
+            0x21 => ChangeRecipeBookSettings { book_id: VarI32, book_open: bool, filter_active: bool },
+            0x22 => SetSeenRecipe { recipe_id: Identifier },
+            0x23 => RenameItem { item_name: String },
+            0x24 => ResourcePack { result: VarI32 },
+            0x25 => SeenAdvancements { action: SeenAdvancementsAction },
+            0x26 => SelectTrade { selected_slot: VarI32 },
+            0x27 => SetBeaconEffect { primary_effect: Option::<VarI32>, secondary_effect: Option::<VarI32> },
+            0x28 => SetHeldItem { slot: i16 },
+            0x29 => ProgramCommandBlock { location: Pos, command: String, mode: VarI32, flags: i8 },
+            0x2a => ProgramCommandBlockMinecart { entity_id: VarI32, command: String, track_output: bool },
+            0x2b => SetCreativeModeSlot { slot: i16, clicked_item: Slot },
+            0x2c => ProgramJigsawBlock { location: Pos, name: Identifier, target: Identifier, pool: Identifier, final_state: String, joint_type: String },
+            0x2d => ProgramStructureBlock { location: Pos, action: VarI32, mode: VarI32, name: String, offset_x: i8, offset_y: i8, offset_z: i8, size_x: i8, size_y: i8, size_z: i8, mirror: VarI32, rotation: VarI32, metadata: String, integrity: f32, seed: VarI64, flags: i8 },
+            0x2e => UpdateSign { location: Pos, line_1: String, line_2: String, line_3: String, line_4: String },
+            0x2f => SwingArm { hand: VarI32 },
+            0x30 => TeleportToEntity { target_player: UUID },
+            0x31 => UseItemOn { hand: VarI32, location: Pos, face: VarI32, cursor_x: f32, cursor_y: f32, cursor_z: f32, inside_block: bool, sequence: VarI32 },
+            0x32 => UseItem { hand: VarI32, sequence: VarI32 }
+        }
         clientbound {
+            0x03 => SpawnPlayer { entity_id: VarI32, uuid: UUID, x: f64, y: f64, z: f64, yaw: u8, pitch: u8 },
+            0x0c => ChangeDifficulty { difficulty: u8, locked: bool },
+            0x0f => CommandSuggestionsResponse { transaction_id: VarI32, start: VarI32, length: VarI32, matches: Vec::<Suggestion> },
+            0x10 => Commands { nodes: Vec::<CommandNode>, root_index: VarI32 },
             0x1a => Disconnect { reason: String },
+            0x1b => DisguisedChatMessage { message: String, chat_type: VarI32, chat_type_name: String, target_name: Option::<String> },
+            0x1c => EntityEvent { entity_id: i32, status: i8 },
+            0x1e => UnloadChunk { chunk_x: i32, chunk_z: i32 },
+            0x1f => GameEvent { event: u8, value: f32 },
+            0x23 => KeepAlive { keep_alive_id: i64 },
+            0x24 => ChunkDataAndUpdateLight { chunk_x: i32, chunk_z: i32, heightmaps: Nbt, data: Vec::<u8>, block_entities: Vec::<ChunkBlockEntity>, light: LightData },
+            0x27 => UpdateLight { chunk_x: VarI32, chunk_z: VarI32, light: LightData },
             // FIXME: implement nbt AND death position properly
             0x28 => Login { entity_id: i32, is_hardcore: bool, gamemode: u8, previous_gamemode: i8, dimensions: Vec::<Identifier>, registry_codec: Nbt, dimension_type: Identifier, dimension_name: Identifier, hashed_seed: i64, max_players: VarI32, render_distance: VarI32, simulation_distance: VarI32, reduced_debug_info: bool, enable_respawn_screen: bool, is_debug: bool, is_flat: bool, has_death_location: bool },
+            0x2b => UpdateEntityPosition { entity_id: VarI32, delta_x: i16, delta_y: i16, delta_z: i16, on_ground: bool },
+            0x2c => UpdateEntityPositionAndRotation { entity_id: VarI32, delta_x: i16, delta_y: i16, delta_z: i16, yaw: u8, pitch: u8, on_ground: bool },
+            0x2d => UpdateEntityRotation { entity_id: VarI32, yaw: u8, pitch: u8, on_ground: bool },
+            0x35 => PlayerChatMessage { sender: UUID, index: VarI32, message_signature: Option::<[u8; 256]>, message: String, timestamp: i64, salt: i64, previous_messages: Vec::<PreviousMessage>, unsigned_content: Option::<String>, filter: FilterMask, chat_type: VarI32, network_name: String, network_target_name: Option::<String> },
+            0x39 => PlayerInfoRemove { players: Vec::<UUID> },
+            0x3a => PlayerInfoUpdate { players: PlayerInfoEntries },
+            0x3c => SynchronizePlayerPosition { x: f64, y: f64, z: f64, yaw: f32, pitch: f32, flags: i8, teleport_id: VarI32 },
+            0x3e => RemoveEntities { entity_ids: Vec::<VarI32> },
+            0x41 => Respawn { dimension_type: Identifier, dimension_name: Identifier, hashed_seed: i64, gamemode: u8, previous_gamemode: i8, is_debug: bool, is_flat: bool, data_kept: u8, has_death_location: bool },
+            0x42 => SetHeadRotation { entity_id: VarI32, head_yaw: u8 },
+            0x45 => ServerData { motd: Option::<String>, icon: Option::<String>, enforces_secure_chat: bool },
             0x4d => SetHeldItem { slot: i8 },
+            0x4e => SetCenterChunk { chunk_x: VarI32, chunk_z: VarI32 },
+            0x4f => SetRenderDistance { view_distance: VarI32 },
             0x50 => SetDefaultSpawnPosition { location: Pos, angle: f32 },
+            0x54 => SetEntityVelocity { entity_id: VarI32, velocity_x: i16, velocity_y: i16, velocity_z: i16 },
+            0x5c => SetSimulationDistance { simulation_distance: VarI32 },
+            0x5e => UpdateTime { world_age: i64, time_of_day: i64 },
+            0x64 => SystemChatMessage { content: String, overlay: bool },
+            0x65 => SetTabListHeaderAndFooter { header: String, footer: String },
+            0x68 => TeleportEntity { entity_id: VarI32, x: f64, y: f64, z: f64, yaw: u8, pitch: u8, on_ground: bool },
             // FIXME: Implement properly
             0x6d => UpdateRecipes { recipes_count: VarI32 },
             // FIXME: Implement properly
@@ -0,0 +1,243 @@
+use std::fmt::{self, Display, Formatter};
+use std::time::{Duration, Instant};
+
+use bytes::Bytes;
+use crate::log;
+use tokio::{sync::mpsc, task::JoinHandle, time};
+
+use crate::{chat::session::{LastSeenMessages, SignedSession}, connection::{Connection, ConnectionWriter, Play}, inventory::Inventory, nbt::{Compound, Tag}, packets::play::{clientbound::{self, KeepAliveData, SynchronizePlayerPositionData}, serverbound::ClientInformationData}, protocol::{DecodeError, Packet}, types::{PlayerInfo, ProfileProperty, UUID}, world::{chunk::ChunkPos, view::ChunkView, WorldEvent, DATA_VERSION}};
+
+pub const TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug)]
+pub enum DisconnectReason {
+    Quit,
+    Malformed(DecodeError),
+    TimedOut,
+    Kicked(String)
+}
+
+impl Display for DisconnectReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Quit => write!(f, "quit"),
+            Self::Malformed(error) => write!(f, "malformed packet: {}", error),
+            Self::TimedOut => write!(f, "timed out"),
+            Self::Kicked(message) => write!(f, "kicked: {}", message)
+        }
+    }
+}
+
+pub struct Player {
+    pub entity_id: i32,
+    pub uuid: UUID,
+    pub username: String,
+    pub properties: Vec<ProfileProperty>,
+    pub gamemode: u8,
+    // whether the player shows up in the tab list
+    pub listed: bool,
+    // a text component, the username is shown without one
+    pub display_name: Option<String>,
+    // in milliseconds, from the keep alives
+    pub latency: i32,
+    // which commands the player may use, 0 for none of the restricted ones
+    pub permission_level: u8,
+    // FIXME: only kept on the server, the client never hears about it since items have no protocol ids yet
+    pub inventory: Inventory,
+    // only in secure chat, once the trust root vouched for the key
+    pub chat_session: Option<SignedSession>,
+    pub last_seen: LastSeenMessages,
+    pub client_information: Option<ClientInformationData>,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub on_ground: bool,
+    // whether the last move left the player up in the air without falling, and for how many ticks it has been
+    pub floating: bool,
+    pub airborne_ticks: u32,
+    pub view: ChunkView,
+    // what was last handed to the saver, players change too many fields for a flag to keep up
+    saved: Option<Compound>,
+    teleport_id: i32,
+    // the teleport the client has not confirmed yet, its movement is ignored until it does
+    pending_teleport: Option<i32>,
+    // the keep alive the client has not answered yet and when it went out
+    pending_keep_alive: Option<(i64, Instant)>,
+    outgoing: mpsc::UnboundedSender<Bytes>,
+    reader: JoinHandle<()>
+}
+
+impl Player {
+    pub fn spawn(entity_id: i32, uuid: UUID, username: String, properties: Vec<ProfileProperty>, connection: Connection<Play>, events: mpsc::UnboundedSender<WorldEvent>) -> Self {
+        let (mut reader, mut writer) = connection.into_split();
+        let (outgoing, mut frames) = mpsc::unbounded_channel::<Bytes>();
+
+        tokio::spawn(async move {
+            while let Some(frame) = frames.recv().await {
+                if writer.send_frame(&frame).await.is_err() {
+                    break;
+                }
+            }
+
+            writer.disconnect().await;
+        });
+
+        let reader = tokio::spawn(async move {
+            let reason = loop {
+                match time::timeout(TIMEOUT, reader.receive()).await {
+                    Ok(Ok(packet)) => {
+                        let _ = events.send(WorldEvent::Packet(entity_id, packet));
+                    },
+                    Ok(Err(DecodeError::UnexpectedEOF)) => break DisconnectReason::Quit,
+                    Ok(Err(error)) => break DisconnectReason::Malformed(error),
+                    Err(_) => break DisconnectReason::TimedOut
+                }
+            };
+
+            let _ = events.send(WorldEvent::Disconnect(entity_id, reason));
+        });
+
+        Self {
+            entity_id,
+            uuid,
+            username,
+            properties,
+            gamemode: 0,
+            listed: true,
+            display_name: None,
+            latency: 0,
+            permission_level: 0,
+            inventory: Inventory::new(),
+            chat_session: None,
+            last_seen: LastSeenMessages::new(),
+            client_information: None,
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+            yaw: 0.0,
+            pitch: 0.0,
+            on_ground: false,
+            view: ChunkView::new(ChunkPos::new(0, 0), 0),
+            floating: false,
+            airborne_ticks: 0,
+            saved: None,
+            teleport_id: 0,
+            pending_teleport: None,
+            pending_keep_alive: None,
+            outgoing,
+            reader
+        }
+    }
+
+    pub fn chunk(&self) -> ChunkPos {
+        ChunkPos::containing(self.x, self.z)
+    }
+
+    // moves the player and tells its client where it is now
+    pub fn teleport(&mut self, x: f64, y: f64, z: f64, yaw: f32, pitch: f32) {
+        (self.x, self.y, self.z, self.yaw, self.pitch) = (x, y, z, yaw, pitch);
+        self.teleport_id = self.teleport_id.wrapping_add(1);
+        self.pending_teleport = Some(self.teleport_id);
+
+        self.send(clientbound::Packet::SynchronizePlayerPosition(SynchronizePlayerPositionData {
+            x,
+            y,
+            z,
+            yaw,
+            pitch,
+            flags: 0,
+            teleport_id: self.teleport_id.into()
+        }));
+    }
+
+    // only the latest teleport counts, confirming an older one still leaves the player waiting
+    pub fn confirm_teleport(&mut self, teleport_id: i32) {
+        if self.pending_teleport == Some(teleport_id) {
+            self.pending_teleport = None;
+        }
+    }
+
+    pub fn is_teleporting(&self) -> bool {
+        self.pending_teleport.is_some()
+    }
+
+    // the id the client has to confirm the last teleport with
+    pub fn pending_teleport(&self) -> Option<i32> {
+        self.pending_teleport
+    }
+
+    pub fn keep_alive(&mut self, keep_alive_id: i64) {
+        self.pending_keep_alive = Some((keep_alive_id, Instant::now()));
+        self.send(clientbound::Packet::KeepAlive(KeepAliveData { keep_alive_id }));
+    }
+
+    // smoothed like vanilla so a single slow answer does not make the bars jump, false for an id that was never sent
+    pub fn confirm_keep_alive(&mut self, keep_alive_id: i64) -> bool {
+        match self.pending_keep_alive {
+            Some((id, sent)) if id == keep_alive_id => {
+                let sample = sent.elapsed().as_millis().min(i32::MAX as u128) as i32;
+                self.latency = (self.latency * 3 + sample) / 4;
+                self.pending_keep_alive = None;
+                true
+            }
+            _ => false
+        }
+    }
+
+    // everything the tab list shows about the player
+    pub fn info(&self) -> PlayerInfo {
+        PlayerInfo {
+            add_player: Some((self.username.clone(), self.properties.clone())),
+            initialize_chat: Some(self.chat_session.as_ref().map(|session| session.data.clone())),
+            gamemode: Some((self.gamemode as i32).into()),
+            listed: Some(self.listed),
+            latency: Some(self.latency.into()),
+            display_name: Some(self.display_name.clone()),
+            ..PlayerInfo::new(self.uuid)
+        }
+    }
+
+    pub fn send(&self, packet: clientbound::Packet) {
+        self.send_frame(ConnectionWriter::<Play>::frame(&packet));
+        log!("  <green>S</> -> <blue>C</> : {}", packet.name());
+    }
+
+    pub fn send_frame(&self, frame: Bytes) {
+        // the writer only stops once the connection is gone, the reader reports that on its own
+        let _ = self.outgoing.send(frame);
+    }
+
+    // the player data when it changed since the last call, none when there is nothing to write
+    pub fn changed_nbt(&mut self) -> Option<Compound> {
+        let nbt = self.to_nbt();
+
+        if self.saved.as_ref() == Some(&nbt) {
+            return None
+        }
+
+        self.saved = Some(nbt.clone());
+        Some(nbt)
+    }
+
+    pub fn to_nbt(&self) -> Compound {
+        let uuid = self.uuid.0;
+
+        Compound::new()
+            .with("DataVersion", Tag::Int(DATA_VERSION))
+            .with("UUID", Tag::IntArray(vec![(uuid >> 96) as i32, (uuid >> 64) as i32, (uuid >> 32) as i32, uuid as i32]))
+            .with("playerGameType", Tag::Int(self.gamemode as i32))
+            .with("Pos", Tag::List(vec![Tag::Double(self.x), Tag::Double(self.y), Tag::Double(self.z)]))
+            .with("Rotation", Tag::List(vec![Tag::Float(self.yaw), Tag::Float(self.pitch)]))
+            .with("OnGround", Tag::Byte(self.on_ground as i8))
+            .with("Dimension", Tag::String("minecraft:overworld".to_string()))
+            .with("Inventory", self.inventory.to_nbt())
+    }
+}
+
+impl Drop for Player {
+    fn drop(&mut self) {
+        self.reader.abort();
+    }
+}
@@ -0,0 +1,7 @@
+use crate::player::{DisconnectReason, Player};
+
+pub trait Plugin: Send {
+    fn on_join(&mut self, _player: &Player) {}
+
+    fn on_quit(&mut self, _player: &Player, _reason: &DisconnectReason) {}
+}
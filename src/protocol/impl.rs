@@ -2,18 +2,31 @@ use std::io::{Read, Write};
 
 use byteorder::{ReadBytesExt, BigEndian, WriteBytesExt};
 
-use crate::types::{VarI32, UUID, Nbt, Identifier, Pos};
+use crate::{command::argument::{ArgumentParser, StringKind}, nbt, types::{VarI32, VarI64, UUID, Nbt, Identifier, Pos, RemainingBytes, ItemStack, ChangedSlot, ArgumentSignature, InteractAction, SeenAdvancementsAction, ChunkBlockEntity, LightData, ProfileProperty, PlayerInfo, PlayerInfoEntries, ChatSession, PreviousMessage, FilterMask, CommandNode, CommandNodeKind, Suggestion}};
 
 use super::{Decode, DecodeResult, DecodeError, Encode};
 
+// 32767 UTF-16 code units take up to 3 bytes each
+const MAX_STRING_LEN: usize = 32767 * 3;
+
+fn decode_len(reader: &mut impl Read) -> DecodeResult<usize> {
+    usize::try_from(i32::from(VarI32::decode(reader)?))
+        .map_err(|_| DecodeError::InvalidData("negative length"))
+}
+
 impl Decode for String {
     fn decode(reader: &mut impl Read) -> DecodeResult<Self> where Self: Sized {
-        let len = i32::from(VarI32::decode(reader)?) as usize;
+        let len = decode_len(reader)?;
+        if len > MAX_STRING_LEN {
+            return Err(DecodeError::InvalidData("string too long"))
+        }
+
         let mut buffer = vec![0; len];
         reader.read_exact(&mut buffer)
             .map_err(|_| DecodeError::UnexpectedEOF)?;
 
-        Ok(String::from_utf8(buffer).unwrap())
+        String::from_utf8(buffer)
+            .map_err(|_| DecodeError::InvalidData("string is not utf-8"))
     }
 }
 
@@ -26,7 +39,8 @@ impl Decode for u16 {
 
 impl Decode for i64 {
     fn decode(reader: &mut impl Read) -> DecodeResult<Self> where Self: Sized {
-        Ok(reader.read_i64::<BigEndian>().unwrap())
+        reader.read_i64::<BigEndian>()
+            .map_err(|_| DecodeError::UnexpectedEOF)
     }
 }
 
@@ -44,7 +58,7 @@ impl Decode for VarI32 {
             }
         }
 
-        unreachable!()
+        Err(DecodeError::InvalidData("VarI32 too long"))
     }
 }
 
@@ -89,7 +103,8 @@ impl Encode for u16 {
 
 impl Decode for bool {
     fn decode(reader: &mut impl Read) -> DecodeResult<Self> where Self: Sized {
-        Ok(reader.read_u8().unwrap() != 0)
+        Ok(reader.read_u8()
+            .map_err(|_| DecodeError::UnexpectedEOF)? != 0)
     }
 }
 
@@ -124,7 +139,8 @@ impl<T: Encode> Encode for Option<T> {
 
 impl Decode for u128 {
     fn decode(reader: &mut impl Read) -> DecodeResult<Self> where Self: Sized {
-        Ok(reader.read_u128::<BigEndian>().unwrap())
+        reader.read_u128::<BigEndian>()
+            .map_err(|_| DecodeError::UnexpectedEOF)
     }
 }
 
@@ -137,7 +153,8 @@ impl Encode for u128 {
 
 impl Decode for i32 {
     fn decode(reader: &mut impl Read) -> DecodeResult<Self> where Self: Sized {
-        Ok(reader.read_i32::<BigEndian>().unwrap())
+        reader.read_i32::<BigEndian>()
+            .map_err(|_| DecodeError::UnexpectedEOF)
     }
 }
 
@@ -150,7 +167,8 @@ impl Encode for i32 {
 
 impl Decode for f32 {
     fn decode(reader: &mut impl Read) -> DecodeResult<Self> where Self: Sized {
-        Ok(reader.read_f32::<BigEndian>().unwrap())
+        reader.read_f32::<BigEndian>()
+            .map_err(|_| DecodeError::UnexpectedEOF)
     }
 }
 
@@ -163,7 +181,8 @@ impl Encode for f32 {
 
 impl Decode for i8 {
     fn decode(reader: &mut impl Read) -> DecodeResult<Self> where Self: Sized {
-        Ok(reader.read_i8().unwrap())
+        reader.read_i8()
+            .map_err(|_| DecodeError::UnexpectedEOF)
     }
 }
 
@@ -176,7 +195,8 @@ impl Encode for i8 {
 
 impl Decode for u8 {
     fn decode(reader: &mut impl Read) -> DecodeResult<Self> where Self: Sized {
-        Ok(reader.read_u8().unwrap())
+        reader.read_u8()
+            .map_err(|_| DecodeError::UnexpectedEOF)
     }
 }
 
@@ -213,8 +233,9 @@ impl<T: Encode> Encode for Vec<T> {
 
 impl<T: Decode> Decode for Vec<T> {
     fn decode(reader: &mut impl Read) -> DecodeResult<Self> where Self: Sized {
-        let len = i32::from(VarI32::decode(reader)?) as usize;
-        let mut out = Vec::with_capacity(len);
+        let len = decode_len(reader)?;
+        // the length comes from the client, so don't trust it with an allocation
+        let mut out = Vec::with_capacity(len.min(1024));
         
         for _ in 0..len {
             out.push(T::decode(reader)?)
@@ -231,9 +252,30 @@ impl Encode for Nbt {
     }
 }
 
+// keeps a copy of everything read through it
+struct Recorder<'a, R: Read> {
+    reader: &'a mut R,
+    bytes: Vec<u8>
+}
+
+impl<R: Read> Read for Recorder<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let count = self.reader.read(buf)?;
+        self.bytes.extend_from_slice(&buf[..count]);
+        Ok(count)
+    }
+}
+
 impl Decode for Nbt {
-    fn decode(_reader: &mut impl Read) -> DecodeResult<Self> where Self: Sized {
-        unimplemented!()
+    fn decode(reader: &mut impl Read) -> DecodeResult<Self> where Self: Sized {
+        let mut recorder = Recorder { reader, bytes: vec![] };
+
+        nbt::read(&mut recorder).map_err(|error| match error.kind() {
+            std::io::ErrorKind::UnexpectedEof => DecodeError::UnexpectedEOF,
+            _ => DecodeError::InvalidData("malformed nbt")
+        })?;
+
+        Ok(Self(recorder.bytes))
     }
 }
 
@@ -270,4 +312,596 @@ impl Decode for Pos {
             z: (data << 26 >> 38) as i32
         })
     }
-}
\ No newline at end of file
+}
+impl Decode for i16 {
+    fn decode(reader: &mut impl Read) -> DecodeResult<Self> where Self: Sized {
+        reader.read_i16::<BigEndian>()
+            .map_err(|_| DecodeError::UnexpectedEOF)
+    }
+}
+
+impl Encode for i16 {
+    fn encode(&self, mut writer: impl Write) -> usize {
+        writer.write_i16::<BigEndian>(*self).unwrap();
+        2
+    }
+}
+
+impl Decode for f64 {
+    fn decode(reader: &mut impl Read) -> DecodeResult<Self> where Self: Sized {
+        reader.read_f64::<BigEndian>()
+            .map_err(|_| DecodeError::UnexpectedEOF)
+    }
+}
+
+impl Encode for f64 {
+    fn encode(&self, mut writer: impl Write) -> usize {
+        writer.write_f64::<BigEndian>(*self).unwrap();
+        8
+    }
+}
+
+impl Decode for VarI64 {
+    fn decode(reader: &mut impl Read) -> DecodeResult<Self> {
+        let mut output: [u8; VarI64::MAX_LEN] = [0; VarI64::MAX_LEN];
+
+        for i in 0..output.len() {
+            let byte = reader.read_u8()
+                .map_err(|_| DecodeError::UnexpectedEOF)?;
+            output[i] = byte;
+
+            if byte & 0x80 == 0 {
+                return Ok(Self (output, i as u8 + 1));
+            }
+        }
+
+        Err(DecodeError::InvalidData("VarI64 too long"))
+    }
+}
+
+impl Encode for VarI64 {
+    fn encode(&self, mut writer: impl Write) -> usize {
+        writer.write_all(self.bytes()).unwrap();
+        self.len()
+    }
+}
+
+impl<const N: usize> Decode for [u8; N] {
+    fn decode(reader: &mut impl Read) -> DecodeResult<Self> where Self: Sized {
+        let mut buffer = [0; N];
+        reader.read_exact(&mut buffer)
+            .map_err(|_| DecodeError::UnexpectedEOF)?;
+
+        Ok(buffer)
+    }
+}
+
+impl<const N: usize> Encode for [u8; N] {
+    fn encode(&self, mut writer: impl Write) -> usize {
+        writer.write_all(self).unwrap();
+        N
+    }
+}
+
+impl Decode for RemainingBytes {
+    fn decode(reader: &mut impl Read) -> DecodeResult<Self> where Self: Sized {
+        let mut buffer = vec![];
+        reader.read_to_end(&mut buffer)
+            .map_err(|_| DecodeError::UnexpectedEOF)?;
+
+        Ok(Self(buffer))
+    }
+}
+
+impl Encode for RemainingBytes {
+    fn encode(&self, mut writer: impl Write) -> usize {
+        writer.write_all(&self.0).unwrap();
+        self.0.len()
+    }
+}
+
+impl Decode for ItemStack {
+    fn decode(reader: &mut impl Read) -> DecodeResult<Self> where Self: Sized {
+        Ok(Self {
+            item_id: VarI32::decode(reader)?,
+            count: i8::decode(reader)?,
+            nbt: Nbt::decode(reader)?
+        })
+    }
+}
+
+impl Encode for ItemStack {
+    fn encode(&self, mut writer: impl Write) -> usize {
+        self.item_id.encode(&mut writer)
+            + self.count.encode(&mut writer)
+            + self.nbt.encode(&mut writer)
+    }
+}
+
+impl Decode for ChangedSlot {
+    fn decode(reader: &mut impl Read) -> DecodeResult<Self> where Self: Sized {
+        Ok(Self {
+            slot: i16::decode(reader)?,
+            item: Option::<ItemStack>::decode(reader)?
+        })
+    }
+}
+
+impl Encode for ChangedSlot {
+    fn encode(&self, mut writer: impl Write) -> usize {
+        self.slot.encode(&mut writer) + self.item.encode(&mut writer)
+    }
+}
+
+impl Decode for ArgumentSignature {
+    fn decode(reader: &mut impl Read) -> DecodeResult<Self> where Self: Sized {
+        Ok(Self {
+            name: String::decode(reader)?,
+            signature: <[u8; 256]>::decode(reader)?
+        })
+    }
+}
+
+impl Encode for ArgumentSignature {
+    fn encode(&self, mut writer: impl Write) -> usize {
+        self.name.encode(&mut writer) + self.signature.encode(&mut writer)
+    }
+}
+
+impl Decode for InteractAction {
+    fn decode(reader: &mut impl Read) -> DecodeResult<Self> where Self: Sized {
+        Ok(match i32::from(VarI32::decode(reader)?) {
+            0 => Self::Interact { hand: VarI32::decode(reader)? },
+            1 => Self::Attack,
+            2 => Self::InteractAt {
+                target_x: f32::decode(reader)?,
+                target_y: f32::decode(reader)?,
+                target_z: f32::decode(reader)?,
+                hand: VarI32::decode(reader)?
+            },
+            _ => return Err(DecodeError::InvalidData("unknown interact action"))
+        })
+    }
+}
+
+impl Encode for InteractAction {
+    fn encode(&self, mut writer: impl Write) -> usize {
+        match self {
+            Self::Interact { hand } => VarI32::from(0).encode(&mut writer) + hand.encode(&mut writer),
+            Self::Attack => VarI32::from(1).encode(&mut writer),
+            Self::InteractAt { target_x, target_y, target_z, hand } => {
+                VarI32::from(2).encode(&mut writer)
+                    + target_x.encode(&mut writer)
+                    + target_y.encode(&mut writer)
+                    + target_z.encode(&mut writer)
+                    + hand.encode(&mut writer)
+            }
+        }
+    }
+}
+
+impl Decode for SeenAdvancementsAction {
+    fn decode(reader: &mut impl Read) -> DecodeResult<Self> where Self: Sized {
+        Ok(match i32::from(VarI32::decode(reader)?) {
+            0 => Self::OpenedTab(Identifier::decode(reader)?),
+            1 => Self::ClosedScreen,
+            _ => return Err(DecodeError::InvalidData("unknown seen advancements action"))
+        })
+    }
+}
+
+impl Encode for SeenAdvancementsAction {
+    fn encode(&self, mut writer: impl Write) -> usize {
+        match self {
+            Self::OpenedTab(tab_id) => VarI32::from(0).encode(&mut writer) + tab_id.encode(&mut writer),
+            Self::ClosedScreen => VarI32::from(1).encode(&mut writer)
+        }
+    }
+}
+
+impl Decode for ChunkBlockEntity {
+    fn decode(reader: &mut impl Read) -> DecodeResult<Self> where Self: Sized {
+        Ok(Self {
+            packed_xz: u8::decode(reader)?,
+            y: i16::decode(reader)?,
+            kind: VarI32::decode(reader)?,
+            data: Nbt::decode(reader)?
+        })
+    }
+}
+
+impl Encode for ChunkBlockEntity {
+    fn encode(&self, mut writer: impl Write) -> usize {
+        self.packed_xz.encode(&mut writer)
+            + self.y.encode(&mut writer)
+            + self.kind.encode(&mut writer)
+            + self.data.encode(&mut writer)
+    }
+}
+
+impl Decode for LightData {
+    fn decode(reader: &mut impl Read) -> DecodeResult<Self> where Self: Sized {
+        Ok(Self {
+            trust_edges: bool::decode(reader)?,
+            sky_light_mask: Vec::<i64>::decode(reader)?,
+            block_light_mask: Vec::<i64>::decode(reader)?,
+            empty_sky_light_mask: Vec::<i64>::decode(reader)?,
+            empty_block_light_mask: Vec::<i64>::decode(reader)?,
+            sky_light: Vec::<Vec<u8>>::decode(reader)?,
+            block_light: Vec::<Vec<u8>>::decode(reader)?
+        })
+    }
+}
+
+impl Encode for LightData {
+    fn encode(&self, mut writer: impl Write) -> usize {
+        self.trust_edges.encode(&mut writer)
+            + self.sky_light_mask.encode(&mut writer)
+            + self.block_light_mask.encode(&mut writer)
+            + self.empty_sky_light_mask.encode(&mut writer)
+            + self.empty_block_light_mask.encode(&mut writer)
+            + self.sky_light.encode(&mut writer)
+            + self.block_light.encode(&mut writer)
+    }
+}
+
+impl Decode for ProfileProperty {
+    fn decode(reader: &mut impl Read) -> DecodeResult<Self> where Self: Sized {
+        Ok(Self {
+            name: String::decode(reader)?,
+            value: String::decode(reader)?,
+            signature: Option::<String>::decode(reader)?
+        })
+    }
+}
+
+impl Encode for ProfileProperty {
+    fn encode(&self, mut writer: impl Write) -> usize {
+        self.name.encode(&mut writer)
+            + self.value.encode(&mut writer)
+            + self.signature.encode(&mut writer)
+    }
+}
+
+impl PlayerInfo {
+    fn decode(reader: &mut impl Read, actions: u8) -> DecodeResult<Self> {
+        let mut info = Self::new(UUID::decode(reader)?);
+
+        if actions & Self::ADD_PLAYER != 0 {
+            info.add_player = Some((String::decode(reader)?, Vec::<ProfileProperty>::decode(reader)?));
+        }
+        if actions & Self::INITIALIZE_CHAT != 0 {
+            info.initialize_chat = Some(Option::<ChatSession>::decode(reader)?);
+        }
+        if actions & Self::UPDATE_GAMEMODE != 0 {
+            info.gamemode = Some(VarI32::decode(reader)?);
+        }
+        if actions & Self::UPDATE_LISTED != 0 {
+            info.listed = Some(bool::decode(reader)?);
+        }
+        if actions & Self::UPDATE_LATENCY != 0 {
+            info.latency = Some(VarI32::decode(reader)?);
+        }
+        if actions & Self::UPDATE_DISPLAY_NAME != 0 {
+            info.display_name = Some(Option::<String>::decode(reader)?);
+        }
+
+        Ok(info)
+    }
+
+    fn encode(&self, mut writer: impl Write) -> usize {
+        let mut size = self.uuid.encode(&mut writer);
+
+        if let Some((name, properties)) = &self.add_player {
+            size += name.encode(&mut writer) + properties.encode(&mut writer);
+        }
+        if let Some(chat_session) = &self.initialize_chat {
+            size += chat_session.encode(&mut writer);
+        }
+        if let Some(gamemode) = &self.gamemode {
+            size += gamemode.encode(&mut writer);
+        }
+        if let Some(listed) = &self.listed {
+            size += listed.encode(&mut writer);
+        }
+        if let Some(latency) = &self.latency {
+            size += latency.encode(&mut writer);
+        }
+        if let Some(display_name) = &self.display_name {
+            size += display_name.encode(&mut writer);
+        }
+
+        size
+    }
+}
+
+impl Decode for PlayerInfoEntries {
+    fn decode(reader: &mut impl Read) -> DecodeResult<Self> where Self: Sized {
+        let actions = u8::decode(reader)?;
+        let len = decode_len(reader)?;
+        let mut entries = Vec::with_capacity(len.min(1024));
+
+        for _ in 0..len {
+            entries.push(PlayerInfo::decode(reader, actions)?);
+        }
+
+        Ok(Self(entries))
+    }
+}
+
+impl Encode for PlayerInfoEntries {
+    fn encode(&self, mut writer: impl Write) -> usize {
+        let actions = self.0.first().map_or(0, PlayerInfo::actions);
+        let mut size = actions.encode(&mut writer) + VarI32::from(self.0.len() as i32).encode(&mut writer);
+
+        for info in &self.0 {
+            size += info.encode(&mut writer);
+        }
+
+        size
+    }
+}
+
+impl Decode for ChatSession {
+    fn decode(reader: &mut impl Read) -> DecodeResult<Self> where Self: Sized {
+        Ok(Self {
+            session_id: UUID::decode(reader)?,
+            expires_at: i64::decode(reader)?,
+            public_key: Vec::<u8>::decode(reader)?,
+            key_signature: Vec::<u8>::decode(reader)?
+        })
+    }
+}
+
+impl Encode for ChatSession {
+    fn encode(&self, mut writer: impl Write) -> usize {
+        self.session_id.encode(&mut writer)
+            + self.expires_at.encode(&mut writer)
+            + self.public_key.encode(&mut writer)
+            + self.key_signature.encode(&mut writer)
+    }
+}
+
+impl Decode for PreviousMessage {
+    fn decode(reader: &mut impl Read) -> DecodeResult<Self> where Self: Sized {
+        let id = VarI32::decode(reader)?;
+
+        // the signature is only written out for messages the client has not cached
+        let signature = match i32::from(id.clone()) {
+            0 => Some(<[u8; 256]>::decode(reader)?),
+            _ => None
+        };
+
+        Ok(Self { id, signature })
+    }
+}
+
+impl Encode for PreviousMessage {
+    fn encode(&self, mut writer: impl Write) -> usize {
+        match &self.signature {
+            Some(signature) => VarI32::from(0).encode(&mut writer) + signature.encode(&mut writer),
+            None => self.id.encode(&mut writer)
+        }
+    }
+}
+
+impl Decode for FilterMask {
+    fn decode(reader: &mut impl Read) -> DecodeResult<Self> where Self: Sized {
+        Ok(match i32::from(VarI32::decode(reader)?) {
+            0 => Self::PassThrough,
+            1 => Self::FullyFiltered,
+            2 => Self::PartiallyFiltered(Vec::<i64>::decode(reader)?),
+            _ => return Err(DecodeError::InvalidData("unknown filter type"))
+        })
+    }
+}
+
+impl Encode for FilterMask {
+    fn encode(&self, mut writer: impl Write) -> usize {
+        match self {
+            Self::PassThrough => VarI32::from(0).encode(&mut writer),
+            Self::FullyFiltered => VarI32::from(1).encode(&mut writer),
+            Self::PartiallyFiltered(mask) => VarI32::from(2).encode(&mut writer) + mask.encode(&mut writer)
+        }
+    }
+}
+
+// numeric parsers carry a flag byte telling which of the bounds follow
+fn encode_bounds<T: Encode>(min: &Option<T>, max: &Option<T>, mut writer: impl Write) -> usize {
+    let flags = min.is_some() as u8 | (max.is_some() as u8) << 1;
+    let mut size = flags.encode(&mut writer);
+
+    if let Some(min) = min {
+        size += min.encode(&mut writer);
+    }
+    if let Some(max) = max {
+        size += max.encode(&mut writer);
+    }
+
+    size
+}
+
+fn decode_bounds<T: Decode>(reader: &mut impl Read) -> DecodeResult<(Option<T>, Option<T>)> {
+    let flags = u8::decode(reader)?;
+    let min = if flags & 0x01 != 0 { Some(T::decode(reader)?) } else { None };
+    let max = if flags & 0x02 != 0 { Some(T::decode(reader)?) } else { None };
+    Ok((min, max))
+}
+
+impl Decode for ArgumentParser {
+    fn decode(reader: &mut impl Read) -> DecodeResult<Self> where Self: Sized {
+        Ok(match i32::from(VarI32::decode(reader)?) {
+            0 => Self::Bool,
+            1 => {
+                let (min, max) = decode_bounds(reader)?;
+                Self::Float { min, max }
+            }
+            2 => {
+                let (min, max) = decode_bounds(reader)?;
+                Self::Double { min, max }
+            }
+            3 => {
+                let (min, max) = decode_bounds(reader)?;
+                Self::Integer { min, max }
+            }
+            4 => {
+                let (min, max) = decode_bounds(reader)?;
+                Self::Long { min, max }
+            }
+            5 => Self::String(match i32::from(VarI32::decode(reader)?) {
+                0 => StringKind::SingleWord,
+                1 => StringKind::QuotablePhrase,
+                2 => StringKind::GreedyPhrase,
+                _ => return Err(DecodeError::InvalidData("unknown string kind"))
+            }),
+            6 => {
+                let flags = u8::decode(reader)?;
+                Self::Entity { single: flags & 0x01 != 0, players_only: flags & 0x02 != 0 }
+            }
+            7 => Self::GameProfile,
+            8 => Self::BlockPos,
+            10 => Self::Vec3,
+            14 => Self::ItemStack,
+            18 => Self::Message,
+            33 => Self::ResourceLocation,
+            39 => Self::Gamemode,
+            40 => Self::Time { min: i32::decode(reader)? },
+            45 => Self::TemplateMirror,
+            46 => Self::TemplateRotation,
+            _ => return Err(DecodeError::InvalidData("unsupported argument parser"))
+        })
+    }
+}
+
+impl Encode for ArgumentParser {
+    fn encode(&self, mut writer: impl Write) -> usize {
+        VarI32::from(self.id()).encode(&mut writer) + match self {
+            Self::Float { min, max } => encode_bounds(min, max, &mut writer),
+            Self::Double { min, max } => encode_bounds(min, max, &mut writer),
+            Self::Integer { min, max } => encode_bounds(min, max, &mut writer),
+            Self::Long { min, max } => encode_bounds(min, max, &mut writer),
+            Self::String(kind) => VarI32::from(*kind as i32).encode(&mut writer),
+            Self::Entity { single, players_only } => (*single as u8 | (*players_only as u8) << 1).encode(&mut writer),
+            Self::Time { min } => min.encode(&mut writer),
+            _ => 0
+        }
+    }
+}
+
+impl Decode for CommandNode {
+    fn decode(reader: &mut impl Read) -> DecodeResult<Self> where Self: Sized {
+        let flags = u8::decode(reader)?;
+        let children = Vec::<VarI32>::decode(reader)?;
+        let redirect = if flags & 0x08 != 0 { Some(VarI32::decode(reader)?) } else { None };
+
+        let kind = match flags & 0x03 {
+            0 => CommandNodeKind::Root,
+            1 => CommandNodeKind::Literal(String::decode(reader)?),
+            2 => CommandNodeKind::Argument {
+                name: String::decode(reader)?,
+                parser: ArgumentParser::decode(reader)?,
+                suggestions: if flags & 0x10 != 0 { Some(Identifier::decode(reader)?) } else { None }
+            },
+            _ => return Err(DecodeError::InvalidData("unknown command node type"))
+        };
+
+        Ok(Self { executable: flags & 0x04 != 0, children, redirect, kind })
+    }
+}
+
+impl Encode for CommandNode {
+    fn encode(&self, mut writer: impl Write) -> usize {
+        let (kind, suggestions) = match &self.kind {
+            CommandNodeKind::Root => (0, false),
+            CommandNodeKind::Literal(_) => (1, false),
+            CommandNodeKind::Argument { suggestions, .. } => (2, suggestions.is_some())
+        };
+
+        let flags: u8 = kind | (self.executable as u8) << 2 | (self.redirect.is_some() as u8) << 3 | (suggestions as u8) << 4;
+        let mut size = flags.encode(&mut writer) + self.children.encode(&mut writer);
+
+        if let Some(redirect) = &self.redirect {
+            size += redirect.encode(&mut writer);
+        }
+
+        size + match &self.kind {
+            CommandNodeKind::Root => 0,
+            CommandNodeKind::Literal(name) => name.encode(&mut writer),
+            CommandNodeKind::Argument { name, parser, suggestions } => {
+                name.encode(&mut writer) + parser.encode(&mut writer) + suggestions.as_ref().map_or(0, |suggestions| suggestions.encode(&mut writer))
+            }
+        }
+    }
+}
+
+impl Decode for Suggestion {
+    fn decode(reader: &mut impl Read) -> DecodeResult<Self> where Self: Sized {
+        Ok(Self {
+            text: String::decode(reader)?,
+            tooltip: Option::<String>::decode(reader)?
+        })
+    }
+}
+
+impl Encode for Suggestion {
+    fn encode(&self, mut writer: impl Write) -> usize {
+        self.text.encode(&mut writer) + self.tooltip.encode(&mut writer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encoded(value: &impl Encode) -> Vec<u8> {
+        let mut bytes = vec![];
+        let size = value.encode(&mut bytes);
+
+        assert_eq!(size, bytes.len());
+        bytes
+    }
+
+    #[test]
+    fn writes_the_actions_of_player_info_once() {
+        let entries = PlayerInfoEntries(vec![
+            PlayerInfo { gamemode: Some(3.into()), latency: Some(300.into()), ..PlayerInfo::new(UUID(1)) },
+            PlayerInfo { gamemode: Some(0.into()), latency: Some(5.into()), ..PlayerInfo::new(UUID(2)) }
+        ]);
+
+        let mut expected = vec![PlayerInfo::UPDATE_GAMEMODE | PlayerInfo::UPDATE_LATENCY, 2];
+        expected.extend(1u128.to_be_bytes());
+        expected.extend([3, 0xac, 0x02]);
+        expected.extend(2u128.to_be_bytes());
+        expected.extend([0, 5]);
+
+        assert_eq!(encoded(&entries), expected);
+    }
+
+    #[test]
+    fn writes_the_fields_of_player_info_in_action_order() {
+        let entries = PlayerInfoEntries(vec![PlayerInfo {
+            add_player: Some(("Steve".to_string(), vec![])),
+            listed: Some(true),
+            display_name: Some(None),
+            ..PlayerInfo::new(UUID(1))
+        }]);
+
+        let mut expected = vec![0x29, 1];
+        expected.extend(1u128.to_be_bytes());
+        expected.extend([5, b'S', b't', b'e', b'v', b'e', 0, 1, 0]);
+
+        let bytes = encoded(&entries);
+        assert_eq!(bytes, expected);
+
+        let decoded = PlayerInfoEntries::decode(&mut &bytes[..]).unwrap();
+        assert_eq!(decoded.0.len(), 1);
+        assert_eq!(decoded.0[0].actions(), 0x29);
+        assert_eq!(decoded.0[0].add_player.as_ref().map(|(name, _)| name.as_str()), Some("Steve"));
+        assert_eq!(decoded.0[0].display_name, Some(None));
+    }
+
+    #[test]
+    fn writes_no_actions_without_entries() {
+        assert_eq!(encoded(&PlayerInfoEntries(vec![])), [0, 0]);
+    }
+}
@@ -1,10 +1,23 @@
+use std::fmt::{self, Display, Formatter};
 use std::io::{Read, Write};
 
 mod r#impl;
 
 #[derive(Debug)]
 pub enum DecodeError {
-    UnexpectedEOF
+    UnexpectedEOF,
+    UnknownPacket(i32),
+    InvalidData(&'static str)
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnexpectedEOF => write!(f, "unexpected end of stream"),
+            Self::UnknownPacket(id) => write!(f, "unknown packet id 0x{:02x}", id),
+            Self::InvalidData(message) => write!(f, "invalid data: {}", message)
+        }
+    }
 }
 
 pub type DecodeResult<T> = Result<T, DecodeError>;
@@ -0,0 +1,59 @@
+use crate::{nbt::Compound, types::Nbt};
+
+#[derive(Clone)]
+pub struct DimensionType {
+    pub name: String,
+    pub id: i32,
+    pub min_y: i32,
+    pub height: i32
+}
+
+pub struct RegistryCodec {
+    nbt: Nbt,
+    root: Compound
+}
+
+impl RegistryCodec {
+    pub fn vanilla() -> Self {
+        let nbt = Nbt(Vec::from(*include_bytes!("./RegistryCodec.nbt")));
+        let root = nbt.compound().expect("the bundled registry codec is valid nbt");
+
+        Self { nbt, root }
+    }
+
+    pub fn nbt(&self) -> Nbt {
+        Nbt(self.nbt.0.clone())
+    }
+
+    pub fn entries(&self, registry: &str) -> impl Iterator<Item = &Compound> {
+        self.root.get_compound(registry)
+            .and_then(|registry| registry.get_list("value"))
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|entry| entry.as_compound())
+    }
+
+    pub fn entry(&self, registry: &str, name: &str) -> Option<&Compound> {
+        let name = name.strip_prefix("minecraft:").unwrap_or(name);
+
+        self.entries(registry).find(|entry| {
+            entry.get_str("name").is_some_and(|entry_name| entry_name.strip_prefix("minecraft:").unwrap_or(entry_name) == name)
+        })
+    }
+
+    pub fn id(&self, registry: &str, name: &str) -> Option<i32> {
+        self.entry(registry, name)?.get_i32("id")
+    }
+
+    pub fn dimension_type(&self, name: &str) -> Option<DimensionType> {
+        let entry = self.entry("minecraft:dimension_type", name)?;
+        let element = entry.get_compound("element")?;
+
+        Some(DimensionType {
+            name: entry.get_str("name")?.to_string(),
+            id: entry.get_i32("id")?,
+            min_y: element.get_i32("min_y")?,
+            height: element.get_i32("height")?
+        })
+    }
+}
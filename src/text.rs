@@ -0,0 +1,30 @@
+pub fn escape(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+
+    for character in text.chars() {
+        match character {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            character if (character as u32) < 0x20 => output.push_str(&format!("\\u{:04x}", character as u32)),
+            character => output.push(character)
+        }
+    }
+
+    output
+}
+
+pub fn plain(text: &str) -> String {
+    format!("{{\"text\":\"{}\"}}", escape(text))
+}
+
+// the arguments are text components themselves
+pub fn translate(key: &str, arguments: &[&str]) -> String {
+    format!("{{\"translate\":\"{}\",\"with\":[{}]}}", escape(key), arguments.join(","))
+}
+
+pub fn colored(text: &str, color: &str) -> String {
+    format!("{{\"text\":\"{}\",\"color\":\"{}\"}}", escape(text), escape(color))
+}
@@ -1,4 +1,6 @@
-use std::fmt::Debug;
+use std::fmt::{Debug, Display};
+
+use crate::{command::argument::ArgumentParser, nbt::{self, Compound, Tag}};
 
 #[derive(Clone)]
 pub struct VarI32(pub [u8; VarI32::MAX_LEN], pub u8);
@@ -10,6 +12,10 @@ impl VarI32 {
         self.1 as usize
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.1 == 0
+    }
+
     pub fn bytes(&self) -> &[u8] {
         &self.0[0..self.len()]
     }
@@ -53,9 +59,73 @@ impl Debug for VarI32 {
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone)]
+pub struct VarI64(pub [u8; VarI64::MAX_LEN], pub u8);
+
+impl VarI64 {
+    pub const MAX_LEN: usize = 10;
+
+    pub fn len(&self) -> usize {
+        self.1 as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.1 == 0
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        &self.0[0..self.len()]
+    }
+}
+
+impl From<VarI64> for i64 {
+    fn from(value: VarI64) -> Self {
+        let mut output: Self = 0;
+
+        for i in 0..value.1 {
+            output |= (value.0[i as usize] as i64 & 0x7F) << (i * 7);
+        }
+
+        output
+    }
+}
+
+impl From<i64> for VarI64 {
+    fn from(value: i64) -> Self {
+        let mut output: [u8; Self::MAX_LEN] = [0; Self::MAX_LEN];
+        let mut value = value as u64;
+
+        for i in 0..output.len() {
+            if (value & !0x7F) == 0 {
+                output[i] = value as u8;
+                return Self(output, i as u8 + 1)
+            }
+
+            output[i] = (value as u8 & 0x7F) | 0x80;
+
+            value >>= 7;
+        };
+
+        unreachable!()
+    }
+}
+
+impl Debug for VarI64 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("VarI64").field(&i64::from(self.clone())).finish()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct UUID(pub u128);
 
+impl Display for UUID {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let hex = format!("{:032x}", self.0);
+        write!(f, "{}-{}-{}-{}-{}", &hex[0..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..32])
+    }
+}
+
 #[derive(Debug)]
 pub struct Identifier {
     pub namespace: String,
@@ -70,22 +140,203 @@ impl From<&Identifier> for String {
 
 impl From<&str> for Identifier {
     fn from(value: &str) -> Self {
-        // FIXME: this mess
-        let mut pieces = value.split(':');
+        match value.split_once(':') {
+            Some((namespace, value)) => Self {
+                namespace: namespace.to_string(),
+                value: value.to_string()
+            },
+            None => Self {
+                namespace: "minecraft".to_string(),
+                value: value.to_string()
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Nbt(pub Vec<u8>);
+
+impl Nbt {
+    pub fn from_compound(compound: &Compound) -> Self {
+        let mut bytes = vec![];
+        compound.write(&mut bytes, "").unwrap();
+        Self(bytes)
+    }
+
+    pub fn compound(&self) -> Option<Compound> {
+        match nbt::read(&mut &self.0[..]) {
+            Ok((_, Tag::Compound(compound))) => Some(compound),
+            _ => None
+        }
+    }
+}
 
+// the rest of the packet, for fields without a length prefix
+#[derive(Debug)]
+pub struct RemainingBytes(pub Vec<u8>);
+
+#[derive(Debug)]
+pub struct ItemStack {
+    pub item_id: VarI32,
+    pub count: i8,
+    pub nbt: Nbt
+}
+
+pub type Slot = Option<ItemStack>;
+
+#[derive(Debug)]
+pub struct ChangedSlot {
+    pub slot: i16,
+    pub item: Slot
+}
+
+#[derive(Debug)]
+pub struct ArgumentSignature {
+    pub name: String,
+    pub signature: [u8; 256]
+}
+
+#[derive(Debug)]
+pub enum InteractAction {
+    Interact { hand: VarI32 },
+    Attack,
+    InteractAt { target_x: f32, target_y: f32, target_z: f32, hand: VarI32 }
+}
+
+#[derive(Debug)]
+pub enum SeenAdvancementsAction {
+    OpenedTab(Identifier),
+    ClosedScreen
+}
+
+#[derive(Debug, Clone)]
+pub struct ProfileProperty {
+    pub name: String,
+    pub value: String,
+    pub signature: Option<String>
+}
+
+// the key a player signs its chat messages with, as the client sent it
+#[derive(Debug, Clone)]
+pub struct ChatSession {
+    pub session_id: UUID,
+    // milliseconds since the epoch
+    pub expires_at: i64,
+    // X.509 encoded
+    pub public_key: Vec<u8>,
+    // by the trust root, over the player uuid, the expiry and the key
+    pub key_signature: Vec<u8>
+}
+
+// a message the client saw before the one it came with, an id above 0 points into its signature cache
+#[derive(Debug)]
+pub struct PreviousMessage {
+    pub id: VarI32,
+    pub signature: Option<[u8; 256]>
+}
+
+#[derive(Debug)]
+pub enum FilterMask {
+    PassThrough,
+    FullyFiltered,
+    PartiallyFiltered(Vec<i64>)
+}
+
+// one player in a player info update, only the fields that are Some are sent
+#[derive(Debug)]
+pub struct PlayerInfo {
+    pub uuid: UUID,
+    // the username and the profile properties
+    pub add_player: Option<(String, Vec<ProfileProperty>)>,
+    // None inside for a player without a chat session
+    pub initialize_chat: Option<Option<ChatSession>>,
+    pub gamemode: Option<VarI32>,
+    pub listed: Option<bool>,
+    pub latency: Option<VarI32>,
+    // None inside goes back to the username
+    pub display_name: Option<Option<String>>
+}
+
+impl PlayerInfo {
+    pub const ADD_PLAYER: u8 = 0x01;
+    pub const INITIALIZE_CHAT: u8 = 0x02;
+    pub const UPDATE_GAMEMODE: u8 = 0x04;
+    pub const UPDATE_LISTED: u8 = 0x08;
+    pub const UPDATE_LATENCY: u8 = 0x10;
+    pub const UPDATE_DISPLAY_NAME: u8 = 0x20;
+
+    pub fn new(uuid: UUID) -> Self {
         Self {
-            namespace: pieces.next().unwrap().to_string(),
-            value: pieces.next().unwrap().to_string()
+            uuid,
+            add_player: None,
+            initialize_chat: None,
+            gamemode: None,
+            listed: None,
+            latency: None,
+            display_name: None
         }
     }
+
+    pub fn actions(&self) -> u8 {
+        [
+            (self.add_player.is_some(), Self::ADD_PLAYER),
+            (self.initialize_chat.is_some(), Self::INITIALIZE_CHAT),
+            (self.gamemode.is_some(), Self::UPDATE_GAMEMODE),
+            (self.listed.is_some(), Self::UPDATE_LISTED),
+            (self.latency.is_some(), Self::UPDATE_LATENCY),
+            (self.display_name.is_some(), Self::UPDATE_DISPLAY_NAME)
+        ].into_iter().filter(|(set, _)| *set).fold(0, |actions, (_, action)| actions | action)
+    }
 }
 
+// the actions are written once for the whole packet, so every entry has to carry the same fields as the first
 #[derive(Debug)]
-pub struct Nbt(pub Vec<u8>);
+pub struct PlayerInfoEntries(pub Vec<PlayerInfo>);
 
 #[derive(Debug)]
+pub enum CommandNodeKind {
+    Root,
+    Literal(String),
+    // suggestions names where the client gets its suggestions from, minecraft:ask_server for the server
+    Argument { name: String, parser: ArgumentParser, suggestions: Option<Identifier> }
+}
+
+// a node of the command tree, the children and the redirect are indices into the packet's list of nodes
+#[derive(Debug)]
+pub struct CommandNode {
+    pub executable: bool,
+    pub children: Vec<VarI32>,
+    pub redirect: Option<VarI32>,
+    pub kind: CommandNodeKind
+}
+
+#[derive(Debug)]
+pub struct Suggestion {
+    pub text: String,
+    pub tooltip: Option<String>
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Pos {
     pub x: i32,
     pub y: i16,
     pub z: i32
-}
\ No newline at end of file
+}
+#[derive(Debug)]
+pub struct ChunkBlockEntity {
+    pub packed_xz: u8,
+    pub y: i16,
+    pub kind: VarI32,
+    pub data: Nbt
+}
+
+#[derive(Debug, Default)]
+pub struct LightData {
+    pub trust_edges: bool,
+    pub sky_light_mask: Vec<i64>,
+    pub block_light_mask: Vec<i64>,
+    pub empty_sky_light_mask: Vec<i64>,
+    pub empty_block_light_mask: Vec<i64>,
+    pub sky_light: Vec<Vec<u8>>,
+    pub block_light: Vec<Vec<u8>>
+}
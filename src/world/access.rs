@@ -0,0 +1,140 @@
+use std::{collections::BTreeMap, fs::File, io, path::Path};
+
+use crate::nbt::{Compound, Tag};
+
+pub struct Operator {
+    pub name: String,
+    pub permission_level: u8
+}
+
+pub struct Ban {
+    pub name: String,
+    pub reason: String
+}
+
+// who may join and who gets to use the restricted commands, by name since there are no accounts to go by offline
+// every list is keyed by the lowercase name, the names themselves keep the case they were added with
+#[derive(Default)]
+pub struct AccessList {
+    operators: BTreeMap<String, Operator>,
+    bans: BTreeMap<String, Ban>,
+    whitelist: BTreeMap<String, String>,
+    pub whitelist_enabled: bool
+}
+
+fn key(name: &str) -> String {
+    name.to_lowercase()
+}
+
+fn entries<'a>(data: &'a Compound, name: &str) -> impl Iterator<Item = &'a Compound> {
+    data.get_list(name).unwrap_or_default().iter().filter_map(Tag::as_compound)
+}
+
+impl AccessList {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let (_, data) = Compound::read_gzip(File::open(path)?)?;
+        Ok(Self::from_nbt(&data))
+    }
+
+    // entries without a name are skipped
+    pub fn from_nbt(data: &Compound) -> Self {
+        let mut access = Self::new();
+
+        for entry in entries(data, "Operators") {
+            if let Some(name) = entry.get_str("Name") {
+                access.op(name, entry.get_i32("Level").unwrap_or(0).clamp(0, u8::MAX as i32) as u8);
+            }
+        }
+
+        for entry in entries(data, "Bans") {
+            if let Some(name) = entry.get_str("Name") {
+                access.ban(name, entry.get_str("Reason").unwrap_or_default());
+            }
+        }
+
+        for name in data.get_list("Whitelist").unwrap_or_default().iter().filter_map(Tag::as_str) {
+            access.whitelist(name);
+        }
+
+        access.whitelist_enabled = data.get_i32("WhitelistEnabled").is_some_and(|enabled| enabled != 0);
+        access
+    }
+
+    pub fn to_nbt(&self) -> Compound {
+        let string = |value: &str| Tag::String(value.to_string());
+
+        Compound::new()
+            .with("Operators", Tag::List(self.operators.values().map(|operator| Tag::Compound(Compound::new()
+                .with("Name", string(&operator.name))
+                .with("Level", Tag::Int(operator.permission_level as i32)))).collect()))
+            .with("Bans", Tag::List(self.bans.values().map(|ban| Tag::Compound(Compound::new()
+                .with("Name", string(&ban.name))
+                .with("Reason", string(&ban.reason)))).collect()))
+            .with("Whitelist", Tag::List(self.whitelist.values().map(|name| string(name)).collect()))
+            .with("WhitelistEnabled", Tag::Byte(self.whitelist_enabled as i8))
+    }
+
+    pub fn operators(&self) -> impl Iterator<Item = &Operator> {
+        self.operators.values()
+    }
+
+    pub fn operator(&self, name: &str) -> Option<&Operator> {
+        self.operators.get(&key(name))
+    }
+
+    // false when the player already was an operator with that level
+    pub fn op(&mut self, name: &str, permission_level: u8) -> bool {
+        let operator = Operator { name: name.to_string(), permission_level };
+
+        match self.operators.insert(key(name), operator) {
+            Some(previous) => previous.permission_level != permission_level,
+            None => true
+        }
+    }
+
+    pub fn deop(&mut self, name: &str) -> bool {
+        self.operators.remove(&key(name)).is_some()
+    }
+
+    pub fn bans(&self) -> impl Iterator<Item = &Ban> {
+        self.bans.values()
+    }
+
+    pub fn ban_of(&self, name: &str) -> Option<&Ban> {
+        self.bans.get(&key(name))
+    }
+
+    // false when the player was banned already, the old reason stays then
+    pub fn ban(&mut self, name: &str, reason: &str) -> bool {
+        if self.bans.contains_key(&key(name)) {
+            return false
+        }
+
+        self.bans.insert(key(name), Ban { name: name.to_string(), reason: reason.to_string() });
+        true
+    }
+
+    pub fn pardon(&mut self, name: &str) -> bool {
+        self.bans.remove(&key(name)).is_some()
+    }
+
+    pub fn whitelisted(&self) -> impl Iterator<Item = &str> {
+        self.whitelist.values().map(String::as_str)
+    }
+
+    pub fn is_whitelisted(&self, name: &str) -> bool {
+        self.whitelist.contains_key(&key(name))
+    }
+
+    pub fn whitelist(&mut self, name: &str) -> bool {
+        self.whitelist.insert(key(name), name.to_string()).is_none()
+    }
+
+    pub fn unwhitelist(&mut self, name: &str) -> bool {
+        self.whitelist.remove(&key(name)).is_some()
+    }
+}
@@ -0,0 +1,626 @@
+use std::{collections::HashMap, fs::{self, File, OpenOptions}, io::{self, Read, Seek, SeekFrom, Write}, path::{Path, PathBuf}, time::{SystemTime, UNIX_EPOCH}};
+
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use flate2::{read::{GzDecoder, ZlibDecoder}, write::ZlibEncoder, Compression};
+
+use crate::{block::BlockState, nbt::{Compound, Tag}, registry::{DimensionType, RegistryCodec}};
+
+use super::{chunk::{BlockEntity, Chunk, ChunkPos, BIOME_VOLUME, SECTION_VOLUME, SECTION_WIDTH}, palette, save, storage::ChunkStorage, DATA_VERSION};
+
+pub const SECTOR_SIZE: u64 = 4096;
+pub const REGION_WIDTH: i32 = 32;
+// the length of a chunk has to fit the low byte of its location
+const MAX_CHUNK_SECTORS: u64 = 255;
+
+const GZIP: u8 = 1;
+const ZLIB: u8 = 2;
+const UNCOMPRESSED: u8 = 3;
+// set on top of the compression when the chunk did not fit and lives in its own .mcc file
+const EXTERNAL: u8 = 128;
+
+fn invalid_data(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+pub struct RegionFile {
+    path: PathBuf,
+    file: File,
+    // offset in sectors << 8 | length in sectors, 0 when the chunk was never saved
+    locations: [u32; 1024],
+    timestamps: [u32; 1024],
+    // which sectors of the file hold the header or a chunk
+    used: Vec<bool>
+}
+
+impl RegionFile {
+    pub fn open(path: impl Into<PathBuf>) -> io::Result<Self> {
+        Self::open_with(path.into(), false)
+    }
+
+    // opens the region file, starting an empty one when there is none yet
+    pub fn create(path: impl Into<PathBuf>) -> io::Result<Self> {
+        Self::open_with(path.into(), true)
+    }
+
+    fn open_with(path: PathBuf, create: bool) -> io::Result<Self> {
+        let mut file = OpenOptions::new().read(true).write(true).create(create).truncate(false).open(&path)?;
+        let length = file.metadata()?.len();
+        let mut locations = [0; 1024];
+        let mut timestamps = [0; 1024];
+
+        // an empty or cut off header means nothing was saved yet
+        if length >= SECTOR_SIZE * 2 {
+            file.read_u32_into::<BigEndian>(&mut locations)?;
+            file.read_u32_into::<BigEndian>(&mut timestamps)?;
+        } else if create {
+            file.set_len(SECTOR_SIZE * 2)?;
+        }
+
+        let mut used = vec![true; 2];
+
+        for location in locations.iter().filter(|location| **location != 0) {
+            let offset = (*location >> 8) as usize;
+            let end = offset + (*location & 0xFF) as usize;
+
+            if end > used.len() {
+                used.resize(end, false);
+            }
+
+            used[offset..end].fill(true);
+        }
+
+        Ok(Self { path, file, locations, timestamps, used })
+    }
+
+    fn index(x: i32, z: i32) -> usize {
+        ((z & 31) * REGION_WIDTH + (x & 31)) as usize
+    }
+
+    pub fn contains(&self, x: i32, z: i32) -> bool {
+        self.locations[Self::index(x, z)] != 0
+    }
+
+    // the saved chunks as offsets from the corner of the region
+    pub fn chunks(&self) -> impl Iterator<Item = (i32, i32)> + '_ {
+        (0..1024).filter(|index| self.locations[*index] != 0).map(|index| (index as i32 % REGION_WIDTH, index as i32 / REGION_WIDTH))
+    }
+
+    pub fn timestamp(&self, x: i32, z: i32) -> u32 {
+        self.timestamps[Self::index(x, z)]
+    }
+
+    // x and z are chunk coordinates, only the low five bits matter
+    pub fn read(&mut self, x: i32, z: i32) -> io::Result<Option<Compound>> {
+        let location = self.locations[Self::index(x, z)];
+
+        if location == 0 {
+            return Ok(None)
+        }
+
+        let offset = (location >> 8) as u64 * SECTOR_SIZE;
+        let sectors = (location & 0xFF) as u64;
+
+        self.file.seek(SeekFrom::Start(offset))?;
+
+        let length = self.file.read_u32::<BigEndian>()? as u64;
+
+        if length == 0 || length + 4 > sectors * SECTOR_SIZE {
+            return Err(invalid_data(format!("chunk {x} {z} in {} has a bad length", self.path.display())))
+        }
+
+        let compression = self.file.read_u8()?;
+        let mut data = vec![0; length as usize - 1];
+        self.file.read_exact(&mut data)?;
+
+        if compression & EXTERNAL != 0 {
+            data = fs::read(self.external_path(x, z))?;
+        }
+
+        let nbt = match compression & !EXTERNAL {
+            GZIP => Compound::read(&mut GzDecoder::new(data.as_slice()))?,
+            ZLIB => Compound::read(&mut ZlibDecoder::new(data.as_slice()))?,
+            UNCOMPRESSED => Compound::read(&mut data.as_slice())?,
+            other => return Err(invalid_data(format!("unknown chunk compression {other}")))
+        };
+
+        Ok(Some(nbt.1))
+    }
+
+    fn external_path(&self, x: i32, z: i32) -> PathBuf {
+        self.path.with_file_name(format!("c.{x}.{z}.mcc"))
+    }
+
+    // first fit, so the gaps left by chunks that grew or moved get filled again before the file grows
+    fn allocate(&mut self, sectors: usize) -> usize {
+        let mut start = 0;
+
+        for (index, used) in self.used.iter().enumerate() {
+            if *used {
+                start = index + 1;
+            } else if index + 1 - start == sectors {
+                break
+            }
+        }
+
+        if start + sectors > self.used.len() {
+            self.used.resize(start + sectors, false);
+        }
+
+        self.used[start..start + sectors].fill(true);
+        start
+    }
+
+    fn write_header(&mut self, index: usize) -> io::Result<()> {
+        self.file.seek(SeekFrom::Start(index as u64 * 4))?;
+        self.file.write_u32::<BigEndian>(self.locations[index])?;
+        self.file.seek(SeekFrom::Start(SECTOR_SIZE + index as u64 * 4))?;
+        self.file.write_u32::<BigEndian>(self.timestamps[index])
+    }
+
+    pub fn write(&mut self, x: i32, z: i32, nbt: &Compound) -> io::Result<()> {
+        let mut encoder = ZlibEncoder::new(vec![], Compression::default());
+        nbt.write(&mut encoder, "")?;
+        let mut data = encoder.finish()?;
+        let mut compression = ZLIB;
+
+        // length and compression take the first five bytes
+        let mut sectors = (data.len() as u64 + 5).div_ceil(SECTOR_SIZE);
+
+        // the old .mcc file may still be what the header points at, so it is only ever replaced whole
+        if sectors > MAX_CHUNK_SECTORS {
+            save::write_atomic(&self.external_path(x, z), |file| file.write_all(&data))?;
+            data.clear();
+            compression |= EXTERNAL;
+            sectors = 1;
+        }
+
+        let mut buffer = Vec::with_capacity((sectors * SECTOR_SIZE) as usize);
+        buffer.write_u32::<BigEndian>(data.len() as u32 + 1)?;
+        buffer.push(compression);
+        buffer.extend_from_slice(&data);
+        buffer.resize((sectors * SECTOR_SIZE) as usize, 0);
+
+        // the old sectors are only given up once the header points at the new ones
+        let index = Self::index(x, z);
+        let previous = self.locations[index];
+        let offset = self.allocate(sectors as usize);
+
+        self.file.seek(SeekFrom::Start(offset as u64 * SECTOR_SIZE))?;
+        self.file.write_all(&buffer)?;
+
+        // the data has to be on disk before the header points at it, a crash in between leaves the old copy in place
+        self.file.sync_data()?;
+
+        self.locations[index] = (offset as u32) << 8 | sectors as u32;
+        self.timestamps[index] = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as u32;
+        self.write_header(index)?;
+
+        // the old sectors can be written over as soon as they are free, by then the header must not point at them anymore
+        self.file.sync_data()?;
+        self.free(previous);
+
+        if compression & EXTERNAL == 0 {
+            self.remove_external(x, z)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn delete(&mut self, x: i32, z: i32) -> io::Result<()> {
+        let index = Self::index(x, z);
+        let previous = self.locations[index];
+
+        if previous == 0 {
+            return Ok(())
+        }
+
+        self.locations[index] = 0;
+        self.timestamps[index] = 0;
+        self.write_header(index)?;
+        self.file.sync_data()?;
+        self.free(previous);
+        self.remove_external(x, z)
+    }
+
+    fn free(&mut self, location: u32) {
+        if location != 0 {
+            let start = (location >> 8) as usize;
+            self.used[start..start + (location & 0xFF) as usize].fill(false);
+        }
+    }
+
+    fn remove_external(&self, x: i32, z: i32) -> io::Result<()> {
+        match fs::remove_file(self.external_path(x, z)) {
+            Err(error) if error.kind() != io::ErrorKind::NotFound => Err(error),
+            _ => Ok(())
+        }
+    }
+}
+
+// the region files of one dimension, opened as they are first needed
+pub struct RegionStorage {
+    directory: PathBuf,
+    regions: HashMap<(i32, i32), Option<RegionFile>>
+}
+
+impl RegionStorage {
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        Self { directory: directory.into(), regions: HashMap::new() }
+    }
+
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    fn region(&mut self, pos: ChunkPos, create: bool) -> io::Result<Option<&mut RegionFile>> {
+        let key = (pos.x >> 5, pos.z >> 5);
+
+        // a region that was missing on read is looked for again once something is written to it
+        let open = match self.regions.get(&key) {
+            Some(Some(_)) => false,
+            Some(None) => create,
+            None => true
+        };
+
+        if open {
+            let path = self.directory.join(format!("r.{}.{}.mca", key.0, key.1));
+
+            if create {
+                fs::create_dir_all(&self.directory)?;
+            }
+
+            let region = if create { RegionFile::create(path) } else { RegionFile::open(path) };
+
+            let region = match region {
+                Ok(region) => Some(region),
+                Err(error) if error.kind() == io::ErrorKind::NotFound => None,
+                Err(error) => return Err(error)
+            };
+
+            self.regions.insert(key, region);
+        }
+
+        Ok(self.regions.get_mut(&key).unwrap().as_mut())
+    }
+}
+
+impl ChunkStorage for RegionStorage {
+    fn load(&mut self, pos: ChunkPos) -> io::Result<Option<Compound>> {
+        match self.region(pos, false)? {
+            Some(region) => region.read(pos.x, pos.z),
+            None => Ok(None)
+        }
+    }
+
+    fn save(&mut self, pos: ChunkPos, nbt: &Compound) -> io::Result<()> {
+        self.region(pos, true)?.unwrap().write(pos.x, pos.z, nbt)
+    }
+
+    fn delete(&mut self, pos: ChunkPos) -> io::Result<()> {
+        match self.region(pos, false)? {
+            Some(region) => region.delete(pos.x, pos.z),
+            None => Ok(())
+        }
+    }
+
+    fn list(&mut self) -> io::Result<Vec<ChunkPos>> {
+        let entries = match fs::read_dir(&self.directory) {
+            Ok(entries) => entries,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(error) => return Err(error)
+        };
+
+        let mut chunks = vec![];
+
+        for entry in entries {
+            let name = entry?.file_name();
+            let coordinates = name.to_str()
+                .and_then(|name| name.strip_prefix("r.")?.strip_suffix(".mca")?.split_once('.'))
+                .and_then(|(x, z)| Some((x.parse::<i32>().ok()?, z.parse::<i32>().ok()?)));
+
+            let Some((region_x, region_z)) = coordinates else {
+                continue
+            };
+
+            let origin = ChunkPos::new(region_x * REGION_WIDTH, region_z * REGION_WIDTH);
+
+            if let Some(region) = self.region(origin, false)? {
+                chunks.extend(region.chunks().map(|(x, z)| ChunkPos::new(origin.x + x, origin.z + z)));
+            }
+        }
+
+        Ok(chunks)
+    }
+}
+
+impl Chunk {
+    pub fn from_anvil(nbt: &Compound, registry: &RegistryCodec, dimension: &DimensionType) -> Result<Option<Self>, String> {
+        let status = nbt.get_str("Status").unwrap_or("");
+
+        // FIXME: proto chunks are thrown away and generated again from scratch
+        if status.strip_prefix("minecraft:").unwrap_or(status) != "full" {
+            return Ok(None)
+        }
+
+        let x = nbt.get_i32("xPos").ok_or("chunk without xPos")?;
+        let z = nbt.get_i32("zPos").ok_or("chunk without zPos")?;
+        let mut chunk = Chunk::new(ChunkPos::new(x, z), dimension.min_y, dimension.height, 0);
+        let bottom_section = dimension.min_y.div_euclid(SECTION_WIDTH);
+        let mut biome_ids = HashMap::new();
+
+        for section in nbt.get_list("sections").unwrap_or_default().iter().filter_map(Tag::as_compound) {
+            let y = section.get_i32("Y").ok_or("section without Y")?;
+            let index = y - bottom_section;
+
+            // the light only sections above and below the world
+            let Some(target) = usize::try_from(index).ok().and_then(|index| chunk.sections_mut().get_mut(index)) else {
+                continue
+            };
+
+            if let Some(block_states) = section.get_compound("block_states") {
+                // blocks from other versions or mods are kept as unknown states, so saving the chunk writes them back
+                let palette: Vec<BlockState> = block_states.get_list("palette")
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|entry| {
+                        let entry = entry.as_compound().ok_or_else(|| format!("section {y} has a block state that is not a compound"))?;
+                        BlockState::from_nbt_or_unknown(entry).map_err(|error| format!("section {y}: {error}"))
+                    })
+                    .collect::<Result<_, String>>()?;
+
+                match (palette.as_slice(), block_states.get("data")) {
+                    ([], _) => (),
+                    ([state], _) => target.fill(*state),
+                    (_, Some(Tag::LongArray(data))) => {
+                        let bits = palette::bits_for(palette.len()).max(4);
+
+                        for (index, value) in palette::unpack(data, bits, SECTION_VOLUME).into_iter().enumerate() {
+                            let state = *palette.get(value as usize).ok_or_else(|| format!("section {y} has a block outside of its palette"))?;
+                            target.set_block(index & 15, index >> 8, (index >> 4) & 15, state);
+                        }
+                    }
+                    _ => return Err(format!("section {y} has a palette but no data"))
+                }
+            }
+
+            if let Some(biomes) = section.get_compound("biomes") {
+                let palette: Vec<u16> = biomes.get_list("palette")
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|entry| {
+                        let name = entry.as_str().unwrap_or("");
+                        *biome_ids.entry(name.to_string()).or_insert_with(|| {
+                            registry.id("minecraft:worldgen/biome", name).unwrap_or(0) as u16
+                        })
+                    })
+                    .collect();
+
+                match (palette.as_slice(), biomes.get("data")) {
+                    ([], _) => (),
+                    ([biome], _) => target.fill_biome(*biome),
+                    (_, Some(Tag::LongArray(data))) => {
+                        let bits = palette::bits_for(palette.len());
+
+                        for (index, value) in palette::unpack(data, bits, BIOME_VOLUME).into_iter().enumerate() {
+                            let biome = *palette.get(value as usize).ok_or_else(|| format!("section {y} has a biome outside of its palette"))?;
+                            target.set_biome(index & 3, index >> 4, (index >> 2) & 3, biome);
+                        }
+                    }
+                    _ => return Err(format!("section {y} has a biome palette but no data"))
+                }
+            }
+        }
+
+        for block_entity in nbt.get_list("block_entities").unwrap_or_default().iter().filter_map(Tag::as_compound) {
+            let (Some(x), Some(y), Some(z)) = (block_entity.get_i32("x"), block_entity.get_i32("y"), block_entity.get_i32("z")) else {
+                continue
+            };
+
+            if let Some(block_entity) = BlockEntity::from_nbt(block_entity) {
+                chunk.set_block_entity(x, y, z, block_entity);
+            }
+        }
+
+        // the saved heightmaps are only trusted when they were made for the same world height
+        if let Some(heightmaps) = nbt.get_compound("Heightmaps") {
+            let bits = palette::bits_for(chunk.height() as usize + 1);
+            let longs = 256usize.div_ceil(64 / bits as usize);
+            let mut kept = Compound::new();
+
+            for name in ["MOTION_BLOCKING", "WORLD_SURFACE"] {
+                if let Some(Tag::LongArray(data)) = heightmaps.get(name) {
+                    if data.len() == longs {
+                        kept.insert(name, Tag::LongArray(data.clone()));
+                    }
+                }
+            }
+
+            if kept.get("MOTION_BLOCKING").is_some() && kept.get("WORLD_SURFACE").is_some() {
+                chunk.set_stored_heightmaps(kept);
+            }
+        }
+
+        chunk.set_dirty(false);
+        Ok(Some(chunk))
+    }
+    // FIXME: light is left for vanilla to compute again, entities and scheduled ticks are not kept
+    pub fn to_anvil(&self, registry: &RegistryCodec) -> Compound {
+        let mut biome_names = HashMap::new();
+
+        for entry in registry.entries("minecraft:worldgen/biome") {
+            if let (Some(id), Some(name)) = (entry.get_i32("id"), entry.get_str("name")) {
+                biome_names.insert(id as u16, name);
+            }
+        }
+
+        let bottom_section = self.min_y().div_euclid(SECTION_WIDTH);
+
+        let sections = self.sections().iter().enumerate().map(|(index, section)| {
+            let mut block_palette = vec![];
+            let blocks: Vec<u32> = section.blocks().iter().map(|state| palette_index(&mut block_palette, *state)).collect();
+
+            let mut biome_palette = vec![];
+            let biomes: Vec<u32> = section.biomes().iter().map(|biome| palette_index(&mut biome_palette, *biome)).collect();
+
+            let mut block_states = Compound::new()
+                .with("palette", Tag::List(block_palette.iter().map(|state| Tag::Compound(state.to_nbt())).collect()));
+
+            if block_palette.len() > 1 {
+                let bits = palette::bits_for(block_palette.len()).max(4);
+                block_states.insert("data", Tag::LongArray(palette::pack(blocks.into_iter(), bits)));
+            }
+
+            let mut biomes_tag = Compound::new().with("palette", Tag::List(biome_palette.iter().map(|biome| {
+                Tag::String(biome_names.get(biome).copied().unwrap_or("minecraft:plains").to_string())
+            }).collect()));
+
+            if biome_palette.len() > 1 {
+                biomes_tag.insert("data", Tag::LongArray(palette::pack(biomes.into_iter(), palette::bits_for(biome_palette.len()))));
+            }
+
+            Tag::Compound(Compound::new()
+                .with("Y", Tag::Byte((bottom_section + index as i32) as i8))
+                .with("block_states", Tag::Compound(block_states))
+                .with("biomes", Tag::Compound(biomes_tag)))
+        }).collect();
+
+        let block_entities = self.block_entities().map(|(pos, block_entity)| {
+            let mut data = block_entity.data.clone();
+            data.insert("id", Tag::String(block_entity.id.clone()));
+            data.insert("x", Tag::Int(pos.x));
+            data.insert("y", Tag::Int(pos.y as i32));
+            data.insert("z", Tag::Int(pos.z));
+            data.insert("keepPacked", Tag::Byte(0));
+            Tag::Compound(data)
+        }).collect();
+
+        Compound::new()
+            .with("DataVersion", Tag::Int(DATA_VERSION))
+            .with("xPos", Tag::Int(self.pos.x))
+            .with("yPos", Tag::Int(bottom_section))
+            .with("zPos", Tag::Int(self.pos.z))
+            .with("Status", Tag::String("minecraft:full".to_string()))
+            .with("LastUpdate", Tag::Long(0))
+            .with("InhabitedTime", Tag::Long(0))
+            .with("isLightOn", Tag::Byte(0))
+            .with("sections", Tag::List(sections))
+            .with("block_entities", Tag::List(block_entities))
+            .with("Heightmaps", Tag::Compound(self.heightmaps()))
+            .with("block_ticks", Tag::List(vec![]))
+            .with("fluid_ticks", Tag::List(vec![]))
+            .with("PostProcessing", Tag::List(vec![]))
+            .with("structures", Tag::Compound(Compound::new()
+                .with("References", Tag::Compound(Compound::new()))
+                .with("starts", Tag::Compound(Compound::new()))))
+    }
+}
+
+fn palette_index<T: PartialEq + Copy>(palette: &mut Vec<T>, value: T) -> u32 {
+    match palette.iter().position(|entry| *entry == value) {
+        Some(index) => index as u32,
+        None => {
+            palette.push(value);
+            palette.len() as u32 - 1
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, properties: &[(&str, &str)]) -> Tag {
+        let mut entry = Compound::new().with("Name", Tag::String(name.to_string()));
+
+        if !properties.is_empty() {
+            entry.insert("Properties", Tag::Compound(Compound(
+                properties.iter().map(|(key, value)| (key.to_string(), Tag::String(value.to_string()))).collect()
+            )));
+        }
+
+        Tag::Compound(entry)
+    }
+
+    fn chunk_nbt(palette: Vec<Tag>, data: Vec<i64>) -> Compound {
+        let section = Compound::new()
+            .with("Y", Tag::Byte(0))
+            .with("block_states", Tag::Compound(Compound::new()
+                .with("palette", Tag::List(palette))
+                .with("data", Tag::LongArray(data))));
+
+        Compound::new()
+            .with("Status", Tag::String("minecraft:full".to_string()))
+            .with("xPos", Tag::Int(0))
+            .with("zPos", Tag::Int(0))
+            .with("sections", Tag::List(vec![Tag::Compound(section)]))
+    }
+
+    fn block_palette(nbt: &Compound) -> Vec<Tag> {
+        let sections = nbt.get_list("sections").unwrap();
+        let section = sections.iter().filter_map(Tag::as_compound).find(|section| section.get_i32("Y") == Some(0)).unwrap();
+        section.get_compound("block_states").unwrap().get_list("palette").unwrap().to_vec()
+    }
+
+    #[test]
+    fn keeps_unknown_block_states() {
+        let registry = RegistryCodec::vanilla();
+        let dimension = registry.dimension_type("minecraft:overworld").unwrap();
+        // blocks and properties of later versions than 1.19.4
+        let palette = vec![
+            entry("minecraft:stone", &[]),
+            entry("minecraft:copper_bulb", &[("lit", "false"), ("powered", "false")]),
+            entry("minecraft:decorated_pot", &[("cracked", "true"), ("facing", "north"), ("waterlogged", "false")]),
+            entry("minecraft:sculk_vein_air", &[])
+        ];
+        // the four entries in turn, four bits each
+        let data = vec![0x3210321032103210; SECTION_VOLUME / 16];
+
+        let chunk = Chunk::from_anvil(&chunk_nbt(palette.clone(), data), &registry, &dimension).unwrap().unwrap();
+        let bulb = chunk.get_block(1, 0, 0);
+        let pot = chunk.get_block(2, 0, 0);
+
+        assert!(bulb.is_unknown());
+        assert_eq!(bulb.stand_in(), BlockState::STONE);
+        assert!(pot.is_unknown());
+        assert_eq!(pot.stand_in(), BlockState::from_name("minecraft:decorated_pot").unwrap());
+        assert!(chunk.get_block(3, 0, 0).is_air());
+        assert_eq!(block_palette(&chunk.to_anvil(&registry)), palette);
+    }
+
+    #[test]
+    fn reads_vanilla_palettes() {
+        let registry = RegistryCodec::vanilla();
+        let dimension = registry.dimension_type("minecraft:overworld").unwrap();
+        // a section of a house as 1.19.4 saves it
+        let palette = vec![
+            entry("minecraft:air", &[]),
+            entry("minecraft:oak_planks", &[]),
+            entry("minecraft:oak_stairs", &[("facing", "east"), ("half", "top"), ("shape", "outer_left"), ("waterlogged", "false")]),
+            entry("minecraft:spruce_door", &[("facing", "south"), ("half", "upper"), ("hinge", "right"), ("open", "true"), ("powered", "false")]),
+            entry("minecraft:cave_air", &[])
+        ];
+        // the five entries in turn, four bits each
+        let data = vec![0x0432104321043210; SECTION_VOLUME / 16];
+
+        let chunk = Chunk::from_anvil(&chunk_nbt(palette.clone(), data), &registry, &dimension).unwrap().unwrap();
+        let stairs = chunk.get_block(2, 0, 0);
+        let door = chunk.get_block(3, 0, 0);
+
+        assert!(!stairs.is_unknown() && !door.is_unknown());
+        assert_eq!(stairs, BlockState(2937));
+        assert_eq!(stairs.properties(), [("facing", "east"), ("half", "top"), ("shape", "outer_left"), ("waterlogged", "false")]);
+        assert_eq!(door, BlockState(11838));
+        assert_eq!(door.name(), "minecraft:spruce_door");
+        assert!(chunk.get_block(4, 0, 0).is_air());
+        assert_eq!(block_palette(&chunk.to_anvil(&registry)), palette);
+    }
+
+    #[test]
+    fn rejects_blocks_outside_of_the_palette() {
+        let registry = RegistryCodec::vanilla();
+        let dimension = registry.dimension_type("minecraft:overworld").unwrap();
+        let palette = vec![entry("minecraft:stone", &[]), entry("minecraft:dirt", &[])];
+
+        assert!(Chunk::from_anvil(&chunk_nbt(palette, vec![0x5; SECTION_VOLUME / 16]), &registry, &dimension).is_err());
+    }
+}
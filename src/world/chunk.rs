@@ -0,0 +1,277 @@
+use std::collections::HashMap;
+
+use crate::{block::BlockState, nbt::Compound, types::Pos};
+
+pub const SECTION_WIDTH: i32 = 16;
+pub const SECTION_VOLUME: usize = 16 * 16 * 16;
+pub const BIOME_WIDTH: i32 = 4;
+pub const BIOME_VOLUME: usize = 4 * 4 * 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ChunkPos {
+    pub x: i32,
+    pub z: i32
+}
+
+impl ChunkPos {
+    pub fn new(x: i32, z: i32) -> Self {
+        Self { x, z }
+    }
+
+    pub fn of(pos: &Pos) -> Self {
+        Self::new(pos.x >> 4, pos.z >> 4)
+    }
+
+    // the chunk an entity at those coordinates is in
+    pub fn containing(x: f64, z: f64) -> Self {
+        Self::new((x.floor() as i32) >> 4, (z.floor() as i32) >> 4)
+    }
+
+    pub fn min_block_x(&self) -> i32 {
+        self.x * SECTION_WIDTH
+    }
+
+    pub fn min_block_z(&self) -> i32 {
+        self.z * SECTION_WIDTH
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BlockEntity {
+    pub id: String,
+    pub data: Compound
+}
+
+impl BlockEntity {
+    // the saved form also carries the id and position, which the chunk keeps on its own
+    pub fn from_nbt(nbt: &Compound) -> Option<Self> {
+        let id = nbt.get_str("id")?.to_string();
+        let mut data = nbt.clone();
+
+        for key in ["id", "x", "y", "z", "keepPacked"] {
+            data.remove(key);
+        }
+
+        Some(Self { id, data })
+    }
+}
+
+#[derive(Clone)]
+pub struct ChunkSection {
+    blocks: Box<[BlockState; SECTION_VOLUME]>,
+    biomes: [u16; BIOME_VOLUME],
+    non_air_count: u16
+}
+
+impl ChunkSection {
+    pub fn new(biome: u16) -> Self {
+        Self {
+            blocks: Box::new([BlockState::AIR; SECTION_VOLUME]),
+            biomes: [biome; BIOME_VOLUME],
+            non_air_count: 0
+        }
+    }
+
+    fn block_index(x: usize, y: usize, z: usize) -> usize {
+        (y * 16 + z) * 16 + x
+    }
+
+    fn biome_index(x: usize, y: usize, z: usize) -> usize {
+        (y * 4 + z) * 4 + x
+    }
+
+    pub fn get_block(&self, x: usize, y: usize, z: usize) -> BlockState {
+        self.blocks[Self::block_index(x, y, z)]
+    }
+
+    pub fn set_block(&mut self, x: usize, y: usize, z: usize, state: BlockState) -> BlockState {
+        let previous = std::mem::replace(&mut self.blocks[Self::block_index(x, y, z)], state);
+
+        match (previous.is_air(), state.is_air()) {
+            (true, false) => self.non_air_count += 1,
+            (false, true) => self.non_air_count -= 1,
+            _ => ()
+        }
+
+        previous
+    }
+
+    pub fn fill(&mut self, state: BlockState) {
+        self.blocks.fill(state);
+        self.non_air_count = if state.is_air() { 0 } else { SECTION_VOLUME as u16 };
+    }
+
+    pub fn blocks(&self) -> &[BlockState; SECTION_VOLUME] {
+        &self.blocks
+    }
+
+    pub fn get_biome(&self, x: usize, y: usize, z: usize) -> u16 {
+        self.biomes[Self::biome_index(x, y, z)]
+    }
+
+    pub fn set_biome(&mut self, x: usize, y: usize, z: usize, biome: u16) {
+        self.biomes[Self::biome_index(x, y, z)] = biome;
+    }
+
+    pub fn fill_biome(&mut self, biome: u16) {
+        self.biomes.fill(biome);
+    }
+
+    pub fn biomes(&self) -> &[u16; BIOME_VOLUME] {
+        &self.biomes
+    }
+
+    pub fn non_air_count(&self) -> u16 {
+        self.non_air_count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.non_air_count == 0
+    }
+}
+
+#[derive(Clone)]
+pub struct Chunk {
+    pub pos: ChunkPos,
+    min_y: i32,
+    sections: Vec<ChunkSection>,
+    block_entities: HashMap<Pos, BlockEntity>,
+    // heightmaps read from disk, dropped as soon as a block changes
+    stored_heightmaps: Option<Compound>,
+    // changed since it was last saved
+    dirty: bool,
+    // stands in for a saved chunk that could not be read, never saved so the one on disk is left for repair
+    placeholder: bool
+}
+
+impl Chunk {
+    pub fn new(pos: ChunkPos, min_y: i32, height: i32, biome: u16) -> Self {
+        Self {
+            pos,
+            min_y,
+            sections: vec![ChunkSection::new(biome); (height / SECTION_WIDTH) as usize],
+            block_entities: HashMap::new(),
+            stored_heightmaps: None,
+            dirty: true,
+            placeholder: false
+        }
+    }
+
+    pub fn min_y(&self) -> i32 {
+        self.min_y
+    }
+
+    pub fn height(&self) -> i32 {
+        self.sections.len() as i32 * SECTION_WIDTH
+    }
+
+    pub fn sections(&self) -> &[ChunkSection] {
+        &self.sections
+    }
+
+    pub fn sections_mut(&mut self) -> &mut [ChunkSection] {
+        self.stored_heightmaps = None;
+        self.dirty = true;
+        &mut self.sections
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.dirty && !self.placeholder
+    }
+
+    pub fn set_dirty(&mut self, dirty: bool) {
+        self.dirty = dirty;
+    }
+
+    pub fn is_placeholder(&self) -> bool {
+        self.placeholder
+    }
+
+    pub fn set_placeholder(&mut self, placeholder: bool) {
+        self.placeholder = placeholder;
+    }
+
+    pub fn stored_heightmaps(&self) -> Option<&Compound> {
+        self.stored_heightmaps.as_ref()
+    }
+
+    pub fn set_stored_heightmaps(&mut self, heightmaps: Compound) {
+        self.stored_heightmaps = Some(heightmaps);
+    }
+
+    // local x and z, absolute y
+    fn locate(&self, x: i32, y: i32, z: i32) -> Option<(usize, usize, usize, usize)> {
+        let relative_y = y - self.min_y;
+
+        if !(0..self.height()).contains(&relative_y) {
+            return None
+        }
+
+        Some((
+            (relative_y / SECTION_WIDTH) as usize,
+            (x & 15) as usize,
+            (relative_y & 15) as usize,
+            (z & 15) as usize
+        ))
+    }
+
+    pub fn get_block(&self, x: i32, y: i32, z: i32) -> BlockState {
+        match self.locate(x, y, z) {
+            Some((section, x, y, z)) => self.sections[section].get_block(x, y, z),
+            None => BlockState::AIR
+        }
+    }
+
+    pub fn set_block(&mut self, x: i32, y: i32, z: i32, state: BlockState) -> Option<BlockState> {
+        let (section, local_x, local_y, local_z) = self.locate(x, y, z)?;
+        let previous = self.sections[section].set_block(local_x, local_y, local_z, state);
+
+        if previous != state {
+            self.block_entities.remove(&self.block_pos(x, y, z));
+            self.stored_heightmaps = None;
+            self.dirty = true;
+        }
+
+        Some(previous)
+    }
+
+    pub fn get_biome(&self, x: i32, y: i32, z: i32) -> Option<u16> {
+        let (section, x, y, z) = self.locate(x, y, z)?;
+        Some(self.sections[section].get_biome(x / 4, y / 4, z / 4))
+    }
+
+    pub fn set_biome(&mut self, x: i32, y: i32, z: i32, biome: u16) {
+        if let Some((section, x, y, z)) = self.locate(x, y, z) {
+            self.sections[section].set_biome(x / 4, y / 4, z / 4, biome);
+            self.dirty = true;
+        }
+    }
+
+    fn block_pos(&self, x: i32, y: i32, z: i32) -> Pos {
+        Pos {
+            x: self.pos.min_block_x() + (x & 15),
+            y: y as i16,
+            z: self.pos.min_block_z() + (z & 15)
+        }
+    }
+
+    pub fn block_entity(&self, x: i32, y: i32, z: i32) -> Option<&BlockEntity> {
+        self.block_entities.get(&self.block_pos(x, y, z))
+    }
+
+    pub fn set_block_entity(&mut self, x: i32, y: i32, z: i32, block_entity: BlockEntity) {
+        let pos = self.block_pos(x, y, z);
+        self.block_entities.insert(pos, block_entity);
+        self.dirty = true;
+    }
+
+    pub fn remove_block_entity(&mut self, x: i32, y: i32, z: i32) -> Option<BlockEntity> {
+        let removed = self.block_entities.remove(&self.block_pos(x, y, z));
+        self.dirty |= removed.is_some();
+        removed
+    }
+
+    pub fn block_entities(&self) -> impl Iterator<Item = (&Pos, &BlockEntity)> {
+        self.block_entities.iter()
+    }
+}
@@ -0,0 +1,201 @@
+use crate::{block::{self, BlockState}, nbt::{Compound, Tag}, packets::play::clientbound::{self, ChunkDataAndUpdateLightData, UpdateLightData}, protocol::Encode, types::{ChunkBlockEntity, LightData, Nbt}};
+
+use super::{chunk::{Chunk, SECTION_WIDTH}, palette::{self, PalettedContainer}};
+
+const FULL_LIGHT: [u8; 2048] = [0xFF; 2048];
+
+fn set_bit(mask: &mut Vec<i64>, bit: usize) {
+    if mask.len() <= bit / 64 {
+        mask.resize(bit / 64 + 1, 0);
+    }
+
+    mask[bit / 64] |= 1 << (bit % 64);
+}
+
+impl Chunk {
+    // per column the number of blocks between the bottom of the world and the top matching block, 0 when there is none
+    pub fn heightmap(&self, predicate: impl Fn(BlockState) -> bool) -> [u32; 256] {
+        let mut heights = [0; 256];
+
+        for z in 0..16 {
+            for x in 0..16 {
+                let top = (0..self.height()).rev().find(|y| predicate(self.get_block(x, self.min_y() + y, z)));
+                heights[(z * 16 + x) as usize] = top.map_or(0, |y| y as u32 + 1);
+            }
+        }
+
+        heights
+    }
+
+    pub fn heightmaps(&self) -> Compound {
+        if let Some(heightmaps) = self.stored_heightmaps() {
+            return heightmaps.clone()
+        }
+
+        let bits = palette::bits_for(self.height() as usize + 1);
+        let motion_blocking = self.heightmap(BlockState::blocks_motion);
+        let world_surface = self.heightmap(|state| !state.is_air());
+
+        Compound::new()
+            .with("MOTION_BLOCKING", Tag::LongArray(palette::pack(motion_blocking.into_iter(), bits)))
+            .with("WORLD_SURFACE", Tag::LongArray(palette::pack(world_surface.into_iter(), bits)))
+    }
+
+    pub fn section_data(&self, biome_count: usize) -> Vec<u8> {
+        let mut data = vec![];
+
+        for section in self.sections() {
+            let blocks: Vec<u32> = section.blocks().iter().map(|state| state.stand_in().0 as u32).collect();
+            let biomes: Vec<u32> = section.biomes().iter().map(|biome| *biome as u32).collect();
+
+            (section.non_air_count() as i16).encode(&mut data);
+            PalettedContainer::blocks(&blocks).encode(&mut data);
+            PalettedContainer::biomes(&biomes, biome_count).encode(&mut data);
+        }
+
+        data
+    }
+
+    // FIXME: sky light only reaches straight down to the first motion blocking block, nothing spreads and blocks never emit
+    pub fn light_data(&self) -> LightData {
+        let heights = self.heightmap(BlockState::blocks_motion);
+        let mut light = LightData { trust_edges: true, ..LightData::default() };
+
+        // one extra light section below and above the world
+        let light_sections = self.sections().len() + 2;
+
+        for index in 0..light_sections {
+            let bottom = (index as i32 - 1) * SECTION_WIDTH;
+
+            set_bit(&mut light.empty_block_light_mask, index);
+
+            if heights.iter().all(|height| *height as i32 <= bottom) {
+                set_bit(&mut light.sky_light_mask, index);
+                light.sky_light.push(FULL_LIGHT.to_vec());
+            } else if heights.iter().all(|height| *height as i32 >= bottom + SECTION_WIDTH) {
+                set_bit(&mut light.empty_sky_light_mask, index);
+            } else {
+                let mut nibbles = vec![0u8; 2048];
+
+                for y in 0..SECTION_WIDTH {
+                    for (column, height) in heights.iter().enumerate() {
+                        if bottom + y >= *height as i32 {
+                            let index = y as usize * 256 + column;
+                            nibbles[index / 2] |= 15 << ((index % 2) * 4);
+                        }
+                    }
+                }
+
+                set_bit(&mut light.sky_light_mask, index);
+                light.sky_light.push(nibbles);
+            }
+        }
+
+        light
+    }
+
+    pub fn block_entity_data(&self) -> Vec<ChunkBlockEntity> {
+        self.block_entities()
+            .filter_map(|(pos, block_entity)| Some(ChunkBlockEntity {
+                packed_xz: (((pos.x & 15) << 4) | (pos.z & 15)) as u8,
+                y: pos.y,
+                kind: block::block_entity_type_id(&block_entity.id)?.into(),
+                data: Nbt::from_compound(&block_entity.data)
+            }))
+            .collect()
+    }
+
+    pub fn to_packet(&self, biome_count: usize) -> clientbound::Packet {
+        clientbound::Packet::ChunkDataAndUpdateLight(ChunkDataAndUpdateLightData {
+            chunk_x: self.pos.x,
+            chunk_z: self.pos.z,
+            heightmaps: Nbt::from_compound(&self.heightmaps()),
+            data: self.section_data(biome_count),
+            block_entities: self.block_entity_data(),
+            light: self.light_data()
+        })
+    }
+
+    pub fn to_light_packet(&self) -> clientbound::Packet {
+        clientbound::Packet::UpdateLight(UpdateLightData {
+            chunk_x: self.pos.x.into(),
+            chunk_z: self.pos.z.into(),
+            light: self.light_data()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::world::chunk::ChunkPos;
+
+    use super::*;
+
+    // one section from y 0 to 15 with a stone floor at y 4 and a poppy on it in the corner
+    fn chunk() -> Chunk {
+        let mut chunk = Chunk::new(ChunkPos::new(0, 0), 0, 16, 0);
+        let stone = BlockState::from_name("minecraft:stone").unwrap();
+
+        for z in 0..16 {
+            for x in 0..16 {
+                chunk.set_block(x, 4, z, stone);
+            }
+        }
+
+        chunk.set_block(0, 5, 0, BlockState::from_name("minecraft:poppy").unwrap());
+        chunk
+    }
+
+    // five bit heights, twelve to a long and four in the last one
+    #[test]
+    fn encodes_heightmaps() {
+        let mut world_surface = vec![0x294A5294A5294A5; 22];
+        world_surface[0] = 0x294A5294A5294A6;
+        world_surface[21] = 0x294A5;
+
+        let mut motion_blocking = vec![0x294A5294A5294A5; 22];
+        motion_blocking[21] = 0x294A5;
+
+        let heightmaps = chunk().heightmaps();
+
+        assert_eq!(heightmaps, Compound::new()
+            .with("MOTION_BLOCKING", Tag::LongArray(motion_blocking))
+            .with("WORLD_SURFACE", Tag::LongArray(world_surface)));
+
+        // an unnamed compound with a long array of 22 longs first
+        let mut expected = vec![10, 0, 0, 12, 0, 15];
+        expected.extend(b"MOTION_BLOCKING");
+        expected.extend([0, 0, 0, 22, 0x02, 0x94, 0xA5, 0x29, 0x4A, 0x52, 0x94, 0xA5]);
+
+        assert_eq!(Nbt::from_compound(&heightmaps).0[..expected.len()], expected);
+    }
+
+    #[test]
+    fn encodes_sections() {
+        // 257 blocks that are not air, then the palette of air, stone and poppy
+        let mut expected = vec![0x01, 0x01, 4, 3, 0, 1, 0x99, 0x10, 0x80, 0x02];
+        expected.extend([0; 64 * 8]);
+        expected.extend([0x11; 16 * 8]);
+        expected.extend([0, 0, 0, 0, 0, 0, 0, 2]);
+        expected.extend([0; 175 * 8]);
+        // every biome is the first one
+        expected.extend([0, 0, 0]);
+
+        assert_eq!(chunk().section_data(64), expected);
+    }
+
+    #[test]
+    fn encodes_light() {
+        let light = chunk().light_data();
+        let mut partial = vec![0; 640];
+        partial.extend([0xFF; 2048 - 640]);
+
+        assert!(light.trust_edges);
+        assert_eq!(light.sky_light_mask, [0b110]);
+        assert_eq!(light.empty_sky_light_mask, [0b001]);
+        assert!(light.block_light_mask.is_empty());
+        assert_eq!(light.empty_block_light_mask, [0b111]);
+        assert_eq!(light.sky_light, [partial, FULL_LIGHT.to_vec()]);
+        assert!(light.block_light.is_empty());
+    }
+}
@@ -0,0 +1,213 @@
+use crate::registry::RegistryCodec;
+
+use super::{noise::OctaveNoise, random::Random};
+
+// the octave noise mostly stays within -0.33..0.33, spread it over the -1..1 the parameters below expect
+const CLIMATE_SCALE: f64 = 3.0;
+
+const FULL: (f64, f64) = (-2.0, 2.0);
+
+const TEMPERATURES: [(f64, f64); 5] = [(-1.0, -0.45), (-0.45, -0.15), (-0.15, 0.2), (0.2, 0.55), (0.55, 1.0)];
+const HUMIDITIES: [(f64, f64); 5] = [(-1.0, -0.35), (-0.35, -0.1), (-0.1, 0.1), (0.1, 0.3), (0.3, 1.0)];
+
+pub const MUSHROOM_FIELDS: (f64, f64) = (-1.2, -1.05);
+pub const DEEP_OCEAN: (f64, f64) = (-1.05, -0.455);
+pub const OCEAN: (f64, f64) = (-0.455, -0.19);
+pub const COAST: (f64, f64) = (-0.19, -0.11);
+pub const INLAND: (f64, f64) = (-0.11, 1.0);
+pub const FAR_INLAND: (f64, f64) = (0.03, 1.0);
+
+const PEAK_EROSION: (f64, f64) = (-1.0, -0.78);
+const SLOPE_EROSION: (f64, f64) = (-0.78, -0.375);
+const FLAT_EROSION: (f64, f64) = (-0.375, 0.55);
+const WET_EROSION: (f64, f64) = (0.55, 1.0);
+
+pub const RIVER_WEIRDNESS: f64 = 0.05;
+
+// indexed by temperature, then humidity
+const OCEANS: [[&str; 5]; 2] = [
+    ["deep_frozen_ocean", "deep_cold_ocean", "deep_ocean", "deep_lukewarm_ocean", "warm_ocean"],
+    ["frozen_ocean", "cold_ocean", "ocean", "lukewarm_ocean", "warm_ocean"]
+];
+
+const MIDDLE: [[&str; 5]; 5] = [
+    ["snowy_plains", "snowy_plains", "snowy_plains", "snowy_taiga", "taiga"],
+    ["plains", "plains", "forest", "taiga", "old_growth_spruce_taiga"],
+    ["flower_forest", "plains", "forest", "birch_forest", "dark_forest"],
+    ["savanna", "savanna", "forest", "jungle", "jungle"],
+    ["desert", "desert", "desert", "desert", "desert"]
+];
+
+const BEACHES: [&str; 5] = ["snowy_beach", "beach", "beach", "beach", "desert"];
+const SLOPES: [&str; 5] = ["snowy_slopes", "snowy_slopes", "grove", "meadow", "meadow"];
+const PEAKS: [&str; 5] = ["frozen_peaks", "jagged_peaks", "jagged_peaks", "stony_peaks", "stony_peaks"];
+
+#[derive(Debug, Clone, Copy)]
+pub struct Climate {
+    pub temperature: f64,
+    pub humidity: f64,
+    pub continentalness: f64,
+    pub erosion: f64,
+    pub weirdness: f64,
+    // 0 at the surface, 1 about 128 blocks below it
+    pub depth: f64
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Biome {
+    pub id: u16,
+    pub name: &'static str
+}
+
+struct ParameterPoint {
+    temperature: (f64, f64),
+    humidity: (f64, f64),
+    continentalness: (f64, f64),
+    erosion: (f64, f64),
+    weirdness: (f64, f64),
+    depth: (f64, f64)
+}
+
+impl ParameterPoint {
+    fn distance(&self, climate: &Climate) -> f64 {
+        let axis = |(min, max): (f64, f64), value: f64| {
+            let outside = if value < min { min - value } else if value > max { value - max } else { 0.0 };
+            outside * outside
+        };
+
+        axis(self.temperature, climate.temperature)
+            + axis(self.humidity, climate.humidity)
+            + axis(self.continentalness, climate.continentalness)
+            + axis(self.erosion, climate.erosion)
+            + axis(self.weirdness, climate.weirdness)
+            + axis(self.depth, climate.depth)
+    }
+}
+
+// picks the biome whose parameter ranges are closest to the climate at a position, like vanilla's overworld
+pub struct MultiNoiseBiomeSource {
+    temperature: OctaveNoise,
+    humidity: OctaveNoise,
+    continentalness: OctaveNoise,
+    erosion: OctaveNoise,
+    weirdness: OctaveNoise,
+    biomes: Vec<(ParameterPoint, Biome)>
+}
+
+impl MultiNoiseBiomeSource {
+    // fails when the registry codec has none of the biomes, there would be nothing to pick from
+    pub fn new(random: &mut Random, registry: &RegistryCodec) -> Result<Self, String> {
+        let mut source = Self {
+            temperature: OctaveNoise::new(random, 3, 2048.0),
+            humidity: OctaveNoise::new(random, 3, 1024.0),
+            continentalness: OctaveNoise::new(random, 4, 1536.0),
+            erosion: OctaveNoise::new(random, 3, 768.0),
+            weirdness: OctaveNoise::new(random, 3, 512.0),
+            biomes: vec![]
+        };
+
+        // earlier entries win ties, so the narrow cave and river ranges go first
+        source.add(registry, "lush_caves", FULL, (0.7, 1.0), FULL, FULL, FULL, (0.2, 0.9));
+        source.add(registry, "dripstone_caves", FULL, FULL, (0.8, 1.0), FULL, FULL, (0.2, 0.9));
+
+        for (index, temperature) in TEMPERATURES.into_iter().enumerate() {
+            let river = if index == 0 { "frozen_river" } else { "river" };
+            source.add(registry, river, temperature, FULL, (-0.11, 0.55), (-0.375, 1.0), (-RIVER_WEIRDNESS, RIVER_WEIRDNESS), FULL);
+        }
+
+        source.add(registry, "mushroom_fields", FULL, FULL, MUSHROOM_FIELDS, FULL, FULL, FULL);
+
+        for (index, temperature) in TEMPERATURES.into_iter().enumerate() {
+            source.add(registry, OCEANS[0][index], temperature, FULL, DEEP_OCEAN, FULL, FULL, FULL);
+            source.add(registry, OCEANS[1][index], temperature, FULL, OCEAN, FULL, FULL, FULL);
+            source.add(registry, BEACHES[index], temperature, FULL, COAST, (-0.375, 1.0), FULL, FULL);
+            source.add(registry, "stony_shore", temperature, FULL, COAST, (-1.0, -0.375), FULL, FULL);
+            source.add(registry, SLOPES[index], temperature, FULL, FAR_INLAND, SLOPE_EROSION, FULL, FULL);
+            source.add(registry, PEAKS[index], temperature, FULL, FAR_INLAND, PEAK_EROSION, FULL, FULL);
+
+            for (humidity_index, humidity) in HUMIDITIES.into_iter().enumerate() {
+                let middle = MIDDLE[index][humidity_index];
+                let wet = match (index, humidity_index) {
+                    (1..=2, 3..) => "swamp",
+                    (3.., 3..) => "mangrove_swamp",
+                    _ => middle
+                };
+
+                source.add(registry, middle, temperature, humidity, INLAND, FLAT_EROSION, FULL, FULL);
+                source.add(registry, wet, temperature, humidity, INLAND, WET_EROSION, FULL, FULL);
+                source.add(registry, middle, temperature, humidity, (-0.11, 0.03), (-1.0, -0.375), FULL, FULL);
+            }
+        }
+
+        if source.biomes.is_empty() {
+            return Err("the registry codec has none of the overworld biomes".to_string())
+        }
+
+        Ok(source)
+    }
+
+    // biomes the registry codec does not know are left out so every id matches what the client was sent
+    #[allow(clippy::too_many_arguments)]
+    fn add(&mut self, registry: &RegistryCodec, name: &'static str, temperature: (f64, f64), humidity: (f64, f64), continentalness: (f64, f64), erosion: (f64, f64), weirdness: (f64, f64), depth: (f64, f64)) {
+        if let Some(id) = registry.id("minecraft:worldgen/biome", &format!("minecraft:{}", name)) {
+            self.biomes.push((
+                ParameterPoint { temperature, humidity, continentalness, erosion, weirdness, depth },
+                Biome { id: id as u16, name }
+            ));
+        }
+    }
+
+    // the climate at the surface of a block column
+    pub fn climate(&self, x: i32, z: i32) -> Climate {
+        let sample = |noise: &OctaveNoise| (noise.sample_2d(x as f64, z as f64) * CLIMATE_SCALE).clamp(-1.0, 1.0);
+
+        Climate {
+            temperature: sample(&self.temperature),
+            humidity: sample(&self.humidity),
+            continentalness: (self.continentalness.sample_2d(x as f64, z as f64) * CLIMATE_SCALE).clamp(-1.2, 1.0),
+            erosion: sample(&self.erosion),
+            weirdness: sample(&self.weirdness),
+            depth: 0.0
+        }
+    }
+
+    pub fn by_id(&self, id: u16) -> Option<Biome> {
+        self.biomes.iter().map(|(_, biome)| *biome).find(|biome| biome.id == id)
+    }
+
+    pub fn biome(&self, climate: &Climate) -> Biome {
+        let mut closest = &self.biomes[0];
+        let mut closest_distance = f64::INFINITY;
+
+        for entry in &self.biomes {
+            let distance = entry.0.distance(climate);
+
+            if distance < closest_distance {
+                closest = entry;
+                closest_distance = distance;
+            }
+        }
+
+        closest.1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_biomes_the_client_knows() {
+        let registry = RegistryCodec::vanilla();
+        let source = MultiNoiseBiomeSource::new(&mut Random::new(0), &registry).unwrap();
+
+        for (_, biome) in &source.biomes {
+            assert_eq!(registry.id("minecraft:worldgen/biome", biome.name), Some(biome.id as i32), "{}", biome.name);
+        }
+
+        for (x, z) in [(0, 0), (5000, -3000), (-12000, 800), (40000, 40000)] {
+            let biome = source.biome(&source.climate(x, z));
+            assert_eq!(source.by_id(biome.id), Some(biome));
+        }
+    }
+}
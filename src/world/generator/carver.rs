@@ -0,0 +1,147 @@
+use std::f64::consts::PI;
+
+use crate::{block::BlockState, world::chunk::Chunk};
+
+use super::{random::Random, terrain::{TerrainGenerator, SEA_LEVEL}, ChunkRegion};
+
+// how many chunks away a tunnel can start and still reach into a chunk
+const CAVE_RANGE: i32 = 4;
+
+// percent of chunks that start caves and ravines
+const CAVE_CHANCE: i32 = 15;
+const RAVINE_CHANCE: i32 = 2;
+
+const CAVE_SALT: i64 = 10_001;
+const RAVINE_SALT: i64 = 10_002;
+
+// caves this close to the bottom of the world fill with lava
+const LAVA_LAYERS: i32 = 9;
+
+struct Carver<'a> {
+    region: ChunkRegion<'a>,
+    // the topmost solid block of every column, which is never carved together with the one below it
+    tops: [i32; 256],
+    lava_level: i32,
+    lava: BlockState
+}
+
+impl Carver<'_> {
+    fn carve_ellipsoid(&mut self, x: f64, y: f64, z: f64, radius: f64, vertical_radius: f64) {
+        let min_x = self.region.pos().min_block_x();
+        let min_z = self.region.pos().min_block_z();
+
+        if x + radius < min_x as f64 || x - radius >= (min_x + 16) as f64 || z + radius < min_z as f64 || z - radius >= (min_z + 16) as f64 {
+            return
+        }
+
+        let bottom = (y - vertical_radius).floor().max((self.region.min_y() + 1) as f64) as i32;
+        let top = (y + vertical_radius).floor().min((self.region.min_y() + self.region.height() - 1) as f64) as i32;
+
+        for block_x in ((x - radius).floor() as i32).max(min_x)..=((x + radius).floor() as i32).min(min_x + 15) {
+            for block_z in ((z - radius).floor() as i32).max(min_z)..=((z + radius).floor() as i32).min(min_z + 15) {
+                let top = top.min(self.tops[((block_z & 15) * 16 + (block_x & 15)) as usize] - 2);
+
+                for block_y in bottom..=top {
+                    let (dx, dy, dz) = ((block_x as f64 + 0.5 - x) / radius, (block_y as f64 + 0.5 - y) / vertical_radius, (block_z as f64 + 0.5 - z) / radius);
+
+                    if dx * dx + dy * dy + dz * dz >= 1.0 {
+                        continue
+                    }
+
+                    let state = if block_y < self.lava_level { self.lava } else { BlockState::AIR };
+                    self.region.replace(block_x, block_y, block_z, state, |state| {
+                        !state.is_air() && !state.is_liquid() && state.name() != "minecraft:bedrock"
+                    });
+                }
+            }
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn tunnel(&mut self, random: &mut Random, (mut x, mut y, mut z): (f64, f64, f64), width: f64, mut yaw: f64, mut pitch: f64, length: i32, vertical_scale: f64, wander: f64) {
+        let mut yaw_change = 0.0;
+        let mut pitch_change = 0.0;
+
+        for step in 0..length {
+            let radius = 1.5 + (step as f64 * PI / length as f64).sin() * width;
+            let cos_pitch = pitch.cos();
+
+            x += yaw.cos() * cos_pitch;
+            y += pitch.sin();
+            z += yaw.sin() * cos_pitch;
+
+            pitch = pitch * 0.7 + pitch_change * 0.1;
+            yaw += yaw_change * 0.1;
+
+            pitch_change = pitch_change * 0.9 + (random.next_f64() - random.next_f64()) * random.next_f64() * 2.0 * wander;
+            yaw_change = yaw_change * 0.75 + (random.next_f64() - random.next_f64()) * random.next_f64() * 4.0 * wander;
+
+            // every step draws the same numbers, so the path never depends on the chunk being carved
+            if random.next_i32_bounded(4) == 0 {
+                continue
+            }
+
+            self.carve_ellipsoid(x, y, z, radius, radius * vertical_scale);
+        }
+    }
+}
+
+impl TerrainGenerator {
+    pub(super) fn carve(&self, chunk: &mut Chunk) {
+        let min_y = chunk.min_y();
+        let pos = chunk.pos;
+        let heights = chunk.heightmap(|state| state.blocks_motion() && !state.is_liquid());
+
+        let mut carver = Carver {
+            tops: heights.map(|height| min_y + height as i32 - 1),
+            lava_level: min_y + LAVA_LAYERS,
+            lava: BlockState::from_name("minecraft:lava").unwrap(),
+            region: ChunkRegion::new(chunk)
+        };
+
+        for origin_x in pos.x - CAVE_RANGE..=pos.x + CAVE_RANGE {
+            for origin_z in pos.z - CAVE_RANGE..=pos.z + CAVE_RANGE {
+                let mut random = Random::for_chunk(self.seed(), origin_x, origin_z, CAVE_SALT);
+
+                if random.next_i32_bounded(100) < CAVE_CHANCE {
+                    for _ in 0..random.next_i32_bounded(4) + 1 {
+                        let start = (
+                            (origin_x * 16 + random.next_i32_bounded(16)) as f64,
+                            (min_y + 8 + random.next_i32_bounded(128 - min_y - 8)) as f64,
+                            (origin_z * 16 + random.next_i32_bounded(16)) as f64
+                        );
+
+                        let yaw = random.next_f64() * PI * 2.0;
+                        let pitch = (random.next_f64() - 0.5) / 4.0;
+                        let mut width = random.next_f64() * 2.0 + random.next_f64();
+
+                        // now and then a much wider one
+                        if random.next_i32_bounded(10) == 0 {
+                            width *= random.next_f64() * random.next_f64() * 3.0 + 1.0;
+                        }
+
+                        let length = (CAVE_RANGE - 1) * 16 - random.next_i32_bounded(16);
+                        carver.tunnel(&mut Random::new(random.next_i64()), start, width, yaw, pitch, length, 1.0, 1.0);
+                    }
+                }
+
+                let mut random = Random::for_chunk(self.seed(), origin_x, origin_z, RAVINE_SALT);
+
+                if random.next_i32_bounded(100) < RAVINE_CHANCE {
+                    let start = (
+                        (origin_x * 16 + random.next_i32_bounded(16)) as f64,
+                        (SEA_LEVEL - 40 + random.next_i32_bounded(48)) as f64,
+                        (origin_z * 16 + random.next_i32_bounded(16)) as f64
+                    );
+
+                    let yaw = random.next_f64() * PI * 2.0;
+                    let pitch = (random.next_f64() - 0.5) / 8.0;
+                    let width = (random.next_f64() * 2.0 + random.next_f64()) * 2.0;
+                    let length = (CAVE_RANGE - 1) * 16 - random.next_i32_bounded(16);
+
+                    carver.tunnel(&mut Random::new(random.next_i64()), start, width, yaw, pitch, length, 3.0, 0.25);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,311 @@
+use std::f64::consts::PI;
+
+use crate::{block::{BlockState, Rotation}, types::Pos, world::{chunk::Chunk, structure::PlacementSettings}};
+
+use super::{random::Random, terrain::{TerrainGenerator, SEA_LEVEL}, ChunkRegion};
+
+const ORE_SALT: i64 = 20_001;
+const TREE_SALT: i64 = 20_002;
+const PLANT_SALT: i64 = 20_003;
+// every template gets its own salt from here on
+const STRUCTURE_SALT: i64 = 20_100;
+
+const ROTATIONS: [Rotation; 4] = [Rotation::None, Rotation::Clockwise90, Rotation::Clockwise180, Rotation::Counterclockwise90];
+
+struct Ore {
+    name: &'static str,
+    // veins per chunk
+    count: i32,
+    size: i32,
+    min_y: i32,
+    max_y: i32,
+    // most common halfway between min_y and max_y instead of evenly spread
+    triangle: bool
+}
+
+const ORES: &[Ore] = &[
+    Ore { name: "minecraft:coal_ore", count: 20, size: 17, min_y: 0, max_y: 192, triangle: false },
+    Ore { name: "minecraft:iron_ore", count: 10, size: 9, min_y: -24, max_y: 56, triangle: true },
+    Ore { name: "minecraft:gold_ore", count: 4, size: 9, min_y: -64, max_y: 32, triangle: true },
+    Ore { name: "minecraft:lapis_ore", count: 2, size: 7, min_y: -32, max_y: 32, triangle: true },
+    Ore { name: "minecraft:redstone_ore", count: 4, size: 8, min_y: -64, max_y: 15, triangle: false },
+    Ore { name: "minecraft:diamond_ore", count: 4, size: 8, min_y: -64, max_y: 16, triangle: false }
+];
+
+struct Tree {
+    log: &'static str,
+    leaves: &'static str,
+    min_height: i32,
+    max_height: i32,
+    // spruces, everything else gets the round oak canopy
+    cone: bool
+}
+
+const OAK: Tree = Tree { log: "minecraft:oak_log", leaves: "minecraft:oak_leaves", min_height: 4, max_height: 6, cone: false };
+const BIRCH: Tree = Tree { log: "minecraft:birch_log", leaves: "minecraft:birch_leaves", min_height: 5, max_height: 7, cone: false };
+const DARK_OAK: Tree = Tree { log: "minecraft:dark_oak_log", leaves: "minecraft:dark_oak_leaves", min_height: 5, max_height: 7, cone: false };
+const JUNGLE: Tree = Tree { log: "minecraft:jungle_log", leaves: "minecraft:jungle_leaves", min_height: 6, max_height: 11, cone: false };
+const ACACIA: Tree = Tree { log: "minecraft:acacia_log", leaves: "minecraft:acacia_leaves", min_height: 5, max_height: 7, cone: false };
+const MANGROVE: Tree = Tree { log: "minecraft:mangrove_log", leaves: "minecraft:mangrove_leaves", min_height: 5, max_height: 8, cone: false };
+const SPRUCE: Tree = Tree { log: "minecraft:spruce_log", leaves: "minecraft:spruce_leaves", min_height: 6, max_height: 9, cone: true };
+const TALL_SPRUCE: Tree = Tree { log: "minecraft:spruce_log", leaves: "minecraft:spruce_leaves", min_height: 10, max_height: 15, cone: true };
+
+// trees per chunk and the kinds to pick from
+fn trees(biome: &str) -> (f64, &'static [Tree]) {
+    match biome {
+        "forest" => (10.0, &[OAK, OAK, OAK, BIRCH]),
+        "flower_forest" => (6.0, &[OAK, BIRCH]),
+        "birch_forest" => (10.0, &[BIRCH]),
+        "dark_forest" => (14.0, &[DARK_OAK, DARK_OAK, OAK]),
+        "taiga" | "grove" => (8.0, &[SPRUCE]),
+        "snowy_taiga" => (5.0, &[SPRUCE]),
+        "old_growth_spruce_taiga" => (12.0, &[SPRUCE, TALL_SPRUCE]),
+        "jungle" => (14.0, &[JUNGLE, JUNGLE, OAK]),
+        "swamp" => (2.0, &[OAK]),
+        "mangrove_swamp" => (4.0, &[MANGROVE]),
+        "savanna" => (1.0, &[ACACIA, OAK]),
+        "meadow" => (0.1, &[OAK, BIRCH]),
+        "plains" | "snowy_plains" => (0.05, &[OAK, SPRUCE]),
+        _ => (0.0, &[])
+    }
+}
+
+// chance per column of each plant, tried in order
+fn plants(biome: &str) -> &'static [(&'static str, f64)] {
+    match biome {
+        "plains" => &[("minecraft:grass", 0.2), ("minecraft:dandelion", 0.01), ("minecraft:poppy", 0.01), ("minecraft:azure_bluet", 0.005), ("minecraft:oxeye_daisy", 0.005)],
+        "flower_forest" => &[
+            ("minecraft:grass", 0.05), ("minecraft:dandelion", 0.02), ("minecraft:poppy", 0.02), ("minecraft:allium", 0.02),
+            ("minecraft:azure_bluet", 0.02), ("minecraft:red_tulip", 0.01), ("minecraft:orange_tulip", 0.01), ("minecraft:white_tulip", 0.01),
+            ("minecraft:pink_tulip", 0.01), ("minecraft:oxeye_daisy", 0.02), ("minecraft:cornflower", 0.02), ("minecraft:lily_of_the_valley", 0.02)
+        ],
+        "meadow" => &[("minecraft:grass", 0.4), ("minecraft:allium", 0.02), ("minecraft:azure_bluet", 0.02), ("minecraft:cornflower", 0.02), ("minecraft:oxeye_daisy", 0.02)],
+        "forest" | "birch_forest" => &[("minecraft:grass", 0.1), ("minecraft:dandelion", 0.005), ("minecraft:poppy", 0.005)],
+        "dark_forest" => &[("minecraft:grass", 0.05), ("minecraft:brown_mushroom", 0.01), ("minecraft:red_mushroom", 0.01)],
+        "taiga" | "old_growth_spruce_taiga" => &[("minecraft:fern", 0.1), ("minecraft:grass", 0.05)],
+        "jungle" => &[("minecraft:grass", 0.3), ("minecraft:fern", 0.1)],
+        "savanna" => &[("minecraft:grass", 0.3)],
+        "swamp" => &[("minecraft:grass", 0.05), ("minecraft:blue_orchid", 0.02)],
+        "desert" => &[("minecraft:dead_bush", 0.01), ("minecraft:cactus", 0.005)],
+        _ => &[]
+    }
+}
+
+fn is_replaceable(state: BlockState) -> bool {
+    state.is_air() || matches!(state.name(), "minecraft:snow" | "minecraft:grass" | "minecraft:fern")
+}
+
+fn is_soil(state: BlockState) -> bool {
+    matches!(state.name(), "minecraft:grass_block" | "minecraft:dirt" | "minecraft:podzol")
+}
+
+fn place_ore(region: &mut ChunkRegion, random: &mut Random, ore: BlockState, size: i32, (x, y, z): (i32, i32, i32)) {
+    let stone = BlockState::from_name("minecraft:stone").unwrap();
+    let angle = random.next_f64() * PI;
+    let spread = size as f64 / 8.0;
+    let from = (x as f64 + angle.sin() * spread, (y + random.next_i32_bounded(3) - 2) as f64, z as f64 + angle.cos() * spread);
+    let to = (x as f64 - angle.sin() * spread, (y + random.next_i32_bounded(3) - 2) as f64, z as f64 - angle.cos() * spread);
+
+    // a string of blobs along the line, thickest in the middle
+    for step in 0..size {
+        let t = step as f64 / size as f64;
+        let (center_x, center_y, center_z) = (from.0 + (to.0 - from.0) * t, from.1 + (to.1 - from.1) * t, from.2 + (to.2 - from.2) * t);
+        let radius = (((t * PI).sin() + 1.0) * random.next_f64() * size as f64 / 16.0 + 1.0) / 2.0;
+
+        for block_x in (center_x - radius).floor() as i32..=(center_x + radius).floor() as i32 {
+            for block_y in (center_y - radius).floor() as i32..=(center_y + radius).floor() as i32 {
+                for block_z in (center_z - radius).floor() as i32..=(center_z + radius).floor() as i32 {
+                    let (dx, dy, dz) = (block_x as f64 + 0.5 - center_x, block_y as f64 + 0.5 - center_y, block_z as f64 + 0.5 - center_z);
+
+                    if dx * dx + dy * dy + dz * dz < radius * radius {
+                        region.replace(block_x, block_y, block_z, ore, |state| state == stone);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn place_tree(region: &mut ChunkRegion, random: &mut Random, tree: &Tree, (x, y, z): (i32, i32, i32)) {
+    let log = BlockState::from_name(tree.log).unwrap();
+    let leaves = BlockState::from_name(tree.leaves).unwrap();
+    let height = tree.min_height + random.next_i32_bounded(tree.max_height - tree.min_height + 1);
+    let top = y + height;
+
+    if tree.cone {
+        // layers from the tip down, widening and pulling back in every third layer
+        for (layer, leaves_y) in (y + 2..=top + 1).rev().enumerate() {
+            let radius = ((layer as i32 + 1) / 2).min(3) - if layer % 3 == 2 { 1 } else { 0 };
+
+            for dx in -radius..=radius {
+                for dz in -radius..=radius {
+                    if radius > 0 && dx.abs() == radius && dz.abs() == radius {
+                        continue
+                    }
+
+                    region.replace(x + dx, leaves_y, z + dz, leaves, is_replaceable);
+                }
+            }
+        }
+    } else {
+        // two wide layers below two narrow ones, with some corners left out
+        for leaves_y in top - 3..=top {
+            let radius: i32 = if leaves_y >= top - 1 { 1 } else { 2 };
+
+            for dx in -radius..=radius {
+                for dz in -radius..=radius {
+                    let corner = dx.abs() == radius && dz.abs() == radius;
+
+                    // drawn for every corner, so the canopy never depends on the chunk being decorated
+                    if corner && (leaves_y == top || random.next_bool()) {
+                        continue
+                    }
+
+                    region.replace(x + dx, leaves_y, z + dz, leaves, is_replaceable);
+                }
+            }
+        }
+    }
+
+    for log_y in y..top {
+        region.replace(x, log_y, z, log, |state| is_replaceable(state) || state.name() == tree.leaves);
+    }
+
+    region.replace(x, y - 1, z, BlockState::from_name("minecraft:dirt").unwrap(), |state| state.name() == "minecraft:grass_block");
+}
+
+impl TerrainGenerator {
+    // ores and trees from the chunks around are replayed too, a vein or canopy can reach over the border,
+    // every feature draws from a generator seeded by the chunk it starts in, so it always comes out the same
+    pub(super) fn decorate(&self, chunk: &mut Chunk) {
+        let pos = chunk.pos;
+        let min_y = chunk.min_y();
+        let height = chunk.height();
+        let mut region = ChunkRegion::new(chunk);
+
+        for origin_x in pos.x - 1..=pos.x + 1 {
+            for origin_z in pos.z - 1..=pos.z + 1 {
+                let mut random = Random::for_chunk(self.seed(), origin_x, origin_z, ORE_SALT);
+
+                for ore in ORES {
+                    let state = BlockState::from_name(ore.name).unwrap();
+
+                    for _ in 0..ore.count {
+                        let x = origin_x * 16 + random.next_i32_bounded(16);
+                        let z = origin_z * 16 + random.next_i32_bounded(16);
+                        let y = if ore.triangle {
+                            let half = (ore.max_y - ore.min_y) / 2;
+                            ore.min_y + random.next_i32_bounded(half + 1) + random.next_i32_bounded(half + 1)
+                        } else {
+                            ore.min_y + random.next_i32_bounded(ore.max_y - ore.min_y + 1)
+                        };
+
+                        place_ore(&mut region, &mut Random::new(random.next_i64()), state, ore.size, (x, y, z));
+                    }
+                }
+            }
+        }
+
+        for origin_x in pos.x - 1..=pos.x + 1 {
+            for origin_z in pos.z - 1..=pos.z + 1 {
+                let mut random = Random::for_chunk(self.seed(), origin_x, origin_z, TREE_SALT);
+                let center = self.biomes().climate(origin_x * 16 + 8, origin_z * 16 + 8);
+                let (count, kinds) = trees(self.biomes().biome(&center).name);
+
+                if kinds.is_empty() {
+                    continue
+                }
+
+                let count = count as i32 + if random.next_f64() < count.fract() { 1 } else { 0 };
+
+                for _ in 0..count {
+                    let x = origin_x * 16 + random.next_i32_bounded(16);
+                    let z = origin_z * 16 + random.next_i32_bounded(16);
+                    let tree = &kinds[random.next_i32_bounded(kinds.len() as i32) as usize];
+                    let mut tree_random = Random::new(random.next_i64());
+
+                    // the ground comes from the terrain itself since the chunk the tree stands in might not exist
+                    let Some((surface_y, biome)) = self.surface(x, z, min_y, height) else {
+                        continue
+                    };
+
+                    let ground = self.surface_block(biome.name, surface_y, 0, self.soil_depth(x, z));
+
+                    if surface_y >= SEA_LEVEL - 1 && !trees(biome.name).1.is_empty() && matches!(ground, "minecraft:grass_block" | "minecraft:podzol") {
+                        place_tree(&mut region, &mut tree_random, tree, (x, surface_y + 1, z));
+                    }
+                }
+            }
+        }
+
+        for (index, (template, chance)) in self.structures().iter().enumerate() {
+            let [size_x, _, size_z] = template.size;
+            let range = (size_x.max(size_z) + 15) / 16;
+
+            for origin_x in pos.x - range..=pos.x + range {
+                for origin_z in pos.z - range..=pos.z + range {
+                    let mut random = Random::for_chunk(self.seed(), origin_x, origin_z, STRUCTURE_SALT + index as i64);
+
+                    if random.next_f64() >= *chance {
+                        continue
+                    }
+
+                    let x = origin_x * 16 + random.next_i32_bounded(16);
+                    let z = origin_z * 16 + random.next_i32_bounded(16);
+                    let rotation = ROTATIONS[random.next_i32_bounded(4) as usize];
+
+                    let Some((surface_y, _)) = self.surface(x, z, min_y, height) else {
+                        continue
+                    };
+
+                    if surface_y >= SEA_LEVEL {
+                        let settings = PlacementSettings { rotation, ..PlacementSettings::default() };
+                        template.place_in(&mut region, Pos { x, y: (surface_y + 1) as i16, z }, settings);
+                    }
+                }
+            }
+        }
+
+        // plants stay within their column, so only this chunk is involved
+        let mut random = Random::for_chunk(self.seed(), pos.x, pos.z, PLANT_SALT);
+        let heights = chunk.heightmap(|state| !state.is_air());
+
+        for z in 0..16 {
+            for x in 0..16 {
+                let roll = random.next_f64();
+                let extra = random.next_i32_bounded(3);
+
+                let top = min_y + heights[(z * 16 + x) as usize] as i32 - 1;
+                let ground = chunk.get_block(x, top, z);
+                let Some(biome) = chunk.get_biome(x, top, z).and_then(|id| self.biomes().by_id(id)) else {
+                    continue
+                };
+
+                let mut chance = 0.0;
+                let Some((plant, _)) = plants(biome.name).iter().find(|(_, plant_chance)| {
+                    chance += plant_chance;
+                    roll < chance
+                }) else {
+                    continue
+                };
+
+                match *plant {
+                    // cacti need a free block on every side, which is only known inside the chunk
+                    "minecraft:cactus" => if ground.name() == "minecraft:sand" && (1..15).contains(&x) && (1..15).contains(&z) {
+                        let cactus = BlockState::from_name(plant).unwrap();
+
+                        for y in top + 1..=top + 1 + extra {
+                            chunk.set_block(x, y, z, cactus);
+                        }
+                    },
+                    "minecraft:dead_bush" => if ground.name() == "minecraft:sand" {
+                        chunk.set_block(x, top + 1, z, BlockState::from_name(plant).unwrap());
+                    },
+                    _ => if is_soil(ground) {
+                        chunk.set_block(x, top + 1, z, BlockState::from_name(plant).unwrap());
+                    }
+                }
+            }
+        }
+    }
+}
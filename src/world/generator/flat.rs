@@ -0,0 +1,152 @@
+use crate::{block::BlockState, nbt::{Compound, Tag}, registry::RegistryCodec, world::chunk::Chunk};
+
+use super::WorldGenerator;
+
+pub const PRESETS: &[(&str, &str)] = &[
+    ("classic_flat", "minecraft:bedrock,2*minecraft:dirt,minecraft:grass_block;minecraft:plains"),
+    ("tunnelers_dream", "minecraft:bedrock,230*minecraft:stone,5*minecraft:dirt,minecraft:grass_block;minecraft:windswept_hills"),
+    ("water_world", "minecraft:bedrock,5*minecraft:stone,5*minecraft:dirt,5*minecraft:sand,90*minecraft:water;minecraft:deep_ocean"),
+    ("overworld", "minecraft:bedrock,59*minecraft:stone,3*minecraft:dirt,minecraft:grass_block;minecraft:plains"),
+    ("snowy_kingdom", "minecraft:bedrock,59*minecraft:stone,3*minecraft:dirt,minecraft:grass_block,minecraft:snow;minecraft:snowy_plains"),
+    ("bottomless_pit", "2*minecraft:cobblestone,3*minecraft:dirt,minecraft:grass_block;minecraft:plains"),
+    ("desert", "minecraft:bedrock,3*minecraft:stone,52*minecraft:sandstone,8*minecraft:sand;minecraft:desert"),
+    ("redstone_ready", "minecraft:bedrock,3*minecraft:stone,116*minecraft:sandstone;minecraft:desert"),
+    ("the_void", "minecraft:air;minecraft:the_void")
+];
+
+// the stone platform vanilla places around the spawn of a void world
+const VOID_PLATFORM_RADIUS: i32 = 16;
+const VOID_PLATFORM_Y: i32 = 63;
+
+pub struct FlatGenerator {
+    // bottom to top, starting at the bottom of the world
+    layers: Vec<BlockState>,
+    biome: u16,
+    biome_name: String,
+    void_platform: bool
+}
+
+impl FlatGenerator {
+    pub fn new(layers: &str, biome: &str, registry: &RegistryCodec) -> Result<Self, String> {
+        // the layers start at the bottom of the overworld and may not reach past its top
+        let max_height = registry.dimension_type("minecraft:overworld").map_or(0, |dimension| dimension.height) as usize;
+        let mut states = vec![];
+
+        for layer in layers.split(',').map(str::trim).filter(|layer| !layer.is_empty()) {
+            let (count, name) = match layer.split_once('*') {
+                Some((count, name)) => (count.trim().parse::<usize>().map_err(|_| format!("invalid layer count in '{}'", layer))?, name.trim()),
+                None => (1, layer)
+            };
+
+            if count > max_height - states.len() {
+                return Err(format!("the layers are higher than the world, which is {} blocks high", max_height))
+            }
+
+            let state = BlockState::from_name(name).ok_or_else(|| format!("unknown block '{}'", name))?;
+            states.extend(std::iter::repeat_n(state, count));
+        }
+
+        let biome_id = registry.id("minecraft:worldgen/biome", biome).ok_or_else(|| format!("unknown biome '{}'", biome))?;
+
+        Ok(Self {
+            void_platform: states.iter().all(|state| state.is_air()),
+            layers: states,
+            biome: biome_id as u16,
+            biome_name: biome.to_string()
+        })
+    }
+
+    // the classic "layers;biome" format used by the vanilla presets
+    pub fn from_preset_string(preset: &str, registry: &RegistryCodec) -> Result<Self, String> {
+        let (layers, biome) = preset.split_once(';').unwrap_or((preset, "minecraft:plains"));
+        Self::new(layers, biome, registry)
+    }
+
+    // the settings of a flat generator in level.dat, as written by settings
+    pub fn from_settings(settings: &Compound, registry: &RegistryCodec) -> Result<Self, String> {
+        let mut layers = vec![];
+
+        for layer in settings.get_list("layers").unwrap_or_default() {
+            let layer = layer.as_compound().ok_or("flat layer is not a compound")?;
+            let block = layer.get_str("block").ok_or("flat layer without a block")?;
+            let height = layer.get_i32("height").ok_or("flat layer without a height")?;
+
+            layers.push(format!("{}*{}", height, block));
+        }
+
+        Self::new(&layers.join(","), settings.get_str("biome").unwrap_or("minecraft:plains"), registry)
+    }
+
+    pub fn preset(name: &str, registry: &RegistryCodec) -> Result<Self, String> {
+        let name = name.strip_prefix("minecraft:").unwrap_or(name);
+        let (_, preset) = PRESETS.iter()
+            .find(|(preset_name, _)| *preset_name == name)
+            .ok_or_else(|| format!("unknown flat preset '{}'", name))?;
+
+        Self::from_preset_string(preset, registry)
+    }
+}
+
+impl WorldGenerator for FlatGenerator {
+    fn generate(&self, chunk: &mut Chunk) {
+        let min_y = chunk.min_y();
+
+        for (index, state) in self.layers.iter().enumerate().filter(|(_, state)| !state.is_air()) {
+            for z in 0..16 {
+                for x in 0..16 {
+                    chunk.set_block(x, min_y + index as i32, z, *state);
+                }
+            }
+        }
+
+        for section in chunk.sections_mut() {
+            section.fill_biome(self.biome);
+        }
+
+        if self.void_platform {
+            let stone = BlockState::from_name("minecraft:stone").unwrap();
+            let cobblestone = BlockState::from_name("minecraft:cobblestone").unwrap();
+
+            for z in 0..16 {
+                for x in 0..16 {
+                    let block_x = chunk.pos.min_block_x() + x;
+                    let block_z = chunk.pos.min_block_z() + z;
+
+                    if (block_x - 8).abs() <= VOID_PLATFORM_RADIUS && (block_z - 8).abs() <= VOID_PLATFORM_RADIUS {
+                        let state = if block_x == 8 && block_z == 8 { cobblestone } else { stone };
+                        chunk.set_block(x, VOID_PLATFORM_Y, z, state);
+                    }
+                }
+            }
+        }
+    }
+
+    fn is_flat(&self) -> bool {
+        true
+    }
+
+    fn settings(&self) -> Compound {
+        let mut layers: Vec<(BlockState, i32)> = vec![];
+
+        for state in &self.layers {
+            match layers.last_mut() {
+                Some((last, height)) if last == state => *height += 1,
+                _ => layers.push((*state, 1))
+            }
+        }
+
+        let layers = layers.into_iter()
+            .map(|(state, height)| Tag::Compound(Compound::new()
+                .with("block", Tag::String(state.name().to_string()))
+                .with("height", Tag::Int(height))))
+            .collect();
+
+        Compound::new()
+            .with("type", Tag::String("minecraft:flat".to_string()))
+            .with("settings", Tag::Compound(Compound::new()
+                .with("layers", Tag::List(layers))
+                .with("biome", Tag::String(self.biome_name.clone()))
+                .with("lakes", Tag::Byte(0))
+                .with("features", Tag::Byte(0))))
+    }
+}
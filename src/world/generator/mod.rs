@@ -0,0 +1,100 @@
+use std::sync::Arc;
+
+use crate::{block::BlockState, nbt::{Compound, Tag}, registry::RegistryCodec};
+
+use super::chunk::{BlockEntity, Chunk, ChunkPos};
+
+pub mod biome;
+pub mod carver;
+pub mod feature;
+pub mod flat;
+pub mod noise;
+pub mod random;
+pub mod terrain;
+
+pub use self::flat::FlatGenerator;
+pub use self::terrain::TerrainGenerator;
+
+pub trait WorldGenerator: Send + Sync {
+    // fills a chunk that is still all air
+    fn generate(&self, chunk: &mut Chunk);
+
+    fn is_flat(&self) -> bool {
+        false
+    }
+
+    // how the overworld generator is described in level.dat, so vanilla keeps generating the same kind of terrain
+    fn settings(&self) -> Compound {
+        Compound::new()
+            .with("type", Tag::String("minecraft:noise".to_string()))
+            .with("settings", Tag::String("minecraft:overworld".to_string()))
+            .with("biome_source", Tag::Compound(Compound::new()
+                .with("type", Tag::String("minecraft:multi_noise".to_string()))
+                .with("preset", Tag::String("minecraft:overworld".to_string()))))
+    }
+}
+
+// the generator a world was made with, from the overworld generator in its level.dat
+pub fn from_settings(settings: &Compound, seed: i64, registry: &RegistryCodec) -> Result<Arc<dyn WorldGenerator>, String> {
+    match settings.get_str("type") {
+        Some("minecraft:flat") => {
+            let flat = settings.get_compound("settings").ok_or("flat generator without settings")?;
+            Ok(Arc::new(FlatGenerator::from_settings(flat, registry)?))
+        }
+        // FIXME: every noise generator makes the overworld, whatever its settings and biome source
+        Some("minecraft:noise") => Ok(Arc::new(TerrainGenerator::new(seed, registry)?)),
+        Some(kind) => Err(format!("unknown generator type '{}'", kind)),
+        None => Err("generator without a type".to_string())
+    }
+}
+
+// the chunk being generated in absolute block coordinates, features started in neighbouring chunks
+// are replayed against it and whatever lands outside of it is dropped
+pub struct ChunkRegion<'a> {
+    chunk: &'a mut Chunk
+}
+
+impl<'a> ChunkRegion<'a> {
+    pub fn new(chunk: &'a mut Chunk) -> Self {
+        Self { chunk }
+    }
+
+    pub fn pos(&self) -> ChunkPos {
+        self.chunk.pos
+    }
+
+    pub fn min_y(&self) -> i32 {
+        self.chunk.min_y()
+    }
+
+    pub fn height(&self) -> i32 {
+        self.chunk.height()
+    }
+
+    pub fn contains(&self, x: i32, z: i32) -> bool {
+        x >> 4 == self.chunk.pos.x && z >> 4 == self.chunk.pos.z
+    }
+
+    pub fn get(&self, x: i32, y: i32, z: i32) -> Option<BlockState> {
+        self.contains(x, z).then(|| self.chunk.get_block(x, y, z))
+    }
+
+    pub fn set(&mut self, x: i32, y: i32, z: i32, state: BlockState) {
+        if self.contains(x, z) {
+            self.chunk.set_block(x, y, z, state);
+        }
+    }
+
+    pub fn set_block_entity(&mut self, x: i32, y: i32, z: i32, block_entity: BlockEntity) {
+        if self.contains(x, z) {
+            self.chunk.set_block_entity(x, y, z, block_entity);
+        }
+    }
+
+    // only places the block over blocks that pass the predicate
+    pub fn replace(&mut self, x: i32, y: i32, z: i32, state: BlockState, predicate: impl Fn(BlockState) -> bool) {
+        if self.get(x, y, z).is_some_and(predicate) {
+            self.chunk.set_block(x, y, z, state);
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use super::random::Random;
+
+fn fade(t: f64) -> f64 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(t: f64, a: f64, b: f64) -> f64 {
+    a + t * (b - a)
+}
+
+fn gradient(hash: u8, x: f64, y: f64, z: f64) -> f64 {
+    let hash = hash & 15;
+    let u = if hash < 8 { x } else { y };
+    let v = if hash < 4 { y } else if hash == 12 || hash == 14 { x } else { z };
+
+    (if hash & 1 == 0 { u } else { -u }) + (if hash & 2 == 0 { v } else { -v })
+}
+
+// improved perlin noise with a seeded permutation and origin, roughly in -1..1
+pub struct PerlinNoise {
+    permutation: [u8; 256],
+    origin: (f64, f64, f64)
+}
+
+impl PerlinNoise {
+    pub fn new(random: &mut Random) -> Self {
+        let origin = (random.next_f64() * 256.0, random.next_f64() * 256.0, random.next_f64() * 256.0);
+        let mut permutation = [0u8; 256];
+
+        for (index, value) in permutation.iter_mut().enumerate() {
+            *value = index as u8;
+        }
+
+        for index in 0..256 {
+            let other = index + random.next_i32_bounded(256 - index as i32) as usize;
+            permutation.swap(index, other);
+        }
+
+        Self { permutation, origin }
+    }
+
+    fn hash(&self, value: i32) -> u8 {
+        self.permutation[(value & 255) as usize]
+    }
+
+    pub fn sample(&self, x: f64, y: f64, z: f64) -> f64 {
+        let (x, y, z) = (x + self.origin.0, y + self.origin.1, z + self.origin.2);
+        let (floor_x, floor_y, floor_z) = (x.floor(), y.floor(), z.floor());
+        let (cell_x, cell_y, cell_z) = (floor_x as i32, floor_y as i32, floor_z as i32);
+        let (x, y, z) = (x - floor_x, y - floor_y, z - floor_z);
+        let (u, v, w) = (fade(x), fade(y), fade(z));
+
+        let a = self.hash(cell_x) as i32 + cell_y;
+        let aa = self.hash(a) as i32 + cell_z;
+        let ab = self.hash(a + 1) as i32 + cell_z;
+        let b = self.hash(cell_x + 1) as i32 + cell_y;
+        let ba = self.hash(b) as i32 + cell_z;
+        let bb = self.hash(b + 1) as i32 + cell_z;
+
+        lerp(w,
+            lerp(v,
+                lerp(u, gradient(self.hash(aa), x, y, z), gradient(self.hash(ba), x - 1.0, y, z)),
+                lerp(u, gradient(self.hash(ab), x, y - 1.0, z), gradient(self.hash(bb), x - 1.0, y - 1.0, z))),
+            lerp(v,
+                lerp(u, gradient(self.hash(aa + 1), x, y, z - 1.0), gradient(self.hash(ba + 1), x - 1.0, y, z - 1.0)),
+                lerp(u, gradient(self.hash(ab + 1), x, y - 1.0, z - 1.0), gradient(self.hash(bb + 1), x - 1.0, y - 1.0, z - 1.0))))
+    }
+}
+
+// octave i samples at twice the frequency and half the amplitude of octave i - 1, normalized to roughly -1..1
+pub struct OctaveNoise {
+    octaves: Vec<PerlinNoise>,
+    scale: f64
+}
+
+impl OctaveNoise {
+    // `scale` is the size in blocks of the features of the first octave
+    pub fn new(random: &mut Random, octaves: usize, scale: f64) -> Self {
+        Self {
+            octaves: (0..octaves).map(|_| PerlinNoise::new(random)).collect(),
+            scale
+        }
+    }
+
+    pub fn sample(&self, x: f64, y: f64, z: f64) -> f64 {
+        let mut value = 0.0;
+        let mut frequency = 1.0 / self.scale;
+        let mut amplitude = 1.0;
+        let mut total = 0.0;
+
+        for octave in &self.octaves {
+            value += octave.sample(x * frequency, y * frequency, z * frequency) * amplitude;
+            total += amplitude;
+            frequency *= 2.0;
+            amplitude /= 2.0;
+        }
+
+        value / total
+    }
+
+    pub fn sample_2d(&self, x: f64, z: f64) -> f64 {
+        self.sample(x, 0.0, z)
+    }
+}
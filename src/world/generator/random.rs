@@ -0,0 +1,110 @@
+// the linear congruential generator of java.util.Random, so seeds behave like they do in java
+const MULTIPLIER: i64 = 0x5DEECE66D;
+const ADDEND: i64 = 0xB;
+const MASK: i64 = (1 << 48) - 1;
+
+#[derive(Clone)]
+pub struct Random {
+    seed: i64
+}
+
+impl Random {
+    pub fn new(seed: i64) -> Self {
+        Self { seed: (seed ^ MULTIPLIER) & MASK }
+    }
+
+    // a generator that only depends on the world seed and a block position
+    pub fn at(seed: i64, x: i32, y: i32, z: i32) -> Self {
+        Self::new(seed ^ position_seed(x, y, z))
+    }
+
+    // the features of one chunk, the salt keeps different kinds of features apart
+    pub fn for_chunk(seed: i64, chunk_x: i32, chunk_z: i32, salt: i64) -> Self {
+        let mut random = Self::new(seed);
+        let x = random.next_i64() | 1;
+        let z = random.next_i64() | 1;
+
+        Self::new(((chunk_x as i64).wrapping_mul(x) ^ (chunk_z as i64).wrapping_mul(z) ^ seed).wrapping_add(salt))
+    }
+
+    fn next(&mut self, bits: u32) -> i32 {
+        self.seed = self.seed.wrapping_mul(MULTIPLIER).wrapping_add(ADDEND) & MASK;
+        (self.seed >> (48 - bits)) as i32
+    }
+
+    pub fn next_i32(&mut self) -> i32 {
+        self.next(32)
+    }
+
+    // uniform in 0..bound
+    pub fn next_i32_bounded(&mut self, bound: i32) -> i32 {
+        assert!(bound > 0, "bound must be positive");
+
+        if bound & (bound - 1) == 0 {
+            return ((bound as i64 * self.next(31) as i64) >> 31) as i32
+        }
+
+        loop {
+            let bits = self.next(31);
+            let value = bits % bound;
+
+            if bits.wrapping_sub(value).wrapping_add(bound - 1) >= 0 {
+                return value
+            }
+        }
+    }
+
+    pub fn next_i64(&mut self) -> i64 {
+        ((self.next(32) as i64) << 32).wrapping_add(self.next(32) as i64)
+    }
+
+    pub fn next_bool(&mut self) -> bool {
+        self.next(1) != 0
+    }
+
+    pub fn next_f32(&mut self) -> f32 {
+        self.next(24) as f32 / (1 << 24) as f32
+    }
+
+    pub fn next_f64(&mut self) -> f64 {
+        (((self.next(26) as i64) << 27) + self.next(27) as i64) as f64 / (1i64 << 53) as f64
+    }
+}
+
+pub fn position_seed(x: i32, y: i32, z: i32) -> i64 {
+    let seed = (x as i64).wrapping_mul(3129871) ^ (z as i64).wrapping_mul(116129781) ^ y as i64;
+    seed.wrapping_mul(seed).wrapping_mul(42317861).wrapping_add(seed.wrapping_mul(11)) >> 16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the expected values come from java.util.Random
+    #[test]
+    fn matches_java() {
+        let mut random = Random::new(0);
+        assert_eq!(random.next_i32(), -1155484576);
+        assert_eq!(random.next_i32(), -723955400);
+
+        assert_eq!(Random::new(0).next_i64(), -4962768465676381896);
+        assert_eq!(Random::new(0).next_f64(), 0.730967787376657);
+        assert_eq!(Random::new(0).next_f32(), 0.73096776);
+    }
+
+    #[test]
+    fn bounded_matches_java() {
+        let mut random = Random::new(42);
+        assert_eq!([0; 5].map(|_| random.next_i32_bounded(10)), [0, 3, 8, 4, 0]);
+
+        let mut random = Random::new(42);
+        assert_eq!([0; 3].map(|_| random.next_i32_bounded(16)), [11, 0, 10]);
+    }
+
+    // large bounds reject values close to i32::MAX, which overflows the check like it does in java
+    #[test]
+    fn bounded_rejects_without_overflow() {
+        let mut random = Random::new(3);
+        assert_eq!([0; 6].map(|_| random.next_i32_bounded(1500000000)), [1207763660, 152454210, 144139128, 1249547202, 1235116564, 488197459]);
+    }
+}
@@ -0,0 +1,313 @@
+use crate::{block::BlockState, registry::RegistryCodec, world::{chunk::{Chunk, SECTION_WIDTH}, structure::StructureTemplate}};
+
+use super::{biome::{Biome, Climate, MultiNoiseBiomeSource, RIVER_WEIRDNESS}, noise::OctaveNoise, random::Random, WorldGenerator};
+
+pub const SEA_LEVEL: i32 = 63;
+
+// density is sampled at the corners of cells this big and interpolated in between
+const CELL_WIDTH: i32 = 4;
+const CELL_HEIGHT: i32 = 8;
+const CELLS_PER_CHUNK: i32 = 16 / CELL_WIDTH;
+
+const BEDROCK_LAYERS: i32 = 5;
+
+// terrain height at the given continentalness, linearly interpolated in between
+const CONTINENT_HEIGHTS: &[(f64, f64)] = &[
+    (-1.2, 72.0), (-1.1, 36.0), (-0.455, 40.0), (-0.19, 54.0), (-0.11, 63.0), (0.03, 68.0), (0.3, 76.0), (1.0, 96.0)
+];
+
+fn lerp(t: f64, a: f64, b: f64) -> f64 {
+    a + t * (b - a)
+}
+
+// density inside a cell from its corners at the bottom and top, each ordered -x -z, +x -z, -x +z, +x +z
+fn interpolate(dx: f64, dy: f64, dz: f64, bottom: [f64; 4], top: [f64; 4]) -> f64 {
+    let blend = |[near_low, near_high, far_low, far_high]: [f64; 4]| lerp(dz, lerp(dx, near_low, near_high), lerp(dx, far_low, far_high));
+    lerp(dy, blend(bottom), blend(top))
+}
+
+fn continent_height(continentalness: f64) -> f64 {
+    let upper = CONTINENT_HEIGHTS.iter().position(|(at, _)| *at >= continentalness).unwrap_or(CONTINENT_HEIGHTS.len() - 1).max(1);
+    let (low, low_height) = CONTINENT_HEIGHTS[upper - 1];
+    let (high, high_height) = CONTINENT_HEIGHTS[upper];
+
+    lerp(((continentalness - low) / (high - low)).clamp(0.0, 1.0), low_height, high_height)
+}
+
+fn is_snowy(biome: &str) -> bool {
+    matches!(biome,
+        "snowy_plains" | "snowy_taiga" | "snowy_slopes" | "snowy_beach" | "grove" | "frozen_peaks" | "jagged_peaks"
+            | "frozen_river" | "frozen_ocean" | "deep_frozen_ocean")
+}
+
+pub struct TerrainGenerator {
+    seed: i64,
+    biomes: MultiNoiseBiomeSource,
+    density: OctaveNoise,
+    surface: OctaveNoise,
+    // templates scattered over dry land with the chance per chunk of one starting there
+    structures: Vec<(StructureTemplate, f64)>
+}
+
+impl TerrainGenerator {
+    pub fn new(seed: i64, registry: &RegistryCodec) -> Result<Self, String> {
+        let mut random = Random::new(seed);
+
+        Ok(Self {
+            seed,
+            biomes: MultiNoiseBiomeSource::new(&mut random, registry)?,
+            density: OctaveNoise::new(&mut random, 4, 96.0),
+            surface: OctaveNoise::new(&mut random, 2, 16.0),
+            structures: vec![]
+        })
+    }
+
+    pub fn add_structure(&mut self, template: StructureTemplate, chance: f64) {
+        self.structures.push((template, chance));
+    }
+
+    pub fn structures(&self) -> &[(StructureTemplate, f64)] {
+        &self.structures
+    }
+
+    pub fn seed(&self) -> i64 {
+        self.seed
+    }
+
+    pub fn biomes(&self) -> &MultiNoiseBiomeSource {
+        &self.biomes
+    }
+
+    // the height the terrain settles around and how far the 3d noise may push it away from that
+    fn shape(&self, climate: &Climate) -> (f64, f64) {
+        let mut height = continent_height(climate.continentalness);
+        let mut spread = 4.0 + 24.0 * ((1.0 - climate.erosion) / 2.0).powi(2);
+
+        if climate.continentalness > -0.11 {
+            // low erosion far inland raises mountains
+            let mountains = ((-climate.erosion - 0.375) / 0.625).max(0.0) * ((climate.continentalness + 0.11) / 0.4).clamp(0.0, 1.0);
+            height += mountains * 100.0;
+
+            // high erosion flattens out into swamps at sea level
+            let flatness = ((climate.erosion - 0.55) / 0.45).max(0.0);
+            height = lerp(flatness, height, SEA_LEVEL as f64);
+            spread = lerp(flatness, spread, 2.0);
+
+            // rivers cut a channel wherever weirdness crosses zero
+            if climate.continentalness < 0.55 {
+                let river = (1.0 - climate.weirdness.abs() / (RIVER_WEIRDNESS * 2.0)).max(0.0);
+                height = lerp(river, height, SEA_LEVEL as f64 - 5.0);
+                spread = lerp(river, spread, 2.0);
+            }
+        }
+
+        (height, spread)
+    }
+
+    // positive inside the ground
+    fn density(&self, (height, spread): (f64, f64), x: i32, y: i32, z: i32) -> f64 {
+        (height - y as f64) / spread + self.density.sample(x as f64, y as f64 * 2.0, z as f64)
+    }
+
+    pub(super) fn soil_depth(&self, x: i32, z: i32) -> i32 {
+        3 + (self.surface.sample_2d(x as f64, z as f64) * 4.0) as i32
+    }
+
+    // the topmost solid block of a column and the biome there, exactly as `generate` places it but without the chunk
+    pub fn surface(&self, x: i32, z: i32, min_y: i32, height: i32) -> Option<(i32, Biome)> {
+        let cells_y = height / CELL_HEIGHT;
+        let (corner_x, corner_z) = (x.div_euclid(CELL_WIDTH) * CELL_WIDTH, z.div_euclid(CELL_WIDTH) * CELL_WIDTH);
+        let (dx, dz) = ((x - corner_x) as f64 / CELL_WIDTH as f64, (z - corner_z) as f64 / CELL_WIDTH as f64);
+
+        let columns = [(0, 0), (CELL_WIDTH, 0), (0, CELL_WIDTH), (CELL_WIDTH, CELL_WIDTH)].map(|(offset_x, offset_z)| {
+            let (x, z) = (corner_x + offset_x, corner_z + offset_z);
+            let climate = self.biomes.climate(x, z);
+            (x, z, climate, self.shape(&climate))
+        });
+
+        let densities = |cell_y: i32| columns.map(|(x, z, _, shape)| self.density(shape, x, min_y + cell_y * CELL_HEIGHT, z));
+
+        // the noise never pushes density past one, so no corner above height + spread can be solid
+        let ceiling = columns.iter().map(|(_, _, _, (height, spread))| height + spread * 1.5).fold(f64::MIN, f64::max);
+        let top_cell = (((ceiling - min_y as f64) / CELL_HEIGHT as f64).ceil() as i32).clamp(0, cells_y - 1);
+
+        for cell_y in (0..=top_cell).rev() {
+            let (bottom, top) = (densities(cell_y), densities((cell_y + 1).min(cells_y)));
+
+            for offset in (0..CELL_HEIGHT).rev() {
+                if interpolate(dx, offset as f64 / CELL_HEIGHT as f64, dz, bottom, top) > 0.0 {
+                    return Some((min_y + cell_y * CELL_HEIGHT + offset, self.biomes.biome(&columns[0].2)))
+                }
+            }
+        }
+
+        None
+    }
+
+    pub(super) fn surface_block(&self, biome: &str, surface_y: i32, depth: i32, soil_depth: i32) -> &'static str {
+        if depth >= soil_depth {
+            return "minecraft:stone"
+        }
+
+        if surface_y < SEA_LEVEL - 1 {
+            return if surface_y < SEA_LEVEL - 8 { "minecraft:gravel" } else { "minecraft:sand" }
+        }
+
+        match biome {
+            "desert" | "beach" | "snowy_beach" => if depth < 3 { "minecraft:sand" } else { "minecraft:sandstone" },
+            "stony_shore" | "stony_peaks" => "minecraft:stone",
+            "frozen_peaks" | "jagged_peaks" => if depth == 0 { "minecraft:snow_block" } else { "minecraft:stone" },
+            "old_growth_spruce_taiga" if depth == 0 => "minecraft:podzol",
+            _ => if depth == 0 { "minecraft:grass_block" } else { "minecraft:dirt" }
+        }
+    }
+}
+
+impl WorldGenerator for TerrainGenerator {
+    fn generate(&self, chunk: &mut Chunk) {
+        let min_y = chunk.min_y();
+        let cells_y = chunk.height() / CELL_HEIGHT;
+        let min_x = chunk.pos.min_block_x();
+        let min_z = chunk.pos.min_block_z();
+
+        // cell corners line up with the 4x4 biome columns of the chunk
+        let column = |cell_x: i32, cell_z: i32| (cell_x * (CELLS_PER_CHUNK + 1) + cell_z) as usize;
+        let mut climates = vec![];
+        let mut shapes = vec![];
+
+        for cell_x in 0..=CELLS_PER_CHUNK {
+            for cell_z in 0..=CELLS_PER_CHUNK {
+                let climate = self.biomes.climate(min_x + cell_x * CELL_WIDTH, min_z + cell_z * CELL_WIDTH);
+                shapes.push(self.shape(&climate));
+                climates.push(climate);
+            }
+        }
+
+        let corner = |cell_x: i32, cell_y: i32, cell_z: i32| column(cell_x, cell_z) * (cells_y + 1) as usize + cell_y as usize;
+        let mut corners = vec![0.0; corner(CELLS_PER_CHUNK + 1, 0, 0)];
+
+        for cell_x in 0..=CELLS_PER_CHUNK {
+            for cell_z in 0..=CELLS_PER_CHUNK {
+                let (x, z) = (min_x + cell_x * CELL_WIDTH, min_z + cell_z * CELL_WIDTH);
+
+                for cell_y in 0..=cells_y {
+                    corners[corner(cell_x, cell_y, cell_z)] = self.density(shapes[column(cell_x, cell_z)], x, min_y + cell_y * CELL_HEIGHT, z);
+                }
+            }
+        }
+
+        let surface_biomes: Vec<Biome> = climates.iter().map(|climate| self.biomes.biome(climate)).collect();
+
+        let water = BlockState::from_name("minecraft:water").unwrap();
+        let ice = BlockState::from_name("minecraft:ice").unwrap();
+        let snow = BlockState::from_name("minecraft:snow").unwrap();
+        let snowy_grass = BlockState::from_properties("minecraft:grass_block", &[("snowy", "true")]).unwrap();
+        let bedrock = BlockState::from_name("minecraft:bedrock").unwrap();
+
+        for x in 0..16 {
+            for z in 0..16 {
+                let (cell_x, cell_z) = (x / CELL_WIDTH, z / CELL_WIDTH);
+                let (dx, dz) = ((x % CELL_WIDTH) as f64 / CELL_WIDTH as f64, (z % CELL_WIDTH) as f64 / CELL_WIDTH as f64);
+                let biome = surface_biomes[column(cell_x, cell_z)].name;
+
+                let soil_depth = self.soil_depth(min_x + x, min_z + z);
+                let densities = |cell_y: i32| [
+                    corners[corner(cell_x, cell_y, cell_z)], corners[corner(cell_x + 1, cell_y, cell_z)],
+                    corners[corner(cell_x, cell_y, cell_z + 1)], corners[corner(cell_x + 1, cell_y, cell_z + 1)]
+                ];
+
+                // the first solid block from the top and how many solid blocks have come since
+                let mut surface_y = None;
+                let mut depth = 0;
+
+                for y in (min_y..min_y + chunk.height()).rev() {
+                    let cell_y = (y - min_y) / CELL_HEIGHT;
+                    let dy = ((y - min_y) % CELL_HEIGHT) as f64 / CELL_HEIGHT as f64;
+                    let density = interpolate(dx, dy, dz, densities(cell_y), densities((cell_y + 1).min(cells_y)));
+
+                    if density > 0.0 {
+                        let surface_y = *surface_y.get_or_insert(y);
+                        let name = self.surface_block(biome, surface_y, depth, soil_depth);
+                        chunk.set_block(x, y, z, BlockState::from_name(name).unwrap());
+                        depth += 1;
+                    } else if surface_y.is_none() && y < SEA_LEVEL {
+                        let frozen = y == SEA_LEVEL - 1 && is_snowy(biome);
+                        chunk.set_block(x, y, z, if frozen { ice } else { water });
+                    }
+                }
+
+                if let Some(surface_y) = surface_y {
+                    if is_snowy(biome) && chunk.get_block(x, surface_y, z).name() == "minecraft:grass_block" {
+                        chunk.set_block(x, surface_y, z, snowy_grass);
+                        chunk.set_block(x, surface_y + 1, z, snow);
+                    }
+                }
+
+                // solid at the very bottom, thinning out over the layers above
+                for layer in 0..BEDROCK_LAYERS {
+                    let y = min_y + layer;
+
+                    if layer == 0 || Random::at(self.seed, min_x + x, y, min_z + z).next_i32_bounded(BEDROCK_LAYERS) < BEDROCK_LAYERS - layer {
+                        chunk.set_block(x, y, z, bedrock);
+                    }
+                }
+            }
+        }
+
+        // biomes below the surface move towards the cave biomes
+        for (index, section) in chunk.sections_mut().iter_mut().enumerate() {
+            for quart_y in 0..4 {
+                let y = min_y + index as i32 * SECTION_WIDTH + quart_y * 4;
+
+                for quart_x in 0..4 {
+                    for quart_z in 0..4 {
+                        let column = column(quart_x, quart_z);
+                        let climate = Climate { depth: (shapes[column].0 - y as f64) / 128.0, ..climates[column] };
+
+                        section.set_biome(quart_x as usize, quart_y as usize, quart_z as usize, self.biomes.biome(&climate).id);
+                    }
+                }
+            }
+        }
+
+        self.carve(chunk);
+        self.decorate(chunk);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::world::chunk::ChunkPos;
+
+    use super::*;
+
+    fn generate(generator: &TerrainGenerator, pos: ChunkPos) -> Chunk {
+        let mut chunk = Chunk::new(pos, -64, 384, 0);
+        generator.generate(&mut chunk);
+        chunk
+    }
+
+    #[test]
+    fn same_seed_gives_same_chunks() {
+        let registry = RegistryCodec::vanilla();
+
+        for pos in [ChunkPos::new(0, 0), ChunkPos::new(-7, 12), ChunkPos::new(1000, -3000)] {
+            let first = generate(&TerrainGenerator::new(1234, &registry).unwrap(), pos);
+            let second = generate(&TerrainGenerator::new(1234, &registry).unwrap(), pos);
+
+            for (first, second) in first.sections().iter().zip(second.sections()) {
+                assert!(first.blocks() == second.blocks());
+                assert!(first.biomes() == second.biomes());
+            }
+        }
+    }
+
+    #[test]
+    fn different_seeds_give_different_chunks() {
+        let registry = RegistryCodec::vanilla();
+        let first = generate(&TerrainGenerator::new(1, &registry).unwrap(), ChunkPos::new(0, 0));
+        let second = generate(&TerrainGenerator::new(2, &registry).unwrap(), ChunkPos::new(0, 0));
+
+        assert!(first.sections().iter().zip(second.sections()).any(|(first, second)| first.blocks() != second.blocks()));
+    }
+}
@@ -0,0 +1,290 @@
+use std::{collections::BTreeMap, fs::File, io, path::Path, time::{SystemTime, UNIX_EPOCH}};
+
+use crate::{nbt::{Compound, Tag}, types::Pos};
+
+use super::{save, DATA_VERSION};
+
+// the anvil format version, the same since 1.2
+const ANVIL_VERSION: i32 = 19133;
+const VERSION_NAME: &str = "1.19.4";
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+// every rule /gamerule knows, with its default, the rules that are not true or false hold integers
+pub const GAME_RULES: &[(&str, &str)] = &[
+    ("announceAdvancements", "true"),
+    ("commandBlockOutput", "true"),
+    ("disableElytraMovementCheck", "false"),
+    ("disableRaids", "false"),
+    ("doDaylightCycle", "true"),
+    ("doEntityDrops", "true"),
+    ("doFireTick", "true"),
+    ("doImmediateRespawn", "false"),
+    ("doInsomnia", "true"),
+    ("doLimitedCrafting", "false"),
+    ("doMobLoot", "true"),
+    ("doMobSpawning", "true"),
+    ("doPatrolSpawning", "true"),
+    ("doTileDrops", "true"),
+    ("doTraderSpawning", "true"),
+    ("doWardenSpawning", "true"),
+    ("doWeatherCycle", "true"),
+    ("drowningDamage", "true"),
+    ("fallDamage", "true"),
+    ("fireDamage", "true"),
+    ("forgiveDeadPlayers", "true"),
+    ("freezeDamage", "true"),
+    ("keepInventory", "false"),
+    ("logAdminCommands", "true"),
+    ("maxCommandChainLength", "65536"),
+    ("maxEntityCramming", "24"),
+    ("mobGriefing", "true"),
+    ("naturalRegeneration", "true"),
+    ("playersSleepingPercentage", "100"),
+    ("randomTickSpeed", "3"),
+    ("reducedDebugInfo", "false"),
+    ("sendCommandFeedback", "true"),
+    ("showDeathMessages", "true"),
+    ("spawnRadius", "10"),
+    ("spectatorsGenerateChunks", "true"),
+    ("universalAnger", "false")
+];
+
+fn string(value: &str) -> Tag {
+    Tag::String(value.to_string())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Weather {
+    Clear,
+    Rain,
+    // rain with thunder
+    Thunder
+}
+
+pub struct LevelData {
+    pub name: String,
+    pub seed: i64,
+    pub spawn: Pos,
+    pub spawn_angle: f32,
+    pub time: i64,
+    pub day_time: i64,
+    // 0 is peaceful, 3 is hard
+    pub difficulty: u8,
+    pub difficulty_locked: bool,
+    pub raining: bool,
+    pub thundering: bool,
+    // ticks until the weather changes, clear weather counts down first
+    pub clear_weather_time: i32,
+    pub rain_time: i32,
+    pub thunder_time: i32,
+    pub game_rules: BTreeMap<String, String>,
+    // whatever else vanilla keeps in level.dat, written back untouched
+    data: Compound
+}
+
+impl LevelData {
+    pub fn new(name: &str, seed: i64) -> Self {
+        Self {
+            name: name.to_string(),
+            seed,
+            spawn: Pos { x: 0, y: 64, z: 0 },
+            spawn_angle: 0.0,
+            time: 0,
+            day_time: 0,
+            difficulty: 2,
+            difficulty_locked: false,
+            raining: false,
+            thundering: false,
+            clear_weather_time: 0,
+            rain_time: 0,
+            thunder_time: 0,
+            game_rules: BTreeMap::new(),
+            data: Compound::new()
+                .with("GameType", Tag::Int(0))
+                .with("allowCommands", Tag::Byte(0))
+                .with("hardcore", Tag::Byte(0))
+                .with("initialized", Tag::Byte(1))
+                .with("DataPacks", Tag::Compound(Compound::new()
+                    .with("Enabled", Tag::List(vec![string("vanilla")]))
+                    .with("Disabled", Tag::List(vec![]))))
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let (_, root) = Compound::read_gzip(File::open(path)?)?;
+        let data = root.get_compound("Data").ok_or_else(|| invalid_data("level.dat without Data"))?;
+
+        Self::from_nbt(data).ok_or_else(|| invalid_data("level.dat is missing the seed or the spawn"))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        save::write_atomic(path.as_ref(), |file| self.to_file().write_gzip(file, ""))
+    }
+
+    // the root of level.dat
+    pub fn to_file(&self) -> Compound {
+        Compound::new().with("Data", Tag::Compound(self.to_nbt()))
+    }
+
+    pub fn from_nbt(data: &Compound) -> Option<Self> {
+        let seed = data.get_compound("WorldGenSettings")
+            .and_then(|settings| settings.get_i64("seed"))
+            // before 1.16
+            .or_else(|| data.get_i64("RandomSeed"))?;
+
+        let game_rules = data.get_compound("GameRules")
+            .map(|rules| rules.0.iter().filter_map(|(name, value)| Some((name.clone(), value.as_str()?.to_string()))).collect())
+            .unwrap_or_default();
+
+        Some(Self {
+            name: data.get_str("LevelName").unwrap_or("world").to_string(),
+            seed,
+            spawn: Pos {
+                x: data.get_i32("SpawnX")?,
+                y: data.get_i32("SpawnY")? as i16,
+                z: data.get_i32("SpawnZ")?
+            },
+            spawn_angle: data.get("SpawnAngle").and_then(Tag::as_f64).unwrap_or(0.0) as f32,
+            time: data.get_i64("Time").unwrap_or(0),
+            day_time: data.get_i64("DayTime").unwrap_or(0),
+            difficulty: data.get_i32("Difficulty").unwrap_or(2).clamp(0, 3) as u8,
+            difficulty_locked: data.get_i32("DifficultyLocked").is_some_and(|locked| locked != 0),
+            raining: data.get_i32("raining").is_some_and(|raining| raining != 0),
+            thundering: data.get_i32("thundering").is_some_and(|thundering| thundering != 0),
+            clear_weather_time: data.get_i32("clearWeatherTime").unwrap_or(0),
+            rain_time: data.get_i32("rainTime").unwrap_or(0),
+            thunder_time: data.get_i32("thunderTime").unwrap_or(0),
+            game_rules,
+            data: data.clone()
+        })
+    }
+
+    // a game rule as vanilla stores it, None when it was never set and the default applies
+    pub fn game_rule(&self, name: &str) -> Option<&str> {
+        self.game_rules.get(name).map(String::as_str)
+    }
+
+    // what the rule is in effect, None for a rule that does not exist
+    pub fn game_rule_or_default(&self, name: &str) -> Option<&str> {
+        self.game_rule(name).or_else(|| GAME_RULES.iter().find(|(rule, _)| *rule == name).map(|(_, default)| *default))
+    }
+
+    pub fn weather(&self) -> Weather {
+        match (self.raining, self.thundering) {
+            (true, true) => Weather::Thunder,
+            (true, false) => Weather::Rain,
+            _ => Weather::Clear
+        }
+    }
+
+    // like /weather, the weather stays for that many ticks
+    pub fn set_weather(&mut self, weather: Weather, duration: i32) {
+        let (clear, rain) = match weather {
+            Weather::Clear => (duration, 0),
+            _ => (0, duration)
+        };
+
+        self.clear_weather_time = clear;
+        self.rain_time = rain;
+        self.thunder_time = rain;
+        self.raining = weather != Weather::Clear;
+        self.thundering = weather == Weather::Thunder;
+    }
+
+    // FIXME: the weather only ever clears up, it never starts on its own
+    pub fn tick_weather(&mut self) {
+        if self.clear_weather_time > 0 {
+            self.clear_weather_time -= 1;
+            return
+        }
+
+        if self.thunder_time > 0 {
+            self.thunder_time -= 1;
+            self.thundering &= self.thunder_time > 0;
+        }
+
+        if self.rain_time > 0 {
+            self.rain_time -= 1;
+            self.raining &= self.rain_time > 0;
+        }
+    }
+
+    pub fn set_game_rule(&mut self, name: &str, value: impl ToString) {
+        self.game_rules.insert(name.to_string(), value.to_string());
+    }
+
+    // the overworld generator as set_generator or vanilla described it
+    pub fn generator(&self) -> Option<&Compound> {
+        self.data.get_compound("WorldGenSettings")?
+            .get_compound("dimensions")?
+            .get_compound("minecraft:overworld")?
+            .get_compound("generator")
+    }
+
+    // FIXME: the nether and the end are described so vanilla can open the world, the server itself only has the overworld
+    pub fn set_generator(&mut self, overworld: Compound) {
+        let dimension = |kind: &str, generator: Compound| Tag::Compound(Compound::new()
+            .with("type", string(kind))
+            .with("generator", Tag::Compound(generator)));
+
+        let noise = |settings: &str, biome_source: Compound| Compound::new()
+            .with("type", string("minecraft:noise"))
+            .with("settings", string(settings))
+            .with("biome_source", Tag::Compound(biome_source));
+
+        let dimensions = Compound::new()
+            .with("minecraft:overworld", dimension("minecraft:overworld", overworld))
+            .with("minecraft:the_nether", dimension("minecraft:the_nether", noise("minecraft:nether", Compound::new()
+                .with("type", string("minecraft:multi_noise"))
+                .with("preset", string("minecraft:nether")))))
+            .with("minecraft:the_end", dimension("minecraft:the_end", noise("minecraft:end", Compound::new()
+                .with("type", string("minecraft:the_end")))));
+
+        let mut settings = self.data.get_compound("WorldGenSettings").cloned().unwrap_or_else(|| Compound::new()
+            .with("generate_features", Tag::Byte(1))
+            .with("bonus_chest", Tag::Byte(0)));
+
+        settings.insert("dimensions", Tag::Compound(dimensions));
+        self.data.insert("WorldGenSettings", Tag::Compound(settings));
+    }
+
+    pub fn to_nbt(&self) -> Compound {
+        let mut data = self.data.clone();
+        let mut settings = data.get_compound("WorldGenSettings").cloned().unwrap_or_default();
+        settings.insert("seed", Tag::Long(self.seed));
+
+        let last_played = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as i64;
+
+        data.insert("DataVersion", Tag::Int(DATA_VERSION));
+        data.insert("version", Tag::Int(ANVIL_VERSION));
+        data.insert("Version", Tag::Compound(Compound::new()
+            .with("Id", Tag::Int(DATA_VERSION))
+            .with("Name", string(VERSION_NAME))
+            .with("Series", string("main"))
+            .with("Snapshot", Tag::Byte(0))));
+        data.insert("LevelName", string(&self.name));
+        data.insert("LastPlayed", Tag::Long(last_played));
+        data.insert("WorldGenSettings", Tag::Compound(settings));
+        data.insert("SpawnX", Tag::Int(self.spawn.x));
+        data.insert("SpawnY", Tag::Int(self.spawn.y as i32));
+        data.insert("SpawnZ", Tag::Int(self.spawn.z));
+        data.insert("SpawnAngle", Tag::Float(self.spawn_angle));
+        data.insert("Time", Tag::Long(self.time));
+        data.insert("DayTime", Tag::Long(self.day_time));
+        data.insert("Difficulty", Tag::Byte(self.difficulty as i8));
+        data.insert("DifficultyLocked", Tag::Byte(self.difficulty_locked as i8));
+        data.insert("raining", Tag::Byte(self.raining as i8));
+        data.insert("thundering", Tag::Byte(self.thundering as i8));
+        data.insert("clearWeatherTime", Tag::Int(self.clear_weather_time));
+        data.insert("rainTime", Tag::Int(self.rain_time));
+        data.insert("thunderTime", Tag::Int(self.thunder_time));
+        data.insert("GameRules", Tag::Compound(Compound(
+            self.game_rules.iter().map(|(name, value)| (name.clone(), string(value))).collect()
+        )));
+
+        data
+    }
+}
@@ -0,0 +1,2081 @@
+use std::{collections::{HashMap, HashSet}, io, path::PathBuf, sync::Arc, time::{Duration, Instant, SystemTime, UNIX_EPOCH}};
+
+use crate::{error, info};
+use sha2::{Digest, Sha256};
+use tokio::{sync::{mpsc, oneshot}, time};
+
+use crate::{block::BlockState, nbt::Compound, command::{self, CommandDispatcher, CommandResult, CommandSender, CommandSource, NodeBuilder, MAX_PERMISSION_LEVEL}, chat::{self, session::{ChatError, SignedSession}, ChatFormatter, ChatMode, ChatVisibility, DefaultFormatter}, connection::{Connection, ConnectionWriter, Play}, packets::play::{serverbound::{self, ChatMessageData, PlayerSessionData}, clientbound::{self, ChangeDifficultyData, CommandSuggestionsResponseData, CommandsData, DisconnectData, DisguisedChatMessageData, EntityEventData, GameEventData, LoginData, PlayerChatMessageData, PlayerInfoRemoveData, PlayerInfoUpdateData, RespawnData, ServerDataData, SetCenterChunkData, SetDefaultSpawnPositionData, SetRenderDistanceData, SetSimulationDistanceData, SetTabListHeaderAndFooterData, SystemChatMessageData, UnloadChunkData, UpdateTimeData}}, player::{DisconnectReason, Player}, plugin::Plugin, registry::{DimensionType, RegistryCodec}, text, types::{ChatSession, FilterMask, Suggestion, Identifier, PlayerInfo, PlayerInfoEntries, Pos, PreviousMessage, ProfileProperty, UUID}};
+
+use self::{access::AccessList, anvil::RegionStorage, level::{LevelData, Weather}, save::Saver, storage::ChunkStorage, ticket::{LoadLevel, Ticket, TicketKind, TicketManager}, tracker::EntityTracker, view::ChunkView, worker::{ChunkSource, ChunkWorkers}, chunk::{BlockEntity, Chunk, ChunkPos}, generator::{TerrainGenerator, WorldGenerator}, structure::{PlacementSettings, StructureTemplate}};
+
+pub mod access;
+pub mod anvil;
+pub mod chunk;
+pub mod generator;
+pub mod level;
+pub mod palette;
+pub mod save;
+pub mod storage;
+pub mod structure;
+pub mod ticket;
+pub mod tracker;
+pub mod view;
+pub mod worker;
+mod encode;
+
+pub const DATA_VERSION: i32 = 3337;
+
+pub const TICK_DURATION: Duration = Duration::from_millis(50);
+pub const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(15);
+pub const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(300);
+pub const RENDER_DISTANCE: i32 = 5;
+pub const SIMULATION_DISTANCE: i32 = 5;
+pub const MAX_PLAYERS: usize = 20;
+// per player, everything past that waits for the next tick
+pub const CHUNKS_PER_TICK: usize = 4;
+// the priority of chunks no player is waiting for, they load after everything players can see
+const UNWATCHED_PRIORITY: i32 = i32::MAX;
+// the priority of chunks the world waits for to finish a join, a structure or the spawn search, they go before everything
+const PENDING_PRIORITY: i32 = -1;
+
+// the furthest a player may move with one packet, anything more is sent back
+pub const MAX_MOVEMENT: f64 = 10.0;
+// past the world border in every direction, a client claiming to be out there is kicked
+const MAX_COORDINATE: f64 = 3.0e7;
+const MAX_HEIGHT: f64 = 2.0e7;
+// the box of a standing player, and how far into a block it may reach before it counts as inside
+const PLAYER_WIDTH: f64 = 0.6;
+const PLAYER_HEIGHT: f64 = 1.8;
+const COLLISION_MARGIN: f64 = 1.0e-5;
+// how long a player may float without falling before it is kicked for flying, like vanilla
+const MAX_AIRBORNE_TICKS: u32 = 80;
+
+// for every template in generated/<namespace>/structures/worldgen, the chance of one starting in a chunk of noise terrain
+const WORLDGEN_STRUCTURE_CHANCE: f64 = 0.01;
+
+// how many chunks away from the origin a dry spawn is looked for
+const SPAWN_SEARCH_RADIUS: i32 = 8;
+
+// in ticks, the clients run their own clock and only get corrected this often
+const TIME_UPDATE_INTERVAL: i64 = 20;
+
+pub enum WorldEvent {
+    Connect { connection: Connection<Play>, uuid: UUID, username: String, properties: Vec<ProfileProperty> },
+    Packet(i32, serverbound::Packet),
+    Disconnect(i32, DisconnectReason),
+    // a command typed into the console, without the slash
+    ConsoleCommand(String),
+    // what the console could complete the command with, and where the completions start
+    CompleteCommand(String, oneshot::Sender<(usize, Vec<String>)>),
+    // kicks everyone, saves the world and stops the tick loop
+    Shutdown
+}
+
+// something the tick loop finishes once the workers brought back every chunk it needs
+enum Pending {
+    // a player that gets into the world once the chunks around the spawn are there
+    Join(Box<Player>),
+    Structure { chunks: Vec<ChunkPos>, blocks: Vec<(Pos, BlockState, Option<Compound>)> },
+    // one ring of chunks around the origin at a time, the first dry spawn in a ring wins
+    SpawnSearch { radius: i32, fallback: Option<Pos> }
+}
+
+pub struct World {
+    directory: PathBuf,
+    registry: Arc<RegistryCodec>,
+    dimension: DimensionType,
+    chunks: HashMap<ChunkPos, Chunk>,
+    tickets: TicketManager,
+    saver: Saver,
+    level: LevelData,
+    access: AccessList,
+    // changed since the last save, the time moving on alone does not count
+    level_dirty: bool,
+    access_dirty: bool,
+    generator: Arc<dyn WorldGenerator>,
+    workers: ChunkWorkers,
+    // handed to the workers and not back yet
+    requested: HashSet<ChunkPos>,
+    // in the order it came in
+    pending: Vec<Pending>,
+    players: HashMap<i32, Player>,
+    max_players: usize,
+    tracker: EntityTracker,
+    view_distance: i32,
+    simulation_distance: i32,
+    chunks_per_tick: usize,
+    plugins: Vec<Box<dyn Plugin>>,
+    // text components above and below the tab list
+    tab_header: String,
+    tab_footer: String,
+    chat_mode: ChatMode,
+    chat_formatter: Box<dyn ChatFormatter>,
+    commands: CommandDispatcher,
+    last_keep_alive: Instant,
+    // None turns autosaving off
+    autosave_interval: Option<Duration>,
+    last_autosave: Instant,
+    events: mpsc::UnboundedSender<WorldEvent>,
+    receiver: Option<mpsc::UnboundedReceiver<WorldEvent>>
+}
+
+impl World {
+    // opens the world in the directory, the seed is only used when it has no level.dat yet
+    pub fn new(directory: impl Into<PathBuf>, seed: i64) -> Self {
+        let (events, receiver) = mpsc::unbounded_channel();
+        let registry = Arc::new(RegistryCodec::vanilla());
+        let dimension = registry.dimension_type("minecraft:overworld").unwrap();
+        let directory = directory.into();
+
+        let level = match LevelData::load(directory.join("level.dat")) {
+            Ok(level) => Some(level),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => None,
+            Err(error) => {
+                error!("failed to read <green>level.dat</>, starting a new world\n{}", error);
+                None
+            }
+        };
+
+        let access = match AccessList::load(directory.join("access.dat")) {
+            Ok(access) => access,
+            Err(error) => {
+                if error.kind() != io::ErrorKind::NotFound {
+                    error!("failed to read <green>access.dat</>, nobody is banned, whitelisted or an operator\n{}", error);
+                }
+
+                AccessList::new()
+            }
+        };
+
+        let fresh = level.is_none();
+        let name = directory.file_name().map_or("world".into(), |name| name.to_string_lossy());
+        let level = level.unwrap_or_else(|| LevelData::new(&name, seed));
+        let terrain = || -> Arc<dyn WorldGenerator> {
+            let mut terrain = TerrainGenerator::new(level.seed, &registry).expect("the vanilla registry codec has the overworld biomes");
+
+            for name in structure::template_names(&directory).into_iter().filter(|name| name.split_once(':').is_some_and(|(_, path)| path.starts_with("worldgen/"))) {
+                match structure::template_path(&directory, &name).and_then(StructureTemplate::load) {
+                    Ok(template) => terrain.add_structure(template, WORLDGEN_STRUCTURE_CHANCE),
+                    Err(error) => error!("failed to read the structure <green>{}</>, it is not generated\n{}", name, error)
+                }
+            }
+
+            Arc::new(terrain)
+        };
+        // a world keeps the generator it was made with, new chunks have to match the old ones
+        let generator = match level.generator() {
+            // every noise generator makes the same terrain, which gets the world's templates
+            Some(settings) if settings.get_str("type") != Some("minecraft:noise") => match generator::from_settings(settings, level.seed, &registry) {
+                Ok(generator) => generator,
+                Err(error) => {
+                    error!("failed to read the generator from <green>level.dat</>, generating noise terrain\n{}", error);
+                    terrain()
+                }
+            },
+            _ => terrain()
+        };
+        let saver = Saver::new(RegionStorage::new(directory.join("region")));
+
+        let workers = ChunkWorkers::new(ChunkSource {
+            generator: generator.clone(),
+            reader: saver.reader(),
+            registry: registry.clone(),
+            dimension: dimension.clone()
+        }, ChunkWorkers::default_threads());
+
+        let mut world = Self {
+            saver,
+            directory,
+            registry,
+            dimension,
+            chunks: HashMap::new(),
+            tickets: TicketManager::new(),
+            level,
+            access,
+            level_dirty: false,
+            access_dirty: false,
+            generator,
+            workers,
+            requested: HashSet::new(),
+            pending: vec![],
+            players: HashMap::new(),
+            max_players: MAX_PLAYERS,
+            tracker: EntityTracker::new(),
+            view_distance: RENDER_DISTANCE,
+            simulation_distance: SIMULATION_DISTANCE,
+            chunks_per_tick: CHUNKS_PER_TICK,
+            plugins: vec![],
+            tab_header: text::plain(""),
+            tab_footer: text::plain(""),
+            chat_mode: ChatMode::Disguised,
+            chat_formatter: Box::new(DefaultFormatter),
+            commands: CommandDispatcher::new(),
+            last_keep_alive: Instant::now(),
+            autosave_interval: Some(AUTOSAVE_INTERVAL),
+            last_autosave: Instant::now(),
+            events,
+            receiver: Some(receiver)
+        };
+
+        world.tickets.add(ChunkPos::of(&world.level.spawn), Ticket::spawn());
+
+        if fresh {
+            world.level.set_generator(world.generator.settings());
+            world.level_dirty = true;
+            world.search_spawn();
+        }
+
+        command::builtin::register(&mut world.commands);
+        world
+    }
+
+    // the changes so far are saved before the chunks are dropped, chunks that were never saved come from the new generator
+    pub fn set_generator(&mut self, generator: impl WorldGenerator + 'static) {
+        self.save();
+        self.saver.flush();
+        self.generator = Arc::new(generator);
+        self.chunks.clear();
+        self.restart_workers();
+        self.resend_views();
+        self.level.set_generator(self.generator.settings());
+        self.level_dirty = true;
+        self.search_spawn();
+    }
+
+    pub fn set_spawn(&mut self, spawn: Pos) {
+        self.tickets.remove(ChunkPos::of(&self.level.spawn), &TicketKind::Spawn);
+        self.tickets.add(ChunkPos::of(&spawn), Ticket::spawn());
+        self.level.spawn = spawn;
+        self.level_dirty = true;
+
+        self.broadcast(clientbound::Packet::SetDefaultSpawnPosition(SetDefaultSpawnPositionData {
+            location: spawn,
+            angle: self.level.spawn_angle
+        }));
+    }
+
+    // looks for the middle of the closest chunk to the origin that is not under water or lava, joins wait until it is found
+    fn search_spawn(&mut self) {
+        let searches: Vec<Pending> = self.pending.extract_if(.., |pending| matches!(pending, Pending::SpawnSearch { .. })).collect();
+
+        for search in searches {
+            self.release(&search);
+        }
+
+        self.wait_for(Pending::SpawnSearch { radius: 0, fallback: None });
+    }
+
+    // the chunks exactly radius away from the origin
+    fn spawn_ring(radius: i32) -> Vec<ChunkPos> {
+        (-radius..=radius)
+            .flat_map(|x| (-radius..=radius).map(move |z| ChunkPos::new(x, z)))
+            .filter(|pos| pos.x.abs() == radius || pos.z.abs() == radius)
+            .collect()
+    }
+
+    // every chunk of the ring is loaded
+    fn continue_spawn_search(&mut self, radius: i32, mut fallback: Option<Pos>) {
+        for pos in Self::spawn_ring(radius) {
+            let chunk = &self.chunks[&pos];
+            let height = chunk.heightmap(BlockState::blocks_motion)[8 * 16 + 8] as i32;
+            let y = chunk.min_y() + height;
+            let spawn = Pos { x: pos.min_block_x() + 8, y: y as i16, z: pos.min_block_z() + 8 };
+
+            if !chunk.get_block(8, y - 1, 8).is_liquid() {
+                return self.set_spawn(spawn)
+            }
+
+            fallback.get_or_insert(spawn);
+        }
+
+        match radius < SPAWN_SEARCH_RADIUS {
+            true => self.wait_for(Pending::SpawnSearch { radius: radius + 1, fallback }),
+            false => self.set_spawn(fallback.unwrap())
+        }
+    }
+
+    pub fn seed(&self) -> i64 {
+        self.level.seed
+    }
+
+    pub fn spawn(&self) -> Pos {
+        self.level.spawn
+    }
+
+    pub fn level(&self) -> &LevelData {
+        &self.level
+    }
+
+    pub fn level_mut(&mut self) -> &mut LevelData {
+        self.level_dirty = true;
+        &mut self.level
+    }
+
+    pub fn set_day_time(&mut self, day_time: i64) {
+        self.level.day_time = day_time;
+        self.level_dirty = true;
+        self.broadcast(self.time_packet());
+    }
+
+    // the client keeps the time moving on its own unless it is negative
+    fn time_packet(&self) -> clientbound::Packet {
+        let time_of_day = match self.level.game_rule_or_default("doDaylightCycle") {
+            Some("false") => match self.level.day_time {
+                0 => -1,
+                day_time => -day_time
+            },
+            _ => self.level.day_time
+        };
+
+        clientbound::Packet::UpdateTime(UpdateTimeData {
+            world_age: self.level.time,
+            time_of_day
+        })
+    }
+
+    // the duration is in ticks
+    pub fn set_weather(&mut self, weather: Weather, duration: i32) {
+        self.level.set_weather(weather, duration);
+        self.level_dirty = true;
+
+        for packet in self.weather_packets() {
+            self.broadcast(packet);
+        }
+    }
+
+    // the client fades the rain and thunder in and out on its own
+    fn weather_packets(&self) -> Vec<clientbound::Packet> {
+        let game_event = |event, value| clientbound::Packet::GameEvent(GameEventData { event, value });
+        let weather = self.level.weather();
+
+        // 1 starts the rain and 2 stops it, 7 and 8 are how strong the rain and the thunder are
+        vec![
+            game_event(if weather == Weather::Clear { 2 } else { 1 }, 0.0),
+            game_event(7, if weather == Weather::Clear { 0.0 } else { 1.0 }),
+            game_event(8, if weather == Weather::Thunder { 1.0 } else { 0.0 })
+        ]
+    }
+
+    // 0 is peaceful, 3 is hard
+    pub fn set_difficulty(&mut self, difficulty: u8) {
+        self.level.difficulty = difficulty.min(3);
+        self.level_dirty = true;
+        self.broadcast(self.difficulty_packet());
+    }
+
+    fn difficulty_packet(&self) -> clientbound::Packet {
+        clientbound::Packet::ChangeDifficulty(ChangeDifficultyData {
+            difficulty: self.level.difficulty,
+            locked: self.level.difficulty_locked
+        })
+    }
+
+    pub fn set_game_rule(&mut self, name: &str, value: &str) {
+        self.level.set_game_rule(name, value);
+        self.level_dirty = true;
+
+        // the clients stop their clocks when they get a negative time
+        if name == "doDaylightCycle" {
+            self.broadcast(self.time_packet());
+        }
+    }
+
+    pub fn access(&self) -> &AccessList {
+        &self.access
+    }
+
+    pub fn access_mut(&mut self) -> &mut AccessList {
+        self.access_dirty = true;
+        &mut self.access
+    }
+
+    // false when nothing changed, a player who is online gets the commands of the new level right away
+    pub fn op(&mut self, name: &str, permission_level: u8) -> bool {
+        if !self.access.op(name, permission_level) {
+            return false
+        }
+
+        self.access_dirty = true;
+
+        if let Some(entity_id) = self.player_by_name(name).map(|player| player.entity_id) {
+            self.set_permission_level(entity_id, permission_level);
+        }
+
+        true
+    }
+
+    pub fn deop(&mut self, name: &str) -> bool {
+        if !self.access.deop(name) {
+            return false
+        }
+
+        self.access_dirty = true;
+
+        if let Some(entity_id) = self.player_by_name(name).map(|player| player.entity_id) {
+            self.set_permission_level(entity_id, 0);
+        }
+
+        true
+    }
+
+    // false when the player was banned already, a player who is online is kicked
+    pub fn ban(&mut self, name: &str, reason: &str) -> bool {
+        if !self.access.ban(name, reason) {
+            return false
+        }
+
+        self.access_dirty = true;
+
+        if let Some(entity_id) = self.player_by_name(name).map(|player| player.entity_id) {
+            self.kick(entity_id, &format!("You are banned from this server.\nReason: {}", reason));
+        }
+
+        true
+    }
+
+    pub fn pardon(&mut self, name: &str) -> bool {
+        let pardoned = self.access.pardon(name);
+        self.access_dirty |= pardoned;
+        pardoned
+    }
+
+    // why the player may not join, None when it may
+    fn refusal(&self, username: &str) -> Option<String> {
+        if let Some(ban) = self.access.ban_of(username) {
+            return Some(format!("You are banned from this server.\nReason: {}", ban.reason))
+        }
+
+        if self.access.whitelist_enabled && !self.access.is_whitelisted(username) && self.access.operator(username).is_none() {
+            return Some("You are not white-listed on this server!".to_string())
+        }
+
+        let joining = self.pending.iter().filter(|pending| matches!(pending, Pending::Join(_))).count();
+
+        if self.players.len() + joining >= self.max_players {
+            return Some("The server is full!".to_string())
+        }
+
+        None
+    }
+
+    // stops the tick loop once the events before it are handled
+    pub fn stop(&self) {
+        let _ = self.events.send(WorldEvent::Shutdown);
+    }
+
+    pub fn events(&self) -> mpsc::UnboundedSender<WorldEvent> {
+        self.events.clone()
+    }
+
+    pub fn dimension(&self) -> &DimensionType {
+        &self.dimension
+    }
+
+    pub fn registry(&self) -> &RegistryCodec {
+        &self.registry
+    }
+
+    pub fn chunk(&self, pos: ChunkPos) -> Option<&Chunk> {
+        self.chunks.get(&pos)
+    }
+
+    pub fn chunk_mut(&mut self, pos: ChunkPos) -> Option<&mut Chunk> {
+        self.chunks.get_mut(&pos)
+    }
+
+    // the chunks it waits for, a join waits for the ones around wherever the spawn is by then
+    fn pending_chunks(&self, pending: &Pending) -> Vec<ChunkPos> {
+        match pending {
+            Pending::Join(_) => view::spiral(ChunkPos::of(&self.level.spawn), 1).collect(),
+            Pending::Structure { chunks, .. } => chunks.clone(),
+            Pending::SpawnSearch { radius, .. } => Self::spawn_ring(*radius)
+        }
+    }
+
+    // the chunks that get a pending ticket, the spawn ticket keeps the ones of joins loaded already
+    fn ticketed_chunks(&self, pending: &Pending) -> Vec<ChunkPos> {
+        match pending {
+            Pending::Join(_) => vec![],
+            _ => self.pending_chunks(pending)
+        }
+    }
+
+    // the chunks are kept loaded and go to the workers ahead of everything else, it is finished right away when they are loaded already
+    fn wait_for(&mut self, pending: Pending) {
+        for pos in self.ticketed_chunks(&pending) {
+            self.tickets.add(pos, Ticket::pending());
+        }
+
+        self.pending.push(pending);
+        self.finish_pending();
+    }
+
+    // lets go of the chunks nothing else waits for
+    fn release(&mut self, pending: &Pending) {
+        for pos in self.ticketed_chunks(pending) {
+            if !self.pending.iter().any(|other| self.ticketed_chunks(other).contains(&pos)) {
+                self.tickets.remove(pos, &TicketKind::Pending);
+            }
+        }
+    }
+
+    // joins also wait for the spawn search, they would spawn in the wrong place otherwise
+    fn finish_pending(&mut self) {
+        let mut index = 0;
+
+        while index < self.pending.len() {
+            let searching = self.pending.iter().any(|pending| matches!(pending, Pending::SpawnSearch { .. }));
+            let pending = &self.pending[index];
+            let ready = self.pending_chunks(pending).iter().all(|pos| self.chunks.contains_key(pos))
+                && !(searching && matches!(pending, Pending::Join(_)));
+
+            if !ready {
+                index += 1;
+                continue
+            }
+
+            let pending = self.pending.remove(index);
+            self.release(&pending);
+
+            match pending {
+                Pending::Join(player) => self.join(*player),
+                Pending::Structure { blocks, .. } => self.finish_structure(blocks),
+                Pending::SpawnSearch { radius, fallback } => self.continue_spawn_search(radius, fallback)
+            }
+        }
+    }
+
+    // the chunks queued so far would come from the old generator or storage, so they are thrown away with the workers
+    fn restart_workers(&mut self) {
+        self.workers = ChunkWorkers::new(ChunkSource {
+            generator: self.generator.clone(),
+            reader: self.saver.reader(),
+            registry: self.registry.clone(),
+            dimension: self.dimension.clone()
+        }, ChunkWorkers::default_threads());
+
+        self.requested.clear();
+    }
+
+    pub fn insert_chunk(&mut self, chunk: Chunk) -> Option<Chunk> {
+        self.chunks.insert(chunk.pos, chunk)
+    }
+
+    pub fn remove_chunk(&mut self, pos: ChunkPos) -> Option<Chunk> {
+        self.chunks.remove(&pos)
+    }
+
+    // like remove_chunk, but the chunk is saved first if it changed
+    pub fn unload_chunk(&mut self, pos: ChunkPos) {
+        if let Some(chunk) = self.chunks.remove(&pos) {
+            if chunk.is_dirty() {
+                self.saver.save_chunk(pos, chunk.to_anvil(&self.registry));
+            }
+        }
+    }
+
+    pub fn add_ticket(&mut self, pos: ChunkPos, ticket: Ticket) {
+        self.tickets.add(pos, ticket);
+    }
+
+    pub fn remove_ticket(&mut self, pos: ChunkPos, kind: &TicketKind) {
+        self.tickets.remove(pos, kind);
+    }
+
+    // FIXME: nothing is simulated yet, so ticking and border chunks only differ in name
+    pub fn load_level(&self, pos: ChunkPos) -> LoadLevel {
+        self.tickets.level(pos)
+    }
+
+    // loads what the tickets ask for and lets go of what no ticket reaches any more
+    fn update_tickets(&mut self) {
+        self.tickets.tick();
+
+        let unloaded: Vec<ChunkPos> = self.chunks.keys().copied().filter(|pos| !self.tickets.level(*pos).is_loaded()).collect();
+
+        for pos in unloaded {
+            self.unload_chunk(pos);
+        }
+
+        let cancelled: Vec<ChunkPos> = self.requested.iter().copied().filter(|pos| !self.tickets.level(*pos).is_loaded()).collect();
+
+        for pos in cancelled {
+            self.workers.cancel(pos);
+            self.requested.remove(&pos);
+        }
+
+        // a chunk that was cancelled while a worker had it, or loaded here in the meantime, is dropped
+        let completed: Vec<Chunk> = self.workers.completed().collect();
+
+        for chunk in completed {
+            if self.requested.remove(&chunk.pos) && !self.chunks.contains_key(&chunk.pos) {
+                self.chunks.insert(chunk.pos, chunk);
+            }
+        }
+
+        let missing: Vec<ChunkPos> = self.tickets.loaded()
+            .map(|(pos, _)| pos)
+            .filter(|pos| !self.chunks.contains_key(pos) && !self.requested.contains(pos))
+            .collect();
+
+        // the players may have moved since the queued chunks were requested
+        let centers: Vec<ChunkPos> = self.players.values().map(|player| player.view.center()).collect();
+        let pending: HashSet<ChunkPos> = self.pending.iter().flat_map(|pending| self.pending_chunks(pending)).collect();
+
+        if !self.requested.is_empty() {
+            self.workers.reprioritize(|pos| Self::priority(&centers, &pending, pos));
+        }
+
+        for pos in missing {
+            self.workers.request(pos, Self::priority(&centers, &pending, pos));
+            self.requested.insert(pos);
+        }
+    }
+
+    // the distance to the closest player, so what players are waiting for loads first
+    fn priority(centers: &[ChunkPos], pending: &HashSet<ChunkPos>, pos: ChunkPos) -> i32 {
+        if pending.contains(&pos) {
+            return PENDING_PRIORITY
+        }
+
+        centers.iter()
+            .map(|center| (pos.x - center.x).abs().max((pos.z - center.z).abs()))
+            .min()
+            .unwrap_or(UNWATCHED_PRIORITY)
+    }
+
+    // None when the chunk is not loaded
+    pub fn get_block(&self, pos: Pos) -> Option<BlockState> {
+        Some(self.chunk(ChunkPos::of(&pos))?.get_block(pos.x, pos.y as i32, pos.z))
+    }
+
+    // returns the replaced block, None when the chunk is not loaded or the position is out of height
+    pub fn set_block(&mut self, pos: Pos, state: BlockState) -> Option<BlockState> {
+        self.chunk_mut(ChunkPos::of(&pos))?.set_block(pos.x, pos.y as i32, pos.z, state)
+    }
+
+    // a template saved by a structure block, by its namespaced name
+    pub fn load_structure(&self, name: &str) -> io::Result<StructureTemplate> {
+        StructureTemplate::load(structure::template_path(&self.directory, name)?)
+    }
+
+    pub fn structure_names(&self) -> Vec<String> {
+        structure::template_names(&self.directory)
+    }
+
+    // the blocks go in once every chunk the template reaches into is loaded or generated, then they are sent again to everyone
+    // FIXME: entities in the template are skipped until the world tracks entities
+    pub fn place_structure(&mut self, template: &StructureTemplate, origin: Pos, settings: PlacementSettings) {
+        let blocks: Vec<(Pos, BlockState, Option<Compound>)> = template.blocks(origin, settings)
+            .map(|(pos, state, nbt)| (pos, state, nbt.cloned()))
+            .collect();
+
+        let chunks: HashSet<ChunkPos> = blocks.iter().map(|(pos, _, _)| ChunkPos::of(pos)).collect();
+        self.wait_for(Pending::Structure { chunks: chunks.into_iter().collect(), blocks });
+    }
+
+    fn finish_structure(&mut self, blocks: Vec<(Pos, BlockState, Option<Compound>)>) {
+        let mut changed = HashSet::new();
+
+        for (pos, state, nbt) in blocks {
+            let chunk_pos = ChunkPos::of(&pos);
+            let chunk = self.chunks.get_mut(&chunk_pos).unwrap();
+            chunk.set_block(pos.x, pos.y as i32, pos.z, state);
+
+            if let Some(block_entity) = nbt.as_ref().and_then(BlockEntity::from_nbt) {
+                chunk.set_block_entity(pos.x, pos.y as i32, pos.z, block_entity);
+            }
+
+            changed.insert(chunk_pos);
+        }
+
+        let biome_count = self.biome_count();
+
+        for chunk_pos in changed {
+            self.broadcast_to_viewers(chunk_pos, self.chunks[&chunk_pos].to_packet(biome_count));
+        }
+    }
+
+    pub fn view_distance(&self) -> i32 {
+        self.view_distance
+    }
+
+    pub fn set_view_distance(&mut self, view_distance: i32) {
+        self.view_distance = view_distance;
+
+        self.broadcast(clientbound::Packet::SetRenderDistance(SetRenderDistanceData {
+            view_distance: view_distance.into()
+        }));
+
+        let entity_ids: Vec<i32> = self.players.keys().copied().collect();
+
+        for entity_id in entity_ids {
+            self.update_view(entity_id);
+        }
+    }
+
+    pub fn simulation_distance(&self) -> i32 {
+        self.simulation_distance
+    }
+
+    pub fn set_simulation_distance(&mut self, simulation_distance: i32) {
+        self.simulation_distance = simulation_distance;
+
+        self.broadcast(clientbound::Packet::SetSimulationDistance(SetSimulationDistanceData {
+            simulation_distance: simulation_distance.into()
+        }));
+
+        let entity_ids: Vec<i32> = self.players.keys().copied().collect();
+
+        for entity_id in entity_ids {
+            self.update_player_tickets(entity_id, None);
+        }
+    }
+
+    // moves the tickets of a player to the center of its view, `previous` is where they were
+    fn update_player_tickets(&mut self, entity_id: i32, previous: Option<ChunkPos>) {
+        let Some(player) = self.players.get(&entity_id) else {
+            return
+        };
+
+        let kind = TicketKind::Player(entity_id);
+        let center = player.view.center();
+        let radius = player.view.radius();
+
+        self.tickets.remove(previous.unwrap_or(center), &kind);
+        self.tickets.add(center, Ticket::player(entity_id, self.simulation_distance.min(radius)));
+        self.tickets.add(center, Ticket::player_view(entity_id, radius));
+    }
+
+    pub fn max_players(&self) -> usize {
+        self.max_players
+    }
+
+    // players already online stay when it goes down
+    pub fn set_max_players(&mut self, max_players: usize) {
+        self.max_players = max_players;
+    }
+
+    pub fn set_chunks_per_tick(&mut self, chunks_per_tick: usize) {
+        self.chunks_per_tick = chunks_per_tick;
+    }
+
+    fn view_radius(&self, player: &Player) -> i32 {
+        view::radius(player.client_information.as_ref().map(|information| information.view_distance), self.view_distance)
+    }
+
+    // recenters the view of a player after it moved or changed its view distance
+    fn update_view(&mut self, entity_id: i32) {
+        let Some(player) = self.players.get(&entity_id) else {
+            return
+        };
+
+        let radius = self.view_radius(player);
+        let player = self.players.get_mut(&entity_id).unwrap();
+        let center = player.chunk();
+        let previous = player.view.center();
+
+        if center == previous && radius == player.view.radius() {
+            return
+        }
+
+        if center != player.view.center() {
+            player.send(clientbound::Packet::SetCenterChunk(SetCenterChunkData {
+                chunk_x: center.x.into(),
+                chunk_z: center.z.into()
+            }));
+        }
+
+        for pos in player.view.update(center, radius) {
+            player.send(clientbound::Packet::UnloadChunk(UnloadChunkData {
+                chunk_x: pos.x,
+                chunk_z: pos.z
+            }));
+        }
+
+        self.update_player_tickets(entity_id, Some(previous));
+    }
+
+    // the clients drop every chunk they have and get them again as they load
+    fn resend_views(&mut self) {
+        for player in self.players.values_mut() {
+            for pos in player.view.reset() {
+                player.send(clientbound::Packet::UnloadChunk(UnloadChunkData {
+                    chunk_x: pos.x,
+                    chunk_z: pos.z
+                }));
+            }
+        }
+    }
+
+    // sends every player the next few loaded chunks of its view, nearest first
+    fn stream_chunks(&mut self) {
+        let chunks_per_tick = self.chunks_per_tick;
+        let biome_count = self.biome_count();
+        let chunks = &self.chunks;
+
+        for player in self.players.values_mut().filter(|player| !player.view.is_complete()) {
+            for pos in player.view.next(chunks_per_tick, |pos| chunks.contains_key(&pos)) {
+                player.send(chunks[&pos].to_packet(biome_count));
+            }
+        }
+    }
+
+    fn biome_count(&self) -> usize {
+        self.registry.entries("minecraft:worldgen/biome").count()
+    }
+
+    pub fn add_plugin(&mut self, plugin: impl Plugin + 'static) {
+        self.plugins.push(Box::new(plugin));
+    }
+
+    pub async fn run(mut self) {
+        let mut receiver = self.receiver.take().unwrap();
+        let mut ticker = time::interval(TICK_DURATION);
+
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => self.tick(),
+                event = receiver.recv() => match event {
+                    Some(WorldEvent::Shutdown) | None => break,
+                    Some(event) => self.handle_event(event)
+                }
+            }
+        }
+
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        let entity_ids: Vec<i32> = self.players.keys().copied().collect();
+
+        for entity_id in entity_ids {
+            self.kick(entity_id, "Server closed");
+        }
+
+        // the time is only written along with other changes while the world runs, but it is kept when it stops
+        self.level_dirty = true;
+        self.save();
+        self.saver.flush();
+
+        info!("saved the world to <green>'{}'</>", self.directory.display());
+    }
+
+    // the chunks loaded so far are saved to the old storage and dropped, the level data stays where it is
+    pub fn set_storage(&mut self, storage: impl ChunkStorage + 'static) {
+        self.save();
+        self.saver.flush();
+        self.saver = Saver::new(storage);
+        self.chunks.clear();
+        self.restart_workers();
+        self.resend_views();
+    }
+
+    pub fn stored_chunks(&self) -> io::Result<Vec<ChunkPos>> {
+        self.saver.list_chunks()
+    }
+
+    // removes the chunk from memory and from the storage, so it gets generated again
+    pub fn delete_chunk(&mut self, pos: ChunkPos) -> Option<Chunk> {
+        self.saver.delete_chunk(pos);
+        self.chunks.remove(&pos)
+    }
+
+    pub fn set_autosave_interval(&mut self, interval: Option<Duration>) {
+        self.autosave_interval = interval;
+    }
+
+    // only hands the changes over, the writes happen in the background
+    pub fn save(&mut self) {
+        for chunk in self.chunks.values_mut().filter(|chunk| chunk.is_dirty()) {
+            self.saver.save_chunk(chunk.pos, chunk.to_anvil(&self.registry));
+            chunk.set_dirty(false);
+        }
+
+        let paths: HashMap<i32, PathBuf> = self.players.values().map(|player| (player.entity_id, self.player_path(player))).collect();
+
+        for player in self.players.values_mut() {
+            if let Some(nbt) = player.changed_nbt() {
+                self.saver.save_file(paths[&player.entity_id].clone(), nbt);
+            }
+        }
+
+        if self.level_dirty {
+            self.saver.save_file(self.directory.join("level.dat"), self.level.to_file());
+            self.level_dirty = false;
+        }
+
+        if self.access_dirty {
+            self.saver.save_file(self.directory.join("access.dat"), self.access.to_nbt());
+            self.access_dirty = false;
+        }
+    }
+
+    fn handle_event(&mut self, event: WorldEvent) {
+        match event {
+            WorldEvent::Connect { connection, uuid, username, properties } => self.connect_client(connection, uuid, username, properties),
+            WorldEvent::Packet(entity_id, packet) => self.handle_packet(entity_id, packet),
+            WorldEvent::Disconnect(entity_id, reason) => self.disconnect(entity_id, reason),
+            WorldEvent::ConsoleCommand(command) => {
+                let _ = self.execute_command(CommandSender::Console, &command);
+            }
+            WorldEvent::CompleteCommand(command, reply) => {
+                let _ = reply.send(self.complete_command(CommandSender::Console, &command));
+            }
+            // stops the loop in run before it gets here
+            WorldEvent::Shutdown => ()
+        }
+    }
+
+    fn handle_packet(&mut self, entity_id: i32, packet: serverbound::Packet) {
+        // packets still queued from a player that is already gone
+        let Some(player) = self.players.get_mut(&entity_id) else {
+            return
+        };
+
+        match packet {
+            serverbound::Packet::ClientInformation(data) => {
+                player.client_information = Some(data);
+                self.update_view(entity_id);
+            }
+            serverbound::Packet::ConfirmTeleportation(data) => player.confirm_teleport(data.teleport_id.into()),
+            serverbound::Packet::KeepAlive(data) if player.confirm_keep_alive(data.keep_alive_id) => {
+                let info = PlayerInfo { latency: Some(player.latency.into()), ..PlayerInfo::new(player.uuid) };
+                self.broadcast_info(info);
+            }
+            serverbound::Packet::ChatMessage(data) => self.chat(entity_id, data),
+            serverbound::Packet::ChatCommand(data) => {
+                if self.acknowledge_messages(entity_id, data.message_count.into(), data.acknowledged).is_none() {
+                    return
+                }
+
+                if !chat::is_valid_message(&data.command) {
+                    self.kick(entity_id, "Illegal characters in chat");
+                    return
+                }
+
+                info!("<green>{}</> issued server command: /{}", self.players[&entity_id].username, data.command);
+                let _ = self.execute_command(CommandSender::Player(entity_id), &data.command);
+            }
+            serverbound::Packet::CommandSuggestionsRequest(data) => self.suggest_command(entity_id, i32::from(data.transaction_id), &data.text),
+            serverbound::Packet::MessageAcknowledgment(data) => {
+                if let Err(error) = player.last_seen.apply_offset(data.message_count.into()) {
+                    self.kick(entity_id, &error.to_string());
+                }
+            }
+            serverbound::Packet::PlayerSession(data) => self.update_chat_session(entity_id, data),
+            serverbound::Packet::SetPlayerPosition(data) => {
+                self.move_player(entity_id, Some((data.x, data.feet_y, data.z)), None, data.on_ground);
+            }
+            serverbound::Packet::SetPlayerPositionAndRotation(data) => {
+                self.move_player(entity_id, Some((data.x, data.feet_y, data.z)), Some((data.yaw, data.pitch)), data.on_ground);
+            }
+            serverbound::Packet::SetPlayerRotation(data) => {
+                self.move_player(entity_id, None, Some((data.yaw, data.pitch)), data.on_ground);
+            }
+            serverbound::Packet::SetPlayerOnGround(data) => self.move_player(entity_id, None, None, data.on_ground),
+            // FIXME: 0 is the respawn button, which only dead players may press, and nobody can die without health
+            serverbound::Packet::ClientCommand(_) => (),
+            _ => ()
+        }
+    }
+
+    // the client moves on its own, the server only puts it back when the move was too far, into blocks or into chunks that are not loaded
+    fn move_player(&mut self, entity_id: i32, position: Option<(f64, f64, f64)>, rotation: Option<(f32, f32)>, on_ground: bool) {
+        let Some(player) = self.players.get_mut(&entity_id) else {
+            return
+        };
+
+        // anything sent before the client saw the teleport starts from the old position
+        if player.is_teleporting() {
+            return
+        }
+
+        let valid_position = position.is_none_or(|(x, y, z)| {
+            x.is_finite() && y.is_finite() && z.is_finite() && x.abs() <= MAX_COORDINATE && z.abs() <= MAX_COORDINATE && y.abs() <= MAX_HEIGHT
+        });
+
+        let valid_rotation = rotation.is_none_or(|(yaw, pitch)| yaw.is_finite() && pitch.is_finite());
+
+        if !valid_position || !valid_rotation {
+            self.kick(entity_id, "Invalid move player packet received");
+            return
+        }
+
+        if let Some((yaw, pitch)) = rotation {
+            player.yaw = yaw;
+            player.pitch = pitch.clamp(-90.0, 90.0);
+        }
+
+        player.on_ground = on_ground;
+
+        let Some((x, y, z)) = position else {
+            return
+        };
+
+        let (old_x, old_y, old_z) = (player.x, player.y, player.z);
+        let distance = ((x - old_x).powi(2) + (y - old_y).powi(2) + (z - old_z).powi(2)).sqrt();
+
+        if distance > MAX_MOVEMENT {
+            info!("<green>{}</> moved too quickly", player.username);
+        }
+
+        // a player already stuck in a block may still move out of it
+        let wrong = self.collides(x, y, z) && !self.collides(old_x, old_y, old_z);
+        let floating = y - old_y >= -0.03125 && !self.touches_blocks(x, y, z);
+        let player = self.players.get_mut(&entity_id).unwrap();
+
+        if wrong {
+            info!("<green>{}</> moved wrongly", player.username);
+        }
+
+        // the client is put back where the server last had it
+        if wrong || distance > MAX_MOVEMENT || !self.chunks.contains_key(&ChunkPos::containing(x, z)) {
+            let (x, y, z, yaw, pitch) = (player.x, player.y, player.z, player.yaw, player.pitch);
+            player.teleport(x, y, z, yaw, pitch);
+            return
+        }
+
+        // creative and spectator players may fly
+        player.floating = floating && matches!(player.gamemode, 0 | 2);
+        (player.x, player.y, player.z) = (x, y, z);
+        self.update_view(entity_id);
+    }
+
+    // whether a block with full collision in a loaded chunk is inside the box of a player at the position
+    fn collides(&self, x: f64, y: f64, z: f64) -> bool {
+        let half = PLAYER_WIDTH / 2.0 - COLLISION_MARGIN;
+        self.any_block((x - half, y + COLLISION_MARGIN, z - half), (x + half, y + PLAYER_HEIGHT - COLLISION_MARGIN, z + half), BlockState::has_full_collision)
+    }
+
+    // like vanilla, anything but air next to the player or a little below its feet can hold it up
+    fn touches_blocks(&self, x: f64, y: f64, z: f64) -> bool {
+        let half = PLAYER_WIDTH / 2.0 + 0.0625;
+        self.any_block((x - half, y - 0.55, z - half), (x + half, y + PLAYER_HEIGHT, z + half), |state| !state.is_air())
+    }
+
+    fn any_block(&self, min: (f64, f64, f64), max: (f64, f64, f64), test: impl Fn(BlockState) -> bool) -> bool {
+        let bottom = self.dimension.min_y;
+        let top = bottom + self.dimension.height - 1;
+
+        for x in min.0.floor() as i32..=max.0.floor() as i32 {
+            for y in (min.1.floor() as i32).max(bottom)..=(max.1.floor() as i32).min(top) {
+                for z in min.2.floor() as i32..=max.2.floor() as i32 {
+                    if self.get_block(Pos { x, y: y as i16, z }).is_some_and(&test) {
+                        return true
+                    }
+                }
+            }
+        }
+
+        false
+    }
+
+    // a client that keeps floating without falling or touching anything is flying
+    fn check_flying(&mut self) {
+        let flying: Vec<i32> = self.players.values_mut()
+            .filter_map(|player| {
+                // moves are ignored until a teleport is confirmed, so the player cannot come down before
+                player.airborne_ticks = if player.floating && !player.is_teleporting() { player.airborne_ticks + 1 } else { 0 };
+                (player.airborne_ticks > MAX_AIRBORNE_TICKS).then_some(player.entity_id)
+            })
+            .collect();
+
+        for entity_id in flying {
+            self.kick(entity_id, "Flying is not enabled on this server");
+        }
+    }
+
+    pub fn teleport(&mut self, entity_id: i32, x: f64, y: f64, z: f64, yaw: f32, pitch: f32) {
+        if let Some(player) = self.players.get_mut(&entity_id) {
+            player.teleport(x, y, z, yaw, pitch);
+            self.update_view(entity_id);
+        }
+    }
+
+    pub fn set_gamemode(&mut self, entity_id: i32, gamemode: u8) {
+        let Some(player) = self.players.get_mut(&entity_id) else {
+            return
+        };
+
+        player.gamemode = gamemode;
+
+        // 3 is the game event for a gamemode change
+        player.send(clientbound::Packet::GameEvent(GameEventData {
+            event: 3,
+            value: gamemode as f32
+        }));
+
+        let info = PlayerInfo { gamemode: Some((gamemode as i32).into()), ..PlayerInfo::new(player.uuid) };
+        self.broadcast_info(info);
+    }
+
+    // the client needs the level for the debug screen shortcuts, and the new command tree
+    pub fn set_permission_level(&mut self, entity_id: i32, permission_level: u8) {
+        let Some(player) = self.players.get_mut(&entity_id) else {
+            return
+        };
+
+        player.permission_level = permission_level;
+        player.send(Self::permission_level_packet(player));
+        self.send_commands(entity_id);
+    }
+
+    // entity events 24 to 28 are the levels 0 to 4
+    fn permission_level_packet(player: &Player) -> clientbound::Packet {
+        clientbound::Packet::EntityEvent(EntityEventData {
+            entity_id: player.entity_id,
+            status: 24 + player.permission_level.min(MAX_PERMISSION_LEVEL) as i8
+        })
+    }
+
+    // returns how many did not fit
+    pub fn give(&mut self, entity_id: i32, item: &str, count: u32) -> u32 {
+        match self.players.get_mut(&entity_id) {
+            Some(player) => player.inventory.add(item, count),
+            None => count
+        }
+    }
+
+    // a text component, None shows the username again
+    pub fn set_display_name(&mut self, entity_id: i32, display_name: Option<String>) {
+        let Some(player) = self.players.get_mut(&entity_id) else {
+            return
+        };
+
+        player.display_name = display_name.clone();
+
+        let info = PlayerInfo { display_name: Some(display_name), ..PlayerInfo::new(player.uuid) };
+        self.broadcast_info(info);
+    }
+
+    // unlisted players are still known to the clients, they can see them but not in the tab list
+    pub fn set_listed(&mut self, entity_id: i32, listed: bool) {
+        let Some(player) = self.players.get_mut(&entity_id) else {
+            return
+        };
+
+        player.listed = listed;
+
+        let info = PlayerInfo { listed: Some(listed), ..PlayerInfo::new(player.uuid) };
+        self.broadcast_info(info);
+    }
+
+    pub fn tab_list(&self) -> (&str, &str) {
+        (&self.tab_header, &self.tab_footer)
+    }
+
+    // both are text components
+    pub fn set_tab_list(&mut self, header: String, footer: String) {
+        self.tab_header = header;
+        self.tab_footer = footer;
+        self.broadcast(self.tab_list_packet());
+    }
+
+    fn tab_list_packet(&self) -> clientbound::Packet {
+        clientbound::Packet::SetTabListHeaderAndFooter(SetTabListHeaderAndFooterData {
+            header: self.tab_header.clone(),
+            footer: self.tab_footer.clone()
+        })
+    }
+
+    pub fn chat_mode(&self) -> &ChatMode {
+        &self.chat_mode
+    }
+
+    pub fn set_chat_mode(&mut self, chat_mode: ChatMode) {
+        self.chat_mode = chat_mode;
+    }
+
+    pub fn set_chat_formatter(&mut self, formatter: impl ChatFormatter + 'static) {
+        self.chat_formatter = Box::new(formatter);
+    }
+
+    // the key the player signs its messages with, only secure chat has a use for it
+    fn update_chat_session(&mut self, entity_id: i32, data: PlayerSessionData) {
+        let ChatMode::Secure(trust_root) = &self.chat_mode else {
+            return
+        };
+
+        let Some(player) = self.players.get_mut(&entity_id) else {
+            return
+        };
+
+        let data = ChatSession {
+            session_id: data.session_id,
+            expires_at: data.expires_at,
+            public_key: data.public_key,
+            key_signature: data.key_signature
+        };
+
+        match SignedSession::validate(player.uuid, data, trust_root) {
+            Ok(session) => {
+                // the others need the key to check the signatures of what the player says
+                let info = PlayerInfo { initialize_chat: Some(Some(session.data.clone())), ..PlayerInfo::new(player.uuid) };
+                player.chat_session = Some(session);
+                self.broadcast_info(info);
+            }
+            Err(error) => self.kick(entity_id, &error.to_string())
+        }
+    }
+
+    // every message the client sends acknowledges the signed ones it got since the last
+    fn acknowledge_messages(&mut self, entity_id: i32, message_count: i32, acknowledged: [u8; 3]) -> Option<Vec<[u8; 256]>> {
+        let player = self.players.get_mut(&entity_id)?;
+
+        match player.last_seen.apply_update(message_count, acknowledged) {
+            Ok(last_seen) => Some(last_seen),
+            Err(error) => {
+                self.kick(entity_id, &error.to_string());
+                None
+            }
+        }
+    }
+
+    // a message from a player, everyone who has player chat turned on sees it
+    fn chat(&mut self, entity_id: i32, data: ChatMessageData) {
+        let Some(last_seen) = self.acknowledge_messages(entity_id, data.message_count.clone().into(), data.acknowledged) else {
+            return
+        };
+
+        if !chat::is_valid_message(&data.message) {
+            self.kick(entity_id, "Illegal characters in chat");
+            return
+        }
+
+        let player = self.players.get_mut(&entity_id).unwrap();
+
+        // the message has to come from the key of the player, in the order the player signed it
+        let signed = match (&self.chat_mode, player.chat_session.as_mut(), data.signature) {
+            (ChatMode::Secure(_), Some(session), Some(signature)) => {
+                match session.verify(player.uuid, &data.message, data.timestamp, data.salt, &signature, &last_seen) {
+                    Ok(index) => Some((index, signature)),
+                    Err(error) => {
+                        self.kick(entity_id, &error.to_string());
+                        return
+                    }
+                }
+            }
+            (ChatMode::Secure(_), _, _) => {
+                self.kick(entity_id, &ChatError::Unsigned.to_string());
+                return
+            }
+            _ => None
+        };
+
+        let Some(packet) = self.chat_packet(entity_id, data, signed, last_seen) else {
+            return
+        };
+
+        let frame = ConnectionWriter::<Play>::frame(&packet);
+        let mut overflowing = vec![];
+
+        for player in self.players.values_mut().filter(|player| ChatVisibility::of(player) == ChatVisibility::Full) {
+            player.send_frame(frame.clone());
+
+            // the clients acknowledge signed messages, and the server has to know what they saw to check their next signature
+            if let Some((_, signature)) = signed {
+                if let Err(error) = player.last_seen.add_pending(signature) {
+                    overflowing.push((player.entity_id, error));
+                }
+            }
+        }
+
+        for (entity_id, error) in overflowing {
+            self.kick(entity_id, &error.to_string());
+        }
+    }
+
+    // what everyone sees of a message in the current chat mode, None when the formatter drops it
+    fn chat_packet(&self, entity_id: i32, data: ChatMessageData, signed: Option<(i32, [u8; 256])>, last_seen: Vec<[u8; 256]>) -> Option<clientbound::Packet> {
+        let player = &self.players[&entity_id];
+        let content = self.chat_formatter.message(player, &data.message)?;
+
+        info!("<green>{}</>: {}", player.username, data.message);
+
+        let name = self.chat_formatter.name(player);
+        let chat_type = self.chat_formatter.chat_type(player);
+
+        let packet = match (&self.chat_mode, signed) {
+            (ChatMode::System, _) => clientbound::Packet::SystemChatMessage(SystemChatMessageData {
+                content: self.chat_formatter.line(&name, &content),
+                overlay: false
+            }),
+            (mode, signed) => {
+                let Some(chat_type) = self.registry.id("minecraft:chat_type", chat_type) else {
+                    error!("unknown chat type <green>'{}'</>", chat_type);
+                    return None
+                };
+
+                match (mode, signed) {
+                    (ChatMode::Secure(_), Some((index, signature))) => clientbound::Packet::PlayerChatMessage(PlayerChatMessageData {
+                        sender: player.uuid,
+                        index: index.into(),
+                        message_signature: Some(signature),
+                        // unsigned content replaces the signed message on the clients, so it is only there when the formatter changed it
+                        unsigned_content: (content != text::plain(&data.message)).then_some(content),
+                        message: data.message,
+                        timestamp: data.timestamp,
+                        salt: data.salt,
+                        previous_messages: last_seen.into_iter().map(|signature| PreviousMessage {
+                            id: 0.into(),
+                            signature: Some(signature)
+                        }).collect(),
+                        filter: FilterMask::PassThrough,
+                        chat_type: chat_type.into(),
+                        network_name: name,
+                        network_target_name: None
+                    }),
+                    _ => clientbound::Packet::DisguisedChatMessage(DisguisedChatMessageData {
+                        message: content,
+                        chat_type: chat_type.into(),
+                        chat_type_name: name,
+                        target_name: None
+                    })
+                }
+            }
+        };
+
+        Some(packet)
+    }
+
+    // a text component, shown in the chat unless the player hid it entirely
+    pub fn send_system_message(&self, entity_id: i32, message: &str) {
+        if let Some(player) = self.players.get(&entity_id).filter(|player| ChatVisibility::of(player) != ChatVisibility::Hidden) {
+            player.send(Self::system_message(message));
+        }
+    }
+
+    pub fn broadcast_system_message(&self, message: &str) {
+        let frame = ConnectionWriter::<Play>::frame(&Self::system_message(message));
+
+        for player in self.players.values().filter(|player| ChatVisibility::of(player) != ChatVisibility::Hidden) {
+            player.send_frame(frame.clone());
+        }
+    }
+
+    fn system_message(message: &str) -> clientbound::Packet {
+        clientbound::Packet::SystemChatMessage(SystemChatMessageData {
+            content: message.to_string(),
+            overlay: false
+        })
+    }
+
+    fn broadcast_info(&self, info: PlayerInfo) {
+        self.broadcast(clientbound::Packet::PlayerInfoUpdate(PlayerInfoUpdateData {
+            players: PlayerInfoEntries(vec![info])
+        }));
+    }
+
+    pub fn players(&self) -> impl Iterator<Item = &Player> {
+        self.players.values()
+    }
+
+    pub fn player(&self, entity_id: i32) -> Option<&Player> {
+        self.players.get(&entity_id)
+    }
+
+    // names are not case sensitive
+    pub fn player_by_name(&self, name: &str) -> Option<&Player> {
+        self.players.values().find(|player| player.username.eq_ignore_ascii_case(name))
+    }
+
+    pub fn commands(&self) -> &CommandDispatcher {
+        &self.commands
+    }
+
+    // the players get the new tree right away
+    pub fn register_command(&mut self, command: NodeBuilder) {
+        self.commands.register(command);
+
+        for entity_id in self.players.keys() {
+            self.send_commands(*entity_id);
+        }
+    }
+
+    // only the commands the player is allowed to use, the client would show the others too
+    fn send_commands(&self, entity_id: i32) {
+        let (Some(player), Some(source)) = (self.players.get(&entity_id), self.command_source(CommandSender::Player(entity_id))) else {
+            return
+        };
+
+        player.send(clientbound::Packet::Commands(CommandsData {
+            nodes: self.commands.to_nodes(&source),
+            root_index: 0.into()
+        }));
+    }
+
+    // the console runs at the spawn with every permission
+    pub fn command_source(&self, sender: CommandSender) -> Option<CommandSource> {
+        match sender {
+            CommandSender::Console => {
+                let spawn = self.level.spawn;
+
+                Some(CommandSource {
+                    sender,
+                    name: "Server".to_string(),
+                    permission_level: MAX_PERMISSION_LEVEL,
+                    position: (spawn.x as f64 + 0.5, spawn.y as f64, spawn.z as f64 + 0.5),
+                    rotation: (self.level.spawn_angle, 0.0)
+                })
+            }
+            CommandSender::Player(entity_id) => self.players.get(&entity_id).map(|player| CommandSource {
+                sender,
+                name: player.username.clone(),
+                permission_level: player.permission_level,
+                position: (player.x, player.y, player.z),
+                rotation: (player.yaw, player.pitch)
+            })
+        }
+    }
+
+    // feedback for whoever ran a command, plain text
+    pub fn reply(&self, sender: CommandSender, message: &str) {
+        match sender {
+            CommandSender::Console => info!("{}", message),
+            CommandSender::Player(entity_id) => self.send_system_message(entity_id, &text::plain(message))
+        }
+    }
+
+    pub fn reply_error(&self, sender: CommandSender, message: &str) {
+        match sender {
+            CommandSender::Console => error!("{}", message),
+            CommandSender::Player(entity_id) => self.send_system_message(entity_id, &text::colored(message, "red"))
+        }
+    }
+
+    // the command comes without the slash, errors go back to the sender as well
+    pub fn execute_command(&mut self, sender: CommandSender, command: &str) -> CommandResult {
+        let Some(source) = self.command_source(sender) else {
+            return Ok(0)
+        };
+
+        let result = self.commands.parse(&source, command).and_then(|parsed| parsed.execute(self));
+
+        if let Err(error) = &result {
+            self.reply_error(sender, &error.to_string());
+        }
+
+        result
+    }
+
+    // the completions for the end of the command and where they start, without the slash
+    pub fn complete_command(&self, sender: CommandSender, command: &str) -> (usize, Vec<String>) {
+        let Some(source) = self.command_source(sender) else {
+            return (command.len(), vec![])
+        };
+
+        let suggestions = self.commands.suggest(self, &source, command);
+        (suggestions.start, suggestions.matches)
+    }
+
+    fn suggest_command(&self, entity_id: i32, transaction_id: i32, text: &str) {
+        let Some(player) = self.players.get(&entity_id) else {
+            return
+        };
+
+        let command = text.strip_prefix('/').unwrap_or(text);
+        let (start, matches) = self.complete_command(CommandSender::Player(entity_id), command);
+        // the client counts the slash, and in utf-16 code units like java strings
+        let start = start + text.len() - command.len();
+        let utf16_len = |text: &str| text.encode_utf16().count() as i32;
+
+        player.send(clientbound::Packet::CommandSuggestionsResponse(CommandSuggestionsResponseData {
+            transaction_id: transaction_id.into(),
+            start: utf16_len(&text[..start]).into(),
+            length: utf16_len(&text[start..]).into(),
+            matches: matches.into_iter().map(|text| Suggestion { text, tooltip: None }).collect()
+        }));
+    }
+
+    // in blocks per tick
+    pub fn set_velocity(&mut self, entity_id: i32, x: f64, y: f64, z: f64) {
+        self.tracker.set_velocity(entity_id, &self.players, x, y, z);
+    }
+
+    // the client throws its chunks and entities away on a respawn, so the view starts over at the spawn
+    pub fn respawn(&mut self, entity_id: i32) {
+        let Some(player) = self.players.get(&entity_id) else {
+            return
+        };
+
+        let radius = self.view_radius(player);
+        let spawn = self.level.spawn;
+        let spawn_chunk = ChunkPos::of(&spawn);
+        let player = self.players.get_mut(&entity_id).unwrap();
+        let previous = player.view.center();
+
+        player.send(clientbound::Packet::Respawn(RespawnData {
+            dimension_type: self.dimension.name.as_str().into(),
+            dimension_name: "minecraft:overworld".into(),
+            hashed_seed: hash_seed(self.level.seed),
+            gamemode: player.gamemode,
+            previous_gamemode: -1,
+            is_debug: false,
+            is_flat: self.generator.is_flat(),
+            data_kept: 0,
+            has_death_location: false
+        }));
+
+        player.send(clientbound::Packet::SetCenterChunk(SetCenterChunkData {
+            chunk_x: spawn_chunk.x.into(),
+            chunk_z: spawn_chunk.z.into()
+        }));
+
+        player.view = ChunkView::new(spawn_chunk, radius);
+
+        player.send(clientbound::Packet::SetDefaultSpawnPosition(SetDefaultSpawnPositionData {
+            location: spawn,
+            angle: self.level.spawn_angle
+        }));
+
+        player.teleport(spawn.x as f64 + 0.5, spawn.y as f64, spawn.z as f64 + 0.5, self.level.spawn_angle, 0.0);
+        self.tracker.forget_viewer(entity_id);
+        self.update_player_tickets(entity_id, Some(previous));
+    }
+
+    fn tick(&mut self) {
+        self.update_tickets();
+        self.finish_pending();
+
+        if self.autosave_interval.is_some_and(|interval| self.last_autosave.elapsed() >= interval) {
+            self.last_autosave = Instant::now();
+            self.save();
+        }
+
+        self.level.time += 1;
+
+        if self.level.game_rule("doDaylightCycle") != Some("false") {
+            self.level.day_time += 1;
+        }
+
+        if self.level.time % TIME_UPDATE_INTERVAL == 0 {
+            self.broadcast(self.time_packet());
+        }
+
+        if self.level.game_rule("doWeatherCycle") != Some("false") {
+            let weather = self.level.weather();
+            self.level.tick_weather();
+
+            if self.level.weather() != weather {
+                self.level_dirty = true;
+
+                for packet in self.weather_packets() {
+                    self.broadcast(packet);
+                }
+            }
+        }
+
+        self.stream_chunks();
+        self.check_flying();
+        self.tracker.update(&self.players);
+
+        if self.last_keep_alive.elapsed() >= KEEP_ALIVE_INTERVAL {
+            self.last_keep_alive = Instant::now();
+
+            let keep_alive_id = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as i64;
+
+            for player in self.players.values_mut() {
+                player.keep_alive(keep_alive_id);
+            }
+        }
+    }
+
+    pub fn broadcast(&self, packet: clientbound::Packet) {
+        let frame = ConnectionWriter::<Play>::frame(&packet);
+
+        for player in self.players.values() {
+            player.send_frame(frame.clone());
+        }
+    }
+
+    // only the chunks that player has been sent
+    pub fn broadcast_to_viewers(&self, pos: ChunkPos, packet: clientbound::Packet) {
+        let frame = ConnectionWriter::<Play>::frame(&packet);
+
+        for player in self.players.values().filter(|player| player.view.is_sent(pos)) {
+            player.send_frame(frame.clone());
+        }
+    }
+
+    pub fn connect_client(&mut self, connection: Connection<Play>, uuid: UUID, username: String, properties: Vec<ProfileProperty>) {
+        let entity_id = self.tracker.next_entity_id();
+
+        let mut player = Player::spawn(entity_id, uuid, username, properties, connection, self.events());
+
+        if let Some(reason) = self.refusal(&player.username) {
+            info!("<green>{}</> was refused: {}", player.username, reason);
+
+            // dropping the player closes the connection once the message is out
+            player.send(clientbound::Packet::Disconnect(DisconnectData {
+                reason: text::plain(&reason)
+            }));
+
+            return
+        }
+
+        player.permission_level = self.access.operator(&player.username).map_or(0, |operator| operator.permission_level);
+        self.wait_for(Pending::Join(Box::new(player)));
+    }
+
+    // the chunks around the spawn are loaded and go out before the position, the rest is streamed from the tick loop
+    fn join(&mut self, mut player: Player) {
+        let entity_id = player.entity_id;
+
+        player.send(clientbound::Packet::Login(LoginData {
+            entity_id,
+            is_hardcore: false,
+            gamemode: player.gamemode,
+            previous_gamemode: 0,
+            dimensions: vec![Identifier::from("minecraft:overworld"), Identifier::from("minecraft:the_nether"), Identifier::from("minecraft:the_end")],
+            registry_codec: self.registry.nbt(),
+            dimension_type: self.dimension.name.as_str().into(),
+            dimension_name: "minecraft:overworld".into(),
+            hashed_seed: hash_seed(self.level.seed),
+            max_players: (self.max_players as i32).into(),
+            render_distance: self.view_distance.into(),
+            simulation_distance: self.simulation_distance.into(),
+            reduced_debug_info: false,
+            enable_respawn_screen: true,
+            is_debug: false,
+            is_flat: self.generator.is_flat(),
+            has_death_location: false
+        }));
+
+        /*player.send(clientbound::Packet::SetHeldItem(SetHeldItemData {
+            slot: 0
+        }));
+
+        player.send(clientbound::Packet::UpdateRecipes(UpdateRecipesData {
+            recipes_count: 0.into()
+        }));
+
+        player.send(clientbound::Packet::UpdateTags(UpdateTagsData {
+            tags_count: 0.into()
+        }));*/
+
+        let spawn_chunk = ChunkPos::of(&self.level.spawn);
+
+        player.send(clientbound::Packet::SetCenterChunk(SetCenterChunkData {
+            chunk_x: spawn_chunk.x.into(),
+            chunk_z: spawn_chunk.z.into()
+        }));
+
+        let biome_count = self.biome_count();
+        player.view = ChunkView::new(spawn_chunk, self.view_radius(&player));
+
+        let chunks = &self.chunks;
+
+        for pos in player.view.next(9, |pos| chunks.contains_key(&pos)) {
+            player.send(chunks[&pos].to_packet(biome_count));
+        }
+
+        player.send(clientbound::Packet::SetDefaultSpawnPosition(SetDefaultSpawnPositionData {
+            location: self.level.spawn,
+            angle: self.level.spawn_angle
+        }));
+
+        let spawn = self.level.spawn;
+        player.teleport(spawn.x as f64 + 0.5, spawn.y as f64, spawn.z as f64 + 0.5, self.level.spawn_angle, 0.0);
+
+        /*let packet = clientbound::Packet::Disconnect(DisconnectData {
+            reason: String::from("[{\"text\":\"[Intro]\n\",\"color\":\"gray\",\"underlined\":true,\"obfuscated\":true},{\"text\":\"Desert you\nOoh-ooh-ooh-ooh\nHurt you\n\n\",\"color\":\"white\",\"underlined\":false},{\"text\":\"[Verse 1]\n\",\"color\":\"gray\",\"underlined\":true},{\"text\":\"We're no strangers to love\nYou know the rules and so do I\nA full commitment's what I'm thinking of\nYou wouldn't get this from any other guy\n\n\",\"color\":\"white\",\"underlined\":false},{\"text\":\"[Pre-Chorus]\n\",\"color\":\"gray\",\"underlined\":true},{\"text\":\"I just wanna tell you how I'm feeling\nGotta make you understand\n\n\",\"color\":\"white\",\"underlined\":false},{\"text\":\"[Chorus]\n\",\"color\":\"gray\",\"underlined\":true},{\"text\":\"Never gonna give you up\nNever gonna let you down\nNever gonna run around and desert you\nNever gonna make you cry\nNever gonna say goodbye\nNever gonna tell a lie and hurt you\n\n\",\"color\":\"white\",\"underlined\":false},{\"text\":\"[Verse 2]\n\",\"color\":\"gray\",\"underlined\":true},{\"text\":\"We've known each other for so long\nYour heart's been aching, but you're too shy to say it\nInside, we both know what's been going on\nWe know the game, and we're gonna play it\n\n\",\"color\":\"white\",\"underlined\":false},{\"text\":\"[Pre-Chorus]\n\",\"color\":\"gray\",\"underlined\":true},{\"text\":\"And if you ask me how I'm feeling\nDon't tell me you're too blind to see\n\n\",\"color\":\"white\",\"underlined\":false},{\"text\":\"[Chorus]\n\",\"color\":\"gray\",\"underlined\":true},{\"text\":\"Never gonna give you up\nNever gonna let you down\nNever gonna run around and desert you\nNever gonna make you cry\nNever gonna say goodbye\nNever gonna tell a lie and hurt you\nNever gonna give you up\nNever gonna let you down\nNever gonna run around and desert you\nNever gonna make you cry\nNever gonna say goodbye\nNever gonna tell a lie and hurt you\n\n\",\"color\":\"red\",\"underlined\":false,\"bold\":true},{\"text\":\"[Post-Chorus]\n\",\"color\":\"gray\",\"underlined\":true,\"bold\":false},{\"text\":\"Ooh (Give you up)\nOoh-ooh (Give you up)\nOoh-ooh\nNever gonna give, never gonna give (Give you up)\nOoh-ooh\nNever gonna give, never gonna give (Give you up)\n\n\",\"color\":\"white\",\"underlined\":false},{\"text\":\"[Bridge]\n\",\"color\":\"gray\",\"underlined\":true},{\"text\":\"We've known each other for so long\nYour heart's been aching, but you're too shy to say it\nInside, we both know what's been going on\nWe know the game, and we're gonna play it\n\n\",\"color\":\"white\",\"underlined\":false},{\"text\":\"[Pre-Chorus]\n\",\"color\":\"gray\",\"underlined\":true},{\"text\":\"I just wanna tell you how I'm feeling\nGotta make you understand\n\n\",\"color\":\"white\",\"underlined\":false},{\"text\":\"[Chorus]\n\",\"color\":\"gray\",\"underlined\":true},{\"text\":\"Never gonna give you up\nNever gonna let you down\nNever gonna run around and desert you\nNever gonna make you cry\nNever gonna say goodbye\nNever gonna tell a lie and hurt you\nNever gonna give you up\nNever gonna let you down\nNever gonna run around and desert you\nNever gonna make you cry\nNever gonna say goodbye\nNever gonna tell a lie and hurt you\nNever gonna give you up\nNever gonna let you down\nNever gonna run around and desert you\nNever gonna make you cry\nNever gonna say goodbye\nNever gonna tell a lie and hurt you\",\"color\":\"white\",\"underlined\":false}]")
+        });
+
+        player.send(packet);*/
+
+        info!("<green>{}</> joined the game", &player.username);
+
+        for plugin in &mut self.plugins {
+            plugin.on_join(&player);
+        }
+
+        // the others learn about the new player, and it about everyone including itself, clients only spawn players they have an entry for
+        self.broadcast_info(player.info());
+
+        let infos = self.players.values().chain([&player]).map(Player::info).collect();
+
+        player.send(clientbound::Packet::PlayerInfoUpdate(PlayerInfoUpdateData {
+            players: PlayerInfoEntries(infos)
+        }));
+
+        player.send(self.tab_list_packet());
+        player.send(Self::permission_level_packet(&player));
+        player.send(self.difficulty_packet());
+        player.send(self.time_packet());
+
+        if self.level.weather() != Weather::Clear {
+            for packet in self.weather_packets() {
+                player.send(packet);
+            }
+        }
+
+        player.send(clientbound::Packet::ServerData(ServerDataData {
+            motd: None,
+            icon: None,
+            enforces_secure_chat: matches!(self.chat_mode, ChatMode::Secure(_))
+        }));
+
+        self.tracker.track(&player);
+        self.players.insert(entity_id, player);
+        self.send_commands(entity_id);
+        self.update_player_tickets(entity_id, None);
+    }
+
+    pub fn kick(&mut self, entity_id: i32, message: &str) {
+        self.disconnect(entity_id, DisconnectReason::Kicked(message.to_string()));
+    }
+
+    pub fn disconnect(&mut self, entity_id: i32, reason: DisconnectReason) {
+        // the reader of a kicked player may still report its own disconnect afterwards
+        let Some(mut player) = self.players.remove(&entity_id) else {
+            // a player still waiting for the chunks around the spawn never got into the world, it only has to be dropped
+            let joining = self.pending.iter().position(|pending| matches!(pending, Pending::Join(player) if player.entity_id == entity_id));
+
+            if let Some(Pending::Join(player)) = joining.map(|index| self.pending.remove(index)) {
+                if let DisconnectReason::Kicked(message) = &reason {
+                    player.send(clientbound::Packet::Disconnect(DisconnectData {
+                        reason: text::plain(message)
+                    }));
+                }
+            }
+
+            return
+        };
+
+        self.tickets.remove(player.view.center(), &TicketKind::Player(entity_id));
+
+        if let DisconnectReason::Kicked(message) = &reason {
+            player.send(clientbound::Packet::Disconnect(DisconnectData {
+                reason: text::plain(message)
+            }));
+        }
+
+        info!("<green>{}</> left the game ({})", &player.username, &reason);
+
+        for plugin in &mut self.plugins {
+            plugin.on_quit(&player, &reason);
+        }
+
+        if let Some(nbt) = player.changed_nbt() {
+            self.saver.save_file(self.player_path(&player), nbt);
+        }
+
+        self.broadcast(clientbound::Packet::PlayerInfoRemove(PlayerInfoRemoveData {
+            players: vec![player.uuid]
+        }));
+
+        self.tracker.untrack(entity_id, &self.players);
+    }
+
+    fn player_path(&self, player: &Player) -> PathBuf {
+        self.directory.join("playerdata").join(format!("{}.dat", player.uuid))
+    }
+}
+
+// the client only ever sees the first eight bytes of the sha-256 of the seed, which it uses for biome blending
+pub fn hash_seed(seed: i64) -> i64 {
+    let digest = Sha256::digest(seed.to_le_bytes());
+    i64::from_le_bytes(digest[..8].try_into().unwrap())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use generator::flat::FlatGenerator;
+    use storage::MemoryStorage;
+    use tokio::net::{TcpListener, TcpStream};
+
+    // a flat world with its spawn settled, the directory is removed again by `remove`
+    pub(crate) fn flat_world(name: &str) -> (World, PathBuf) {
+        let directory = std::env::temp_dir().join(format!("mcserver-{}-{}", name, std::process::id()));
+        let mut world = World::new(&directory, 0);
+
+        world.set_generator(FlatGenerator::new("bedrock,2*dirt,grass_block", "minecraft:plains", &world.registry.clone()).unwrap());
+        finish_pending(&mut world);
+        (world, directory)
+    }
+
+    pub(crate) fn remove(world: World, directory: PathBuf) {
+        drop(world);
+        let _ = std::fs::remove_dir_all(directory);
+    }
+
+    // a player joined over a real connection, the client end has to be kept open
+    pub(crate) async fn join(world: &mut World, username: &str) -> (i32, TcpStream) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let client = TcpStream::connect(listener.local_addr().unwrap()).await.unwrap();
+        let (stream, _) = listener.accept().await.unwrap();
+        let uuid = UUID(username.bytes().fold(0, |uuid, byte| uuid << 8 | byte as u128));
+
+        world.connect_client(Connection::new(stream).into_login().into_play(), uuid, username.to_string(), vec![]);
+        finish_pending(world);
+
+        let entity_id = world.player_by_name(username).map_or(-1, |player| player.entity_id);
+        (entity_id, client)
+    }
+
+    // the teleport to the spawn, which the client confirms before anything else
+    pub(crate) fn confirm_teleport(world: &mut World, entity_id: i32) {
+        let player = world.players.get_mut(&entity_id).unwrap();
+        let teleport_id = player.pending_teleport().unwrap();
+        player.confirm_teleport(teleport_id);
+    }
+
+    // ticks until nothing is pending, between ticks it only blocks while the workers still owe chunks
+    fn finish_pending(world: &mut World) {
+        let started = Instant::now();
+
+        while !world.pending.is_empty() {
+            assert!(started.elapsed() < Duration::from_secs(120), "the pending work never finished");
+            world.tick();
+
+            if !world.requested.is_empty() {
+                assert!(world.workers.wait(Duration::from_secs(60)), "the workers stopped bringing chunks back");
+            }
+        }
+    }
+
+    // the same as Hashing.sha256().hashLong(seed).asLong() in vanilla
+    #[test]
+    fn hashes_seed_like_vanilla() {
+        assert_eq!(hash_seed(0), 8794265229978523055);
+        assert_eq!(hash_seed(1), -6467378160175308932);
+        assert_eq!(hash_seed(-4172144997902289642), 2159143436479834350);
+    }
+
+    // the workers generate the chunks while the world keeps ticking, nothing is ever saved
+    #[test]
+    fn searches_spawn_in_the_background() {
+        let mut world = World::new(std::env::temp_dir().join(format!("mcserver-spawn-{}", std::process::id())), 0);
+
+        assert!(matches!(world.pending[..], [Pending::SpawnSearch { radius: 0, .. }]));
+        assert_eq!(world.spawn(), Pos { x: 0, y: 64, z: 0 });
+
+        finish_pending(&mut world);
+
+        let spawn = world.spawn();
+        let chunk = ChunkPos::of(&spawn);
+
+        assert!(chunk.x.abs().max(chunk.z.abs()) <= SPAWN_SEARCH_RADIUS);
+        assert_eq!((spawn.x & 15, spawn.z & 15), (8, 8));
+        assert!(world.get_block(Pos { y: spawn.y - 1, ..spawn }).is_some());
+        assert!(world.tickets.tickets(chunk).all(|ticket| ticket.kind != TicketKind::Pending));
+    }
+
+    #[test]
+    fn writes_only_what_changed() {
+        let directory = std::env::temp_dir().join(format!("mcserver-dirty-{}", std::process::id()));
+        let mut world = World::new(&directory, 0);
+        let level = directory.join("level.dat");
+        let access = directory.join("access.dat");
+
+        world.set_generator(FlatGenerator::new("bedrock,2*dirt,grass_block", "minecraft:plains", &world.registry.clone()).unwrap());
+
+        // the spawn search moves the spawn when it finishes
+        finish_pending(&mut world);
+
+        world.save();
+        world.saver.flush();
+        assert!(level.exists());
+
+        std::fs::remove_file(&level).unwrap();
+        let _ = std::fs::remove_file(&access);
+
+        // the time moves on, but nothing else changed
+        for _ in 0..40 {
+            world.tick();
+        }
+
+        world.save();
+        world.saver.flush();
+        assert!(!level.exists());
+        assert!(!access.exists());
+
+        world.set_difficulty(3);
+        world.save();
+        world.saver.flush();
+        assert!(level.exists());
+        assert!(!access.exists());
+
+        world.op("Notch", 4);
+        world.save();
+        world.saver.flush();
+        assert!(access.exists());
+
+        drop(world);
+        let _ = std::fs::remove_dir_all(directory);
+    }
+
+    #[test]
+    fn keeps_changes_when_the_storage_changes() {
+        let directory = std::env::temp_dir().join(format!("mcserver-storage-{}", std::process::id()));
+        let mut world = World::new(&directory, 0);
+        let pos = ChunkPos::new(-4, 5);
+        let block = Pos { x: -60, y: 70, z: 90 };
+
+        world.insert_chunk(world.workers.source().load(pos));
+        world.set_block(block, BlockState::from_name("minecraft:gold_block").unwrap());
+        world.set_storage(MemoryStorage::new());
+
+        assert!(world.chunk(pos).is_none());
+        assert!(RegionStorage::new(directory.join("region")).load(pos).unwrap().is_some());
+        assert!(world.stored_chunks().unwrap().is_empty());
+
+        drop(world);
+        let _ = std::fs::remove_dir_all(directory);
+    }
+
+    #[tokio::test]
+    async fn ignores_moves_until_the_teleport_is_confirmed() {
+        let (mut world, directory) = flat_world("teleport");
+        let (entity_id, _client) = join(&mut world, "Steve").await;
+        let spawn = (world.players[&entity_id].x, world.players[&entity_id].y, world.players[&entity_id].z);
+
+        world.move_player(entity_id, Some((spawn.0 + 1.0, spawn.1, spawn.2)), None, true);
+        assert_eq!(world.players[&entity_id].x, spawn.0);
+
+        confirm_teleport(&mut world, entity_id);
+        world.move_player(entity_id, Some((spawn.0 + 1.0, spawn.1, spawn.2)), None, true);
+        assert_eq!(world.players[&entity_id].x, spawn.0 + 1.0);
+
+        remove(world, directory);
+    }
+
+    #[tokio::test]
+    async fn puts_players_back_out_of_blocks() {
+        let (mut world, directory) = flat_world("collision");
+        let (entity_id, _client) = join(&mut world, "Steve").await;
+        confirm_teleport(&mut world, entity_id);
+
+        let player = &world.players[&entity_id];
+        let (x, y, z) = (player.x, player.y, player.z);
+        let wall = Pos { x: (x + 1.0).floor() as i32, y: y as i16 + 1, z: z.floor() as i32 };
+
+        // into the ground, and into a wall at head height
+        world.move_player(entity_id, Some((x, y - 0.5, z)), None, true);
+        assert!(world.players[&entity_id].is_teleporting());
+        assert_eq!(world.players[&entity_id].y, y);
+        confirm_teleport(&mut world, entity_id);
+
+        world.set_block(wall, BlockState::STONE);
+        world.move_player(entity_id, Some((x + 1.0, y, z)), None, true);
+        assert!(world.players[&entity_id].is_teleporting());
+        assert_eq!(world.players[&entity_id].x, x);
+        confirm_teleport(&mut world, entity_id);
+
+        // next to the wall is fine, and so is a slab it has no shape for
+        world.move_player(entity_id, Some((wall.x as f64 - 0.3, y, z)), None, true);
+        assert!(!world.players[&entity_id].is_teleporting());
+
+        world.set_block(wall, BlockState::from_name("minecraft:oak_slab").unwrap());
+        world.move_player(entity_id, Some((x + 1.0, y, z)), None, true);
+        assert!(!world.players[&entity_id].is_teleporting());
+        assert_eq!(world.players[&entity_id].x, x + 1.0);
+
+        remove(world, directory);
+    }
+
+    #[tokio::test]
+    async fn kicks_players_floating_too_long() {
+        let (mut world, directory) = flat_world("flying");
+        let (entity_id, _client) = join(&mut world, "Steve").await;
+        confirm_teleport(&mut world, entity_id);
+
+        let player = &world.players[&entity_id];
+        let (x, y, z) = (player.x, player.y, player.z);
+
+        world.move_player(entity_id, Some((x, y + 3.0, z)), None, false);
+        assert!(world.players[&entity_id].floating);
+
+        for _ in 0..MAX_AIRBORNE_TICKS {
+            world.tick();
+        }
+
+        // coming back down in time starts over
+        world.move_player(entity_id, Some((x, y, z)), None, true);
+        world.tick();
+        assert_eq!(world.players[&entity_id].airborne_ticks, 0);
+
+        world.move_player(entity_id, Some((x, y + 3.0, z)), None, false);
+
+        for _ in 0..=MAX_AIRBORNE_TICKS {
+            world.tick();
+        }
+
+        assert!(!world.players.contains_key(&entity_id));
+
+        remove(world, directory);
+    }
+
+    struct ShoutingFormatter;
+
+    impl ChatFormatter for ShoutingFormatter {
+        fn name(&self, player: &Player) -> String {
+            text::plain(&format!("[Admin] {}", player.username))
+        }
+
+        fn message(&self, _player: &Player, message: &str) -> Option<String> {
+            (message != "quiet").then(|| text::plain(&message.to_uppercase()))
+        }
+
+        fn chat_type(&self, _player: &Player) -> &str {
+            "minecraft:say_command"
+        }
+    }
+
+    fn chat_message(message: &str) -> ChatMessageData {
+        ChatMessageData { message: message.to_string(), timestamp: 0, salt: 0, signature: None, message_count: 0.into(), acknowledged: [0; 3] }
+    }
+
+    #[tokio::test]
+    async fn formats_disguised_and_system_chat() {
+        let (mut world, directory) = flat_world("chat");
+        let (entity_id, _client) = join(&mut world, "Steve").await;
+        let chat_type = world.registry.id("minecraft:chat_type", "minecraft:chat").unwrap();
+
+        // the clients format disguised chat with the chat type
+        let packet = world.chat_packet(entity_id, chat_message("hello"), None, vec![]);
+        assert!(matches!(packet, Some(clientbound::Packet::DisguisedChatMessage(data))
+            if data.message == text::plain("hello") && data.chat_type_name == text::plain("Steve") && i32::from(data.chat_type.clone()) == chat_type));
+
+        // and the server formats system chat the way minecraft:chat would
+        world.set_chat_mode(ChatMode::System);
+        let packet = world.chat_packet(entity_id, chat_message("hello"), None, vec![]);
+        assert!(matches!(packet, Some(clientbound::Packet::SystemChatMessage(data))
+            if data.content == text::translate("chat.type.text", &[&text::plain("Steve"), &text::plain("hello")]) && !data.overlay));
+
+        world.set_chat_formatter(ShoutingFormatter);
+        let packet = world.chat_packet(entity_id, chat_message("hello"), None, vec![]);
+        assert!(matches!(packet, Some(clientbound::Packet::SystemChatMessage(data))
+            if data.content == text::translate("chat.type.text", &[&text::plain("[Admin] Steve"), &text::plain("HELLO")])));
+        assert!(world.chat_packet(entity_id, chat_message("quiet"), None, vec![]).is_none());
+
+        world.set_chat_mode(ChatMode::Disguised);
+        let say_command = world.registry.id("minecraft:chat_type", "minecraft:say_command").unwrap();
+        let packet = world.chat_packet(entity_id, chat_message("hello"), None, vec![]);
+        assert!(matches!(packet, Some(clientbound::Packet::DisguisedChatMessage(data))
+            if data.message == text::plain("HELLO") && data.chat_type_name == text::plain("[Admin] Steve") && i32::from(data.chat_type.clone()) == say_command));
+
+        remove(world, directory);
+    }
+
+    #[test]
+    fn keeps_changes_when_the_generator_changes() {
+        let directory = std::env::temp_dir().join(format!("mcserver-generator-{}", std::process::id()));
+        let mut world = World::new(&directory, 0);
+        let pos = ChunkPos::new(3, -2);
+        let block = Pos { x: 49, y: 70, z: -30 };
+
+        world.insert_chunk(world.workers.source().load(pos));
+        world.set_block(block, BlockState::from_name("minecraft:gold_block").unwrap());
+        world.set_generator(FlatGenerator::new("bedrock,2*dirt,grass_block", "minecraft:plains", &world.registry.clone()).unwrap());
+
+        assert!(world.chunk(pos).is_none());
+        assert_eq!(world.workers.source().load(pos).get_block(block.x, block.y as i32, block.z), BlockState::from_name("minecraft:gold_block").unwrap());
+        assert_eq!(world.workers.source().load(ChunkPos::new(4, -2)).get_block(64, -61, -32), BlockState::from_name("minecraft:grass_block").unwrap());
+
+        drop(world);
+        let _ = std::fs::remove_dir_all(directory);
+    }
+}
@@ -0,0 +1,157 @@
+use std::io::Write;
+
+use crate::{protocol::Encode, types::VarI32};
+
+// ceil(log2) of the number of block states in 1.19.4
+pub const BLOCK_STATE_BITS: u8 = 15;
+
+pub enum PalettedContainer {
+    Single(u32),
+    Indirect { bits: u8, palette: Vec<u32>, data: Vec<i64> },
+    Direct { bits: u8, data: Vec<i64> }
+}
+
+// bits needed to tell apart `count` distinct values
+pub fn bits_for(count: usize) -> u8 {
+    (usize::BITS - count.saturating_sub(1).leading_zeros()) as u8
+}
+
+// entries never span two longs, the leftover high bits stay zero
+pub fn pack(values: impl ExactSizeIterator<Item = u32>, bits: u8) -> Vec<i64> {
+    let per_long = 64 / bits as usize;
+    let mut data = vec![0i64; values.len().div_ceil(per_long)];
+
+    for (index, value) in values.enumerate() {
+        let shift = (index % per_long) * bits as usize;
+        data[index / per_long] |= (value as i64) << shift;
+    }
+
+    data
+}
+
+pub fn unpack(data: &[i64], bits: u8, len: usize) -> Vec<u32> {
+    let per_long = 64 / bits as usize;
+    let mask = (1u64 << bits) - 1;
+
+    (0..len)
+        .map(|index| {
+            let long = data.get(index / per_long).copied().unwrap_or(0) as u64;
+            ((long >> ((index % per_long) * bits as usize)) & mask) as u32
+        })
+        .collect()
+}
+
+impl PalettedContainer {
+    pub fn new(values: &[u32], min_indirect_bits: u8, max_indirect_bits: u8, direct_bits: u8) -> Self {
+        let mut palette: Vec<u32> = vec![];
+
+        for value in values {
+            if !palette.contains(value) {
+                palette.push(*value);
+            }
+        }
+
+        if palette.len() == 1 {
+            return Self::Single(palette[0])
+        }
+
+        let bits = bits_for(palette.len()).max(min_indirect_bits);
+
+        if bits > max_indirect_bits {
+            return Self::Direct {
+                bits: direct_bits,
+                data: pack(values.iter().copied(), direct_bits)
+            }
+        }
+
+        let data = pack(values.iter().map(|value| palette.iter().position(|entry| entry == value).unwrap() as u32), bits);
+
+        Self::Indirect { bits, palette, data }
+    }
+
+    pub fn blocks(values: &[u32]) -> Self {
+        Self::new(values, 4, 8, BLOCK_STATE_BITS)
+    }
+
+    pub fn biomes(values: &[u32], biome_count: usize) -> Self {
+        Self::new(values, 1, 3, bits_for(biome_count))
+    }
+}
+
+impl Encode for PalettedContainer {
+    fn encode(&self, mut writer: impl Write) -> usize {
+        match self {
+            Self::Single(value) => {
+                0u8.encode(&mut writer)
+                    + VarI32::from(*value as i32).encode(&mut writer)
+                    + VarI32::from(0).encode(&mut writer)
+            },
+            Self::Indirect { bits, palette, data } => {
+                let mut size = bits.encode(&mut writer) + VarI32::from(palette.len() as i32).encode(&mut writer);
+
+                for entry in palette {
+                    size += VarI32::from(*entry as i32).encode(&mut writer);
+                }
+
+                size + data.encode(&mut writer)
+            },
+            Self::Direct { bits, data } => {
+                bits.encode(&mut writer) + data.encode(&mut writer)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(container: PalettedContainer) -> Vec<u8> {
+        let mut bytes = vec![];
+        container.encode(&mut bytes);
+        bytes
+    }
+
+    #[test]
+    fn bits() {
+        assert_eq!([1, 2, 3, 4, 5, 16, 17, 385].map(bits_for), [0, 1, 2, 2, 3, 4, 5, 9]);
+    }
+
+    // twelve five bit entries fill 60 bits, the thirteenth starts the next long
+    #[test]
+    fn packs_without_spanning() {
+        let data = pack([31; 13].into_iter(), 5);
+
+        assert_eq!(data, [0x0FFF_FFFF_FFFF_FFFF, 0x1F]);
+        assert_eq!(unpack(&data, 5, 13), [31; 13]);
+        assert_eq!(pack([1, 2, 3, 15].into_iter(), 4), [0xF321]);
+    }
+
+    #[test]
+    fn encodes_single_value() {
+        assert_eq!(encode(PalettedContainer::blocks(&[5; 4096])), [0, 5, 0]);
+        assert_eq!(encode(PalettedContainer::biomes(&[40; 64], 64)), [0, 40, 0]);
+    }
+
+    #[test]
+    fn encodes_indirect() {
+        let values: Vec<u32> = (0..4096).map(|index| if index % 2 == 0 { 1 } else { 9 }).collect();
+        let mut expected = vec![4, 2, 1, 9, 0x80, 0x02];
+        expected.extend([0x10; 256 * 8]);
+
+        assert_eq!(encode(PalettedContainer::blocks(&values)), expected);
+
+        let values: Vec<u32> = (0..64).map(|index| if index % 2 == 0 { 3 } else { 7 }).collect();
+        assert_eq!(encode(PalettedContainer::biomes(&values, 64)), [1, 2, 3, 7, 1, 0xAA, 0xAA, 0xAA, 0xAA, 0xAA, 0xAA, 0xAA, 0xAA]);
+    }
+
+    // more than 256 distinct states do not fit an eight bit palette
+    #[test]
+    fn encodes_direct() {
+        let values: Vec<u32> = (0..4096).map(|index| index % 300).collect();
+        let bytes = encode(PalettedContainer::blocks(&values));
+
+        assert_eq!(bytes.len(), 3 + 1024 * 8);
+        assert_eq!(bytes[..11], [15, 0x80, 0x08, 0x00, 0x00, 0x60, 0x00, 0x80, 0x00, 0x80, 0x00]);
+    }
+}
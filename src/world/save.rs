@@ -0,0 +1,210 @@
+use std::{collections::HashMap, fs::{self, File}, io::{self, Write}, path::{Path, PathBuf}, sync::{mpsc, Arc, Mutex}, thread::{self, JoinHandle}};
+
+use crate::error;
+
+use crate::{nbt::Compound, registry::{DimensionType, RegistryCodec}};
+
+use super::{chunk::{Chunk, ChunkPos}, storage::ChunkStorage};
+
+type Storage = Arc<Mutex<Box<dyn ChunkStorage>>>;
+
+enum Job {
+    Chunk(ChunkPos),
+    Delete(ChunkPos),
+    File(PathBuf, Compound),
+    Flush(mpsc::Sender<()>)
+}
+
+// a chunk waiting for the writer, the generation tells a newer save of the same chunk apart
+struct Pending {
+    generation: u64,
+    nbt: Arc<Compound>
+}
+
+// reads chunks through the saver, so a chunk that is still on its way to the storage is read as saved
+#[derive(Clone)]
+pub struct ChunkReader {
+    storage: Storage,
+    // serialized but not yet on disk, reads look here first so they never see an older copy
+    pending: Arc<Mutex<HashMap<ChunkPos, Pending>>>
+}
+
+impl ChunkReader {
+    pub fn load_chunk(&self, pos: ChunkPos, registry: &RegistryCodec, dimension: &DimensionType) -> io::Result<Option<Chunk>> {
+        let pending = self.pending.lock().unwrap().get(&pos).map(|pending| pending.nbt.clone());
+
+        let nbt = match pending {
+            Some(nbt) => nbt,
+            None => match self.storage.lock().unwrap().load(pos)? {
+                Some(nbt) => Arc::new(nbt),
+                None => return Ok(None)
+            }
+        };
+
+        Chunk::from_anvil(&nbt, registry, dimension).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+    }
+}
+
+// hands chunks and nbt files to a writer thread so the tick loop never waits on the disk
+pub struct Saver {
+    storage: Storage,
+    pending: Arc<Mutex<HashMap<ChunkPos, Pending>>>,
+    generation: u64,
+    jobs: Option<mpsc::Sender<Job>>,
+    thread: Option<JoinHandle<()>>
+}
+
+impl Saver {
+    pub fn new(storage: impl ChunkStorage + 'static) -> Self {
+        let storage: Storage = Arc::new(Mutex::new(Box::new(storage)));
+        let pending = Arc::new(Mutex::new(HashMap::new()));
+        let (jobs, receiver) = mpsc::channel();
+
+        let thread = {
+            let storage = storage.clone();
+            let pending = pending.clone();
+            thread::spawn(move || write_jobs(receiver, storage, pending))
+        };
+
+        Self { storage, pending, generation: 0, jobs: Some(jobs), thread: Some(thread) }
+    }
+
+    pub fn reader(&self) -> ChunkReader {
+        ChunkReader { storage: self.storage.clone(), pending: self.pending.clone() }
+    }
+
+    // the saved chunks, including the ones still on their way to the storage
+    pub fn list_chunks(&self) -> io::Result<Vec<ChunkPos>> {
+        let mut chunks = self.storage.lock().unwrap().list()?;
+
+        for pos in self.pending.lock().unwrap().keys() {
+            if !chunks.contains(pos) {
+                chunks.push(*pos);
+            }
+        }
+
+        Ok(chunks)
+    }
+
+    pub fn delete_chunk(&mut self, pos: ChunkPos) {
+        self.pending.lock().unwrap().remove(&pos);
+        self.send(Job::Delete(pos));
+    }
+
+    pub fn save_chunk(&mut self, pos: ChunkPos, nbt: Compound) {
+        self.generation += 1;
+        self.pending.lock().unwrap().insert(pos, Pending { generation: self.generation, nbt: Arc::new(nbt) });
+        self.send(Job::Chunk(pos));
+    }
+
+    // written as gzipped nbt next to the old file and renamed over it
+    pub fn save_file(&mut self, path: impl Into<PathBuf>, nbt: Compound) {
+        self.send(Job::File(path.into(), nbt));
+    }
+
+    // blocks until everything handed over so far is on disk
+    pub fn flush(&self) {
+        let (done, wait) = mpsc::channel();
+        self.send(Job::Flush(done));
+        let _ = wait.recv();
+    }
+
+    fn send(&self, job: Job) {
+        if let Some(jobs) = &self.jobs {
+            // the writer only stops when the saver is dropped
+            let _ = jobs.send(job);
+        }
+    }
+}
+
+impl Drop for Saver {
+    fn drop(&mut self) {
+        self.jobs.take();
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn write_jobs(receiver: mpsc::Receiver<Job>, storage: Storage, pending: Arc<Mutex<HashMap<ChunkPos, Pending>>>) {
+    let mut next = None;
+
+    while let Some(job) = next.take().or_else(|| receiver.recv().ok()) {
+        match job {
+            Job::Chunk(pos) => {
+                // everything queued behind it is written together, so one autosave ends up in one transaction
+                let mut positions = vec![pos];
+
+                for job in receiver.try_iter() {
+                    match job {
+                        Job::Chunk(pos) => positions.push(pos),
+                        job => {
+                            next = Some(job);
+                            break
+                        }
+                    }
+                }
+
+                write_chunks(&positions, &storage, &pending);
+            }
+            Job::Delete(pos) => {
+                if let Err(err) = storage.lock().unwrap().delete(pos) {
+                    error!("failed to delete chunk <green>{} {}</>\n{}", pos.x, pos.z, err);
+                }
+            }
+            Job::File(path, nbt) => {
+                if let Err(err) = write_atomic(&path, |file| nbt.write_gzip(file, "")) {
+                    error!("failed to save <green>'{}'</>\n{}", path.display(), err);
+                }
+            }
+            Job::Flush(done) => {
+                let _ = done.send(());
+            }
+        }
+    }
+}
+
+fn write_chunks(positions: &[ChunkPos], storage: &Storage, pending: &Mutex<HashMap<ChunkPos, Pending>>) {
+    // a chunk saved twice before the writer got to it is only written once
+    let chunks: HashMap<ChunkPos, (u64, Arc<Compound>)> = {
+        let pending = pending.lock().unwrap();
+
+        positions.iter()
+            .filter_map(|pos| pending.get(pos).map(|pending| (*pos, (pending.generation, pending.nbt.clone()))))
+            .collect()
+    };
+
+    let batch: Vec<(ChunkPos, &Compound)> = chunks.iter().map(|(pos, (_, nbt))| (*pos, nbt.as_ref())).collect();
+
+    // failed chunks stay pending, so they are still read back as saved and written with their next save
+    if let Err(err) = storage.lock().unwrap().save_all(&batch) {
+        error!("failed to save {} chunks\n{}", batch.len(), err);
+        return
+    }
+
+    let mut pending = pending.lock().unwrap();
+
+    for (pos, (generation, _)) in &chunks {
+        if pending.get(pos).is_some_and(|pending| pending.generation == *generation) {
+            pending.remove(pos);
+        }
+    }
+}
+
+// a crash leaves either the old file or the new one, never half of each
+pub fn write_atomic(path: &Path, write: impl FnOnce(&mut File) -> io::Result<()>) -> io::Result<()> {
+    if let Some(directory) = path.parent() {
+        fs::create_dir_all(directory)?;
+    }
+
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+
+    let mut file = File::create(&temporary)?;
+    write(&mut file)?;
+    file.flush()?;
+    file.sync_all()?;
+
+    fs::rename(&temporary, path)
+}
@@ -0,0 +1,213 @@
+use std::{collections::HashMap, io, path::Path};
+
+use flate2::{read::ZlibDecoder, write::ZlibEncoder, Compression};
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::nbt::Compound;
+
+use super::chunk::ChunkPos;
+
+// where the chunks of one dimension are kept, chunks go in and out as their saved nbt
+pub trait ChunkStorage: Send {
+    fn load(&mut self, pos: ChunkPos) -> io::Result<Option<Compound>>;
+
+    fn save(&mut self, pos: ChunkPos, nbt: &Compound) -> io::Result<()>;
+
+    fn delete(&mut self, pos: ChunkPos) -> io::Result<()>;
+
+    fn list(&mut self) -> io::Result<Vec<ChunkPos>>;
+
+    // everything the writer had queued up at once, backends that can should keep it all or nothing
+    fn save_all(&mut self, chunks: &[(ChunkPos, &Compound)]) -> io::Result<()> {
+        chunks.iter().try_for_each(|(pos, nbt)| self.save(*pos, nbt))
+    }
+}
+
+#[derive(Default)]
+pub struct MemoryStorage {
+    chunks: HashMap<ChunkPos, Compound>
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl ChunkStorage for MemoryStorage {
+    fn load(&mut self, pos: ChunkPos) -> io::Result<Option<Compound>> {
+        Ok(self.chunks.get(&pos).cloned())
+    }
+
+    fn save(&mut self, pos: ChunkPos, nbt: &Compound) -> io::Result<()> {
+        self.chunks.insert(pos, nbt.clone());
+        Ok(())
+    }
+
+    fn delete(&mut self, pos: ChunkPos) -> io::Result<()> {
+        self.chunks.remove(&pos);
+        Ok(())
+    }
+
+    fn list(&mut self) -> io::Result<Vec<ChunkPos>> {
+        Ok(self.chunks.keys().copied().collect())
+    }
+}
+
+fn sqlite_error(error: rusqlite::Error) -> io::Error {
+    io::Error::other(error)
+}
+
+// zlib compressed chunks in one table, several dimensions can share the same database
+pub struct SqliteStorage {
+    connection: Connection,
+    dimension: String
+}
+
+impl SqliteStorage {
+    pub fn open(path: impl AsRef<Path>, dimension: &str) -> io::Result<Self> {
+        Self::new(Connection::open(path).map_err(sqlite_error)?, dimension)
+    }
+
+    pub fn open_in_memory(dimension: &str) -> io::Result<Self> {
+        Self::new(Connection::open_in_memory().map_err(sqlite_error)?, dimension)
+    }
+
+    fn new(connection: Connection, dimension: &str) -> io::Result<Self> {
+        connection.execute_batch("
+            PRAGMA journal_mode = WAL;
+            CREATE TABLE IF NOT EXISTS chunks (
+                dimension TEXT NOT NULL,
+                x INTEGER NOT NULL,
+                z INTEGER NOT NULL,
+                data BLOB NOT NULL,
+                PRIMARY KEY (dimension, x, z)
+            );
+        ").map_err(sqlite_error)?;
+
+        Ok(Self { connection, dimension: dimension.to_string() })
+    }
+
+    fn insert(connection: &Connection, dimension: &str, pos: ChunkPos, nbt: &Compound) -> io::Result<()> {
+        let mut encoder = ZlibEncoder::new(vec![], Compression::default());
+        nbt.write(&mut encoder, "")?;
+        let data = encoder.finish()?;
+
+        connection.prepare_cached("INSERT OR REPLACE INTO chunks (dimension, x, z, data) VALUES (?1, ?2, ?3, ?4)")
+            .and_then(|mut statement| statement.execute(params![dimension, pos.x, pos.z, data]))
+            .map_err(sqlite_error)?;
+
+        Ok(())
+    }
+}
+
+impl ChunkStorage for SqliteStorage {
+    fn load(&mut self, pos: ChunkPos) -> io::Result<Option<Compound>> {
+        let data: Option<Vec<u8>> = self.connection.prepare_cached("SELECT data FROM chunks WHERE dimension = ?1 AND x = ?2 AND z = ?3")
+            .and_then(|mut statement| statement.query_row(params![self.dimension, pos.x, pos.z], |row| row.get(0)).optional())
+            .map_err(sqlite_error)?;
+
+        match data {
+            Some(data) => Ok(Some(Compound::read(&mut ZlibDecoder::new(data.as_slice()))?.1)),
+            None => Ok(None)
+        }
+    }
+
+    fn save(&mut self, pos: ChunkPos, nbt: &Compound) -> io::Result<()> {
+        Self::insert(&self.connection, &self.dimension, pos, nbt)
+    }
+
+    fn delete(&mut self, pos: ChunkPos) -> io::Result<()> {
+        self.connection.execute("DELETE FROM chunks WHERE dimension = ?1 AND x = ?2 AND z = ?3", params![self.dimension, pos.x, pos.z])
+            .map_err(sqlite_error)?;
+
+        Ok(())
+    }
+
+    fn list(&mut self) -> io::Result<Vec<ChunkPos>> {
+        let mut statement = self.connection.prepare_cached("SELECT x, z FROM chunks WHERE dimension = ?1").map_err(sqlite_error)?;
+
+        statement.query_map(params![self.dimension], |row| Ok(ChunkPos::new(row.get(0)?, row.get(1)?)))
+            .and_then(|rows| rows.collect())
+            .map_err(sqlite_error)
+    }
+
+    fn save_all(&mut self, chunks: &[(ChunkPos, &Compound)]) -> io::Result<()> {
+        let transaction = self.connection.transaction().map_err(sqlite_error)?;
+
+        for (pos, nbt) in chunks {
+            Self::insert(&transaction, &self.dimension, *pos, nbt)?;
+        }
+
+        // dropping the transaction on an error rolls it back
+        transaction.commit().map_err(sqlite_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::nbt::Tag;
+
+    use super::*;
+
+    fn nbt(value: i32) -> Compound {
+        Compound::new().with("value", Tag::Int(value))
+    }
+
+    fn round_trip(storage: &mut impl ChunkStorage) {
+        let (first, second) = (ChunkPos::new(0, 0), ChunkPos::new(-5, 31));
+
+        assert_eq!(storage.load(first).unwrap(), None);
+
+        storage.save(first, &nbt(1)).unwrap();
+        storage.save(second, &nbt(2)).unwrap();
+        storage.save(first, &nbt(3)).unwrap();
+
+        assert_eq!(storage.load(first).unwrap(), Some(nbt(3)));
+        assert_eq!(storage.load(second).unwrap(), Some(nbt(2)));
+
+        let mut chunks = storage.list().unwrap();
+        chunks.sort_by_key(|pos| (pos.x, pos.z));
+        assert_eq!(chunks, [second, first]);
+
+        storage.delete(first).unwrap();
+        storage.delete(first).unwrap();
+
+        assert_eq!(storage.load(first).unwrap(), None);
+        assert_eq!(storage.list().unwrap(), [second]);
+
+        storage.save_all(&[(first, &nbt(4)), (second, &nbt(5))]).unwrap();
+
+        assert_eq!(storage.load(first).unwrap(), Some(nbt(4)));
+        assert_eq!(storage.load(second).unwrap(), Some(nbt(5)));
+    }
+
+    #[test]
+    fn memory_round_trip() {
+        round_trip(&mut MemoryStorage::new());
+    }
+
+    #[test]
+    fn sqlite_round_trip() {
+        round_trip(&mut SqliteStorage::open_in_memory("minecraft:overworld").unwrap());
+    }
+
+    #[test]
+    fn failed_save_all_writes_nothing() {
+        let mut storage = SqliteStorage::open_in_memory("minecraft:overworld").unwrap();
+        storage.save(ChunkPos::new(0, 0), &nbt(1)).unwrap();
+
+        // the third chunk of the batch is refused after the first two went in
+        storage.connection.execute_batch("
+            CREATE TRIGGER refuse BEFORE INSERT ON chunks WHEN NEW.x = 2
+            BEGIN SELECT RAISE(ABORT, 'refused'); END;
+        ").unwrap();
+
+        let batch = [(ChunkPos::new(0, 0), &nbt(2)), (ChunkPos::new(1, 0), &nbt(3)), (ChunkPos::new(2, 0), &nbt(4))];
+
+        assert!(storage.save_all(&batch).is_err());
+        assert_eq!(storage.load(ChunkPos::new(0, 0)).unwrap(), Some(nbt(1)));
+        assert_eq!(storage.load(ChunkPos::new(1, 0)).unwrap(), None);
+        assert_eq!(storage.list().unwrap(), [ChunkPos::new(0, 0)]);
+    }
+}
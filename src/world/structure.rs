@@ -0,0 +1,357 @@
+use std::{fs::{self, File}, io::{self, BufReader}, path::{Path, PathBuf}};
+
+use crate::{block::{BlockState, Mirror, Rotation}, nbt::{Compound, Tag}, types::Pos};
+
+use super::{chunk::BlockEntity, generator::ChunkRegion};
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PlacementSettings {
+    pub rotation: Rotation,
+    pub mirror: Mirror,
+    // which palette to use for templates that have several, like shipwrecks
+    pub palette: usize,
+    pub ignore_air: bool
+}
+
+pub struct StructureBlock {
+    pub pos: [i32; 3],
+    pub state: usize,
+    pub nbt: Option<Compound>
+}
+
+pub struct StructureEntity {
+    pub pos: [f64; 3],
+    pub block_pos: [i32; 3],
+    pub nbt: Compound
+}
+
+// the .nbt files written by structure blocks and found under data/minecraft/structures
+pub struct StructureTemplate {
+    pub data_version: i32,
+    pub size: [i32; 3],
+    // none for the blocks that are left alone when placing, structure voids and blocks missing from the block registry
+    pub palettes: Vec<Vec<Option<BlockState>>>,
+    pub blocks: Vec<StructureBlock>,
+    pub entities: Vec<StructureEntity>
+}
+
+fn invalid(message: &str) -> String {
+    format!("invalid structure: {}", message)
+}
+
+fn int_triple(tag: Option<&Tag>) -> Option<[i32; 3]> {
+    match tag?.as_list()? {
+        [x, y, z] => Some([x.as_i32()?, y.as_i32()?, z.as_i32()?]),
+        _ => None
+    }
+}
+
+fn double_triple(tag: Option<&Tag>) -> Option<[f64; 3]> {
+    match tag?.as_list()? {
+        [x, y, z] => Some([x.as_f64()?, y.as_f64()?, z.as_f64()?]),
+        _ => None
+    }
+}
+
+// where a world keeps the templates its structure blocks save, generated/<namespace>/structures/<path>.nbt like vanilla
+pub fn template_path(directory: &Path, name: &str) -> io::Result<PathBuf> {
+    let (namespace, path) = name.split_once(':').unwrap_or(("minecraft", name));
+
+    // the name may not reach out of the world directory
+    if [namespace].into_iter().chain(path.split('/')).any(|part| matches!(part, "" | "." | "..")) {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("invalid template name '{}'", name)))
+    }
+
+    Ok(directory.join("generated").join(namespace).join("structures").join(format!("{}.nbt", path)))
+}
+
+// the names of every template of the world, sorted
+pub fn template_names(directory: &Path) -> Vec<String> {
+    let mut names = vec![];
+
+    for namespace in fs::read_dir(directory.join("generated")).into_iter().flatten().flatten() {
+        let prefix = format!("{}:", namespace.file_name().to_string_lossy());
+        collect_templates(&namespace.path().join("structures"), &prefix, &mut names);
+    }
+
+    names.sort();
+    names
+}
+
+fn collect_templates(directory: &Path, prefix: &str, names: &mut Vec<String>) {
+    for entry in fs::read_dir(directory).into_iter().flatten().flatten() {
+        let name = entry.file_name().to_string_lossy().into_owned();
+
+        if entry.path().is_dir() {
+            collect_templates(&entry.path(), &format!("{}{}/", prefix, name), names);
+        } else if let Some(name) = name.strip_suffix(".nbt") {
+            names.push(format!("{}{}", prefix, name));
+        }
+    }
+}
+
+// FIXME: blocks missing from the block registry are skipped like structure voids, the terrain stays where they would be
+fn read_palette(entries: &[Tag]) -> Result<Vec<Option<BlockState>>, String> {
+    entries.iter()
+        .map(|entry| {
+            let entry = entry.as_compound().ok_or_else(|| invalid("palette entry is not a compound"))?;
+
+            Ok(match entry.get_str("Name") {
+                Some("minecraft:structure_void") => None,
+                _ => BlockState::from_nbt(entry)
+            })
+        })
+        .collect()
+}
+
+// vanilla mirrors first and then rotates around the origin corner of the template
+pub fn transform(pos: [i32; 3], mirror: Mirror, rotation: Rotation) -> [i32; 3] {
+    let [mut x, y, mut z] = pos;
+
+    match mirror {
+        Mirror::None => (),
+        Mirror::LeftRight => z = -z,
+        Mirror::FrontBack => x = -x
+    }
+
+    match rotation {
+        Rotation::None => [x, y, z],
+        Rotation::Clockwise90 => [-z, y, x],
+        Rotation::Clockwise180 => [-x, y, -z],
+        Rotation::Counterclockwise90 => [z, y, -x]
+    }
+}
+
+// positions within a block mirror and turn around the block center instead
+pub fn transform_exact(pos: [f64; 3], mirror: Mirror, rotation: Rotation) -> [f64; 3] {
+    let [mut x, y, mut z] = pos;
+
+    match mirror {
+        Mirror::None => (),
+        Mirror::LeftRight => z = 1.0 - z,
+        Mirror::FrontBack => x = 1.0 - x
+    }
+
+    match rotation {
+        Rotation::None => [x, y, z],
+        Rotation::Clockwise90 => [1.0 - z, y, x],
+        Rotation::Clockwise180 => [1.0 - x, y, 1.0 - z],
+        Rotation::Counterclockwise90 => [z, y, 1.0 - x]
+    }
+}
+
+impl StructureTemplate {
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let (_, root) = Compound::read_gzip(BufReader::new(File::open(path)?))?;
+        Self::from_nbt(&root).map_err(|message| io::Error::new(io::ErrorKind::InvalidData, message))
+    }
+
+    pub fn from_nbt(root: &Compound) -> Result<Self, String> {
+        let size = int_triple(root.get("size")).ok_or_else(|| invalid("missing size"))?;
+
+        let palettes = match (root.get_list("palette"), root.get_list("palettes")) {
+            (Some(palette), _) => vec![read_palette(palette)?],
+            (None, Some(palettes)) => palettes.iter()
+                .map(|palette| read_palette(palette.as_list().ok_or_else(|| invalid("palette is not a list"))?))
+                .collect::<Result<_, _>>()?,
+            (None, None) => return Err(invalid("missing palette"))
+        };
+
+        // placing picks one of them, a template without any has nothing to place its blocks with
+        if palettes.is_empty() {
+            return Err(invalid("no palettes"))
+        }
+
+        let blocks = root.get_list("blocks").unwrap_or_default().iter()
+            .map(|block| -> Result<_, String> {
+                let block = block.as_compound().ok_or_else(|| invalid("block is not a compound"))?;
+                let state = block.get_i32("state").ok_or_else(|| invalid("block without a state"))?;
+
+                if state < 0 || palettes.iter().any(|palette| state as usize >= palette.len()) {
+                    return Err(invalid("block state outside of the palette"))
+                }
+
+                Ok(StructureBlock {
+                    pos: int_triple(block.get("pos")).ok_or_else(|| invalid("block without a position"))?,
+                    state: state as usize,
+                    nbt: block.get_compound("nbt").cloned()
+                })
+            })
+            .collect::<Result<_, _>>()?;
+
+        let entities = root.get_list("entities").unwrap_or_default().iter()
+            .map(|entity| -> Result<_, String> {
+                let entity = entity.as_compound().ok_or_else(|| invalid("entity is not a compound"))?;
+
+                Ok(StructureEntity {
+                    pos: double_triple(entity.get("pos")).ok_or_else(|| invalid("entity without a position"))?,
+                    block_pos: int_triple(entity.get("blockPos")).ok_or_else(|| invalid("entity without a block position"))?,
+                    nbt: entity.get_compound("nbt").cloned().unwrap_or_default()
+                })
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            data_version: root.get_i32("DataVersion").unwrap_or(0),
+            size,
+            palettes,
+            blocks,
+            entities
+        })
+    }
+
+    // turned a quarter the template swaps its width and depth
+    pub fn size(&self, rotation: Rotation) -> [i32; 3] {
+        let [x, y, z] = self.size;
+
+        match rotation.quarter_turns() % 2 {
+            0 => [x, y, z],
+            _ => [z, y, x]
+        }
+    }
+
+    // every block in world coordinates with its transformed state and block entity data
+    pub fn blocks(&self, origin: Pos, settings: PlacementSettings) -> impl Iterator<Item = (Pos, BlockState, Option<&Compound>)> + '_ {
+        let palette = &self.palettes[settings.palette.min(self.palettes.len() - 1)];
+
+        self.blocks.iter()
+            .filter_map(move |block| {
+                let [x, y, z] = transform(block.pos, settings.mirror, settings.rotation);
+                let state = palette[block.state]?.mirror(settings.mirror).rotate(settings.rotation);
+                let pos = Pos { x: origin.x + x, y: origin.y + y as i16, z: origin.z + z };
+
+                Some((pos, state, block.nbt.as_ref()))
+            })
+            .filter(move |(_, state, _)| !(settings.ignore_air && state.is_air()))
+    }
+
+    pub fn entities(&self, origin: Pos, settings: PlacementSettings) -> impl Iterator<Item = ([f64; 3], &Compound)> + '_ {
+        self.entities.iter().map(move |entity| {
+            let [x, y, z] = transform_exact(entity.pos, settings.mirror, settings.rotation);
+            ([origin.x as f64 + x, origin.y as f64 + y, origin.z as f64 + z], &entity.nbt)
+        })
+    }
+
+    // for world generation, only the part inside the region's chunk is placed
+    // FIXME: entities are left out until chunks can hold them
+    pub fn place_in(&self, region: &mut ChunkRegion, origin: Pos, settings: PlacementSettings) {
+        for (pos, state, nbt) in self.blocks(origin, settings) {
+            let y = pos.y as i32;
+            region.set(pos.x, y, pos.z, state);
+
+            if let Some(block_entity) = nbt.and_then(BlockEntity::from_nbt) {
+                region.set_block_entity(pos.x, y, pos.z, block_entity);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEMPLATE: &[u8] = include_bytes!("testdata/stairs_and_door.nbt");
+
+    const MIRRORS: [Mirror; 3] = [Mirror::None, Mirror::LeftRight, Mirror::FrontBack];
+    const ROTATIONS: [Rotation; 4] = [Rotation::None, Rotation::Clockwise90, Rotation::Clockwise180, Rotation::Counterclockwise90];
+
+    fn template() -> StructureTemplate {
+        let (_, root) = Compound::read_gzip(TEMPLATE).unwrap();
+        StructureTemplate::from_nbt(&root).unwrap()
+    }
+
+    #[test]
+    fn transforms_block_positions() {
+        // by mirror, then by rotation
+        let expected = [
+            [[1, 2, 3], [-3, 2, 1], [-1, 2, -3], [3, 2, -1]],
+            [[1, 2, -3], [3, 2, 1], [-1, 2, 3], [-3, 2, -1]],
+            [[-1, 2, 3], [-3, 2, -1], [1, 2, -3], [3, 2, 1]]
+        ];
+
+        for (mirror, expected) in MIRRORS.into_iter().zip(expected) {
+            for (rotation, expected) in ROTATIONS.into_iter().zip(expected) {
+                assert_eq!(transform([1, 2, 3], mirror, rotation), expected, "{:?} {:?}", mirror, rotation);
+            }
+        }
+    }
+
+    #[test]
+    fn transforms_positions_within_blocks() {
+        let expected = [
+            [[0.125, 0.5, 0.75], [0.25, 0.5, 0.125], [0.875, 0.5, 0.25], [0.75, 0.5, 0.875]],
+            [[0.125, 0.5, 0.25], [0.75, 0.5, 0.125], [0.875, 0.5, 0.75], [0.25, 0.5, 0.875]],
+            [[0.875, 0.5, 0.75], [0.25, 0.5, 0.875], [0.125, 0.5, 0.25], [0.75, 0.5, 0.125]]
+        ];
+
+        for (mirror, expected) in MIRRORS.into_iter().zip(expected) {
+            for (rotation, expected) in ROTATIONS.into_iter().zip(expected) {
+                assert_eq!(transform_exact([0.125, 0.5, 0.75], mirror, rotation), expected, "{:?} {:?}", mirror, rotation);
+            }
+        }
+    }
+
+    #[test]
+    fn reads_structure_block_files() {
+        let template = template();
+        let stairs = BlockState::from_properties("minecraft:oak_stairs", &[("facing", "east"), ("half", "bottom"), ("shape", "straight")]);
+
+        assert_eq!(template.data_version, 3337);
+        assert_eq!(template.size, [3, 2, 1]);
+        assert_eq!(template.palettes.len(), 1);
+        assert_eq!(template.palettes[0].len(), 6);
+        assert_eq!(template.palettes[0][0], stairs);
+        assert_eq!(template.palettes[0][1], None);
+        assert_eq!(template.blocks.len(), 6);
+        assert_eq!(template.blocks[1].nbt.as_ref().and_then(|nbt| nbt.get_str("id")), Some("minecraft:chest"));
+        assert_eq!(template.entities.len(), 1);
+        assert_eq!(template.entities[0].pos, [0.5, 1.0, 0.5]);
+        assert_eq!(template.entities[0].block_pos, [0, 1, 0]);
+
+        // the structure void is left out, and the air too when it is ignored
+        let origin = Pos { x: 10, y: 64, z: -5 };
+        assert_eq!(template.blocks(origin, PlacementSettings::default()).count(), 5);
+        assert_eq!(template.blocks(origin, PlacementSettings { ignore_air: true, ..Default::default() }).count(), 4);
+    }
+
+    #[test]
+    fn turns_the_blocks_of_a_template() {
+        let template = template();
+        let settings = PlacementSettings { rotation: Rotation::Clockwise90, ..Default::default() };
+        let blocks: Vec<_> = template.blocks(Pos { x: 10, y: 64, z: -5 }, settings).collect();
+
+        assert_eq!(template.size(settings.rotation), [1, 2, 3]);
+
+        let (pos, door, _) = blocks.iter().find(|(_, state, _)| state.name() == "minecraft:oak_door").unwrap();
+        assert_eq!(*pos, Pos { x: 10, y: 64, z: -3 });
+        assert!(door.properties().contains(&("facing", "east")));
+
+        let (_, chest, nbt) = blocks.iter().find(|(_, state, _)| state.name() == "minecraft:chest").unwrap();
+        assert!(chest.properties().contains(&("facing", "east")));
+        assert!(nbt.is_some());
+
+        let (pos, ..) = template.entities(Pos { x: 10, y: 64, z: -5 }, settings).next().unwrap();
+        assert_eq!(pos, [10.5, 65.0, -4.5]);
+    }
+
+    #[test]
+    fn keeps_template_names_in_the_world() {
+        let directory = Path::new("world");
+
+        assert_eq!(template_path(directory, "igloo/top").unwrap(), directory.join("generated/minecraft/structures/igloo/top.nbt"));
+        assert_eq!(template_path(directory, "custom:house").unwrap(), directory.join("generated/custom/structures/house.nbt"));
+
+        for name in ["../level", "minecraft:../../level", ":house", "custom:", "custom:a//b", "custom:./house"] {
+            assert_eq!(template_path(directory, name).unwrap_err().kind(), io::ErrorKind::InvalidInput, "{}", name);
+        }
+    }
+
+    #[test]
+    fn refuses_templates_without_palettes() {
+        let root = Compound::new()
+            .with("size", Tag::List(vec![Tag::Int(1), Tag::Int(1), Tag::Int(1)]))
+            .with("palettes", Tag::List(vec![]));
+
+        assert!(StructureTemplate::from_nbt(&root).is_err());
+    }
+}
@@ -0,0 +1,267 @@
+use std::collections::{hash_map::Entry, HashMap};
+
+use super::chunk::ChunkPos;
+
+// ticket levels as vanilla counts them, lower keeps a chunk more alive
+pub const ENTITY_TICKING_LEVEL: i32 = 31;
+pub const BLOCK_TICKING_LEVEL: i32 = 32;
+pub const BORDER_LEVEL: i32 = 33;
+
+// vanilla keeps 11 chunks around the spawn ticking, this keeps 2
+pub const SPAWN_TICKET_LEVEL: i32 = ENTITY_TICKING_LEVEL - 2;
+pub const PORTAL_TICKET_LEVEL: i32 = 30;
+pub const PORTAL_TICKET_LIFETIME: u64 = 300;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LoadLevel {
+    Inaccessible,
+    // loaded and sent to clients, but nothing in it runs
+    Border,
+    BlockTicking,
+    EntityTicking
+}
+
+impl LoadLevel {
+    pub fn of(level: i32) -> Self {
+        match level {
+            ..=ENTITY_TICKING_LEVEL => Self::EntityTicking,
+            BLOCK_TICKING_LEVEL => Self::BlockTicking,
+            BORDER_LEVEL => Self::Border,
+            _ => Self::Inaccessible
+        }
+    }
+
+    pub fn is_loaded(self) -> bool {
+        self != Self::Inaccessible
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TicketKind {
+    Player(i32),
+    Spawn,
+    Forced,
+    Plugin(String),
+    Portal,
+    // a chunk the world waits for before it can finish something, like placing a structure
+    Pending
+}
+
+#[derive(Debug, Clone)]
+pub struct Ticket {
+    pub kind: TicketKind,
+    pub level: i32,
+    // the ticket never raises a chunk above this level, however close it is
+    pub floor: i32,
+    // in ticks, None for tickets that stay until they are removed
+    pub lifetime: Option<u64>
+}
+
+impl Ticket {
+    pub fn new(kind: TicketKind, level: i32) -> Self {
+        Self { kind, level, floor: 0, lifetime: None }
+    }
+
+    pub fn spawn() -> Self {
+        Self::new(TicketKind::Spawn, SPAWN_TICKET_LEVEL)
+    }
+
+    pub fn forced() -> Self {
+        Self::new(TicketKind::Forced, ENTITY_TICKING_LEVEL)
+    }
+
+    pub fn plugin(name: &str) -> Self {
+        Self::new(TicketKind::Plugin(name.to_string()), ENTITY_TICKING_LEVEL)
+    }
+
+    pub fn portal() -> Self {
+        Self { lifetime: Some(PORTAL_TICKET_LIFETIME), ..Self::new(TicketKind::Portal, PORTAL_TICKET_LEVEL) }
+    }
+
+    pub fn pending() -> Self {
+        Self::new(TicketKind::Pending, BORDER_LEVEL)
+    }
+
+    // what the player simulates
+    pub fn player(entity_id: i32, simulation_distance: i32) -> Self {
+        Self::new(TicketKind::Player(entity_id), ENTITY_TICKING_LEVEL - simulation_distance)
+    }
+
+    // what the player can see, kept loaded but never ticked
+    pub fn player_view(entity_id: i32, view_distance: i32) -> Self {
+        Self { floor: BORDER_LEVEL, ..Self::new(TicketKind::Player(entity_id), BORDER_LEVEL - view_distance) }
+    }
+
+    fn level_at(&self, distance: i32) -> i32 {
+        (self.level + distance).max(self.floor)
+    }
+}
+
+struct PlacedTicket {
+    ticket: Ticket,
+    expires_at: Option<u64>
+}
+
+// the tickets of every chunk and the load levels they spread to their neighbours
+#[derive(Default)]
+pub struct TicketManager {
+    tickets: HashMap<ChunkPos, Vec<PlacedTicket>>,
+    // only the chunks that are not inaccessible
+    levels: HashMap<ChunkPos, i32>,
+    time: u64,
+    changed: bool
+}
+
+impl TicketManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, pos: ChunkPos, ticket: Ticket) {
+        let expires_at = ticket.lifetime.map(|lifetime| self.time + lifetime);
+        self.tickets.entry(pos).or_default().push(PlacedTicket { ticket, expires_at });
+        self.changed = true;
+    }
+
+    // removes every ticket of that kind from the chunk
+    pub fn remove(&mut self, pos: ChunkPos, kind: &TicketKind) {
+        if let Entry::Occupied(mut entry) = self.tickets.entry(pos) {
+            entry.get_mut().retain(|placed| placed.ticket.kind != *kind);
+
+            if entry.get().is_empty() {
+                entry.remove();
+            }
+
+            self.changed = true;
+        }
+    }
+
+    pub fn tickets(&self, pos: ChunkPos) -> impl Iterator<Item = &Ticket> {
+        self.tickets.get(&pos).into_iter().flatten().map(|placed| &placed.ticket)
+    }
+
+    pub fn level(&self, pos: ChunkPos) -> LoadLevel {
+        self.levels.get(&pos).map_or(LoadLevel::Inaccessible, |level| LoadLevel::of(*level))
+    }
+
+    // the accessible chunks
+    pub fn loaded(&self) -> impl Iterator<Item = (ChunkPos, LoadLevel)> + '_ {
+        self.levels.iter().map(|(pos, level)| (*pos, LoadLevel::of(*level)))
+    }
+
+    // drops expired tickets and spreads the levels again when anything changed, true when it did
+    pub fn tick(&mut self) -> bool {
+        self.time += 1;
+
+        let time = self.time;
+
+        self.tickets.retain(|_, tickets| {
+            let count = tickets.len();
+            tickets.retain(|placed| placed.expires_at.is_none_or(|expires_at| expires_at > time));
+            self.changed |= tickets.len() != count;
+            !tickets.is_empty()
+        });
+
+        if !std::mem::take(&mut self.changed) {
+            return false
+        }
+
+        self.levels.clear();
+
+        for (center, tickets) in &self.tickets {
+            for placed in tickets {
+                let ticket = &placed.ticket;
+                let reach = BORDER_LEVEL - ticket.level;
+
+                for x in -reach..=reach {
+                    for z in -reach..=reach {
+                        let level = ticket.level_at(x.abs().max(z.abs()));
+                        let pos = ChunkPos::new(center.x + x, center.z + z);
+
+                        self.levels.entry(pos)
+                            .and_modify(|current| *current = (*current).min(level))
+                            .or_insert(level);
+                    }
+                }
+            }
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ring(distance: i32) -> ChunkPos {
+        ChunkPos::new(10 + distance, -4)
+    }
+
+    #[test]
+    fn spreads_player_tickets_over_the_simulation_distance() {
+        let mut tickets = TicketManager::new();
+        tickets.add(ring(0), Ticket::player(1, 2));
+
+        assert!(tickets.tick());
+        assert!(!tickets.tick());
+
+        for distance in 0..=2 {
+            assert_eq!(tickets.level(ring(distance)), LoadLevel::EntityTicking);
+        }
+
+        assert_eq!(tickets.level(ring(3)), LoadLevel::BlockTicking);
+        assert_eq!(tickets.level(ring(4)), LoadLevel::Border);
+        assert_eq!(tickets.level(ring(5)), LoadLevel::Inaccessible);
+        assert_eq!(tickets.level(ChunkPos::new(14, 0)), LoadLevel::Border);
+        assert_eq!(tickets.loaded().count(), 9 * 9);
+    }
+
+    #[test]
+    fn keeps_the_view_loaded_without_ticking_it() {
+        let mut tickets = TicketManager::new();
+        tickets.add(ring(0), Ticket::player_view(1, 6));
+        tickets.tick();
+
+        // the floor keeps even the center from ticking
+        assert_eq!(tickets.level(ring(0)), LoadLevel::Border);
+        assert_eq!(tickets.level(ring(6)), LoadLevel::Border);
+        assert_eq!(tickets.level(ring(7)), LoadLevel::Inaccessible);
+
+        tickets.add(ring(0), Ticket::player(1, 2));
+        tickets.tick();
+
+        assert_eq!(tickets.level(ring(2)), LoadLevel::EntityTicking);
+        assert_eq!(tickets.level(ring(3)), LoadLevel::BlockTicking);
+        assert_eq!(tickets.level(ring(5)), LoadLevel::Border);
+        assert_eq!(tickets.level(ring(7)), LoadLevel::Inaccessible);
+
+        tickets.remove(ring(0), &TicketKind::Player(1));
+        tickets.tick();
+
+        assert_eq!(tickets.level(ring(0)), LoadLevel::Inaccessible);
+        assert_eq!(tickets.loaded().count(), 0);
+    }
+
+    #[test]
+    fn lets_portal_tickets_expire() {
+        let mut tickets = TicketManager::new();
+        tickets.add(ring(0), Ticket::portal());
+        tickets.add(ring(20), Ticket::forced());
+
+        assert!(tickets.tick());
+        assert_eq!(tickets.level(ring(0)), LoadLevel::EntityTicking);
+        assert_eq!(tickets.level(ring(1)), LoadLevel::EntityTicking);
+        assert_eq!(tickets.level(ring(2)), LoadLevel::BlockTicking);
+
+        for _ in 1..PORTAL_TICKET_LIFETIME - 1 {
+            assert!(!tickets.tick());
+        }
+
+        assert_eq!(tickets.tickets(ring(0)).count(), 1);
+        assert!(tickets.tick());
+        assert_eq!(tickets.tickets(ring(0)).count(), 0);
+        assert_eq!(tickets.level(ring(0)), LoadLevel::Inaccessible);
+        assert_eq!(tickets.level(ring(20)), LoadLevel::EntityTicking);
+    }
+}
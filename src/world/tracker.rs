@@ -0,0 +1,327 @@
+use std::collections::{HashMap, HashSet};
+
+use bytes::Bytes;
+
+use crate::{connection::{ConnectionWriter, Play}, packets::play::clientbound::{self, RemoveEntitiesData, SetEntityVelocityData, SetHeadRotationData, SpawnPlayerData, TeleportEntityData, UpdateEntityPositionAndRotationData, UpdateEntityPositionData, UpdateEntityRotationData}, player::Player};
+
+// players are tracked as far as the viewer can see, but never further than this many chunks
+pub const PLAYER_TRACKING_RANGE: i32 = 32;
+// relative moves lose a little to rounding every time, so the exact position goes out every so often
+const TELEPORT_INTERVAL: u64 = 400;
+
+// positions are sent as 1/4096 of a block
+fn fixed(coordinate: f64) -> i64 {
+    (coordinate * 4096.0).round() as i64
+}
+
+// 256 steps for a full turn
+pub fn angle(degrees: f32) -> u8 {
+    (degrees * 256.0 / 360.0).floor() as i32 as u8
+}
+
+// 1/8000 of a block per tick, clients cannot take more than 3.9 in any direction
+fn velocity(blocks_per_tick: f64) -> i16 {
+    (blocks_per_tick.clamp(-3.9, 3.9) * 8000.0) as i16
+}
+
+// what the viewers of an entity were told last
+struct Tracked {
+    position: (i64, i64, i64),
+    yaw: u8,
+    pitch: u8,
+    head_yaw: u8,
+    viewers: HashSet<i32>,
+    ticks: u64
+}
+
+impl Tracked {
+    fn new(player: &Player) -> Self {
+        Self {
+            position: (fixed(player.x), fixed(player.y), fixed(player.z)),
+            yaw: angle(player.yaw),
+            pitch: angle(player.pitch),
+            head_yaw: angle(player.yaw),
+            viewers: HashSet::new(),
+            ticks: 0
+        }
+    }
+
+    // everything a new viewer needs to see the entity where it is now
+    // the velocity is left out, set_velocity only gives a push and the entity has moved on since
+    fn spawn_packets(&self, player: &Player) -> Vec<clientbound::Packet> {
+        vec![
+            clientbound::Packet::SpawnPlayer(SpawnPlayerData {
+                entity_id: player.entity_id.into(),
+                uuid: player.uuid,
+                x: player.x,
+                y: player.y,
+                z: player.z,
+                yaw: self.yaw,
+                pitch: self.pitch
+            }),
+            clientbound::Packet::SetHeadRotation(SetHeadRotationData {
+                entity_id: player.entity_id.into(),
+                head_yaw: self.head_yaw
+            })
+        ]
+    }
+
+    // the smallest packets that bring the viewers up to date, nothing when the entity did not move
+    fn movement_packets(&mut self, entity_id: i32, (x, y, z): (f64, f64, f64), (yaw, pitch): (f32, f32), on_ground: bool) -> Vec<clientbound::Packet> {
+        let position = (fixed(x), fixed(y), fixed(z));
+        let (yaw, pitch, head_yaw) = (angle(yaw), angle(pitch), angle(yaw));
+
+        let delta = (position.0 - self.position.0, position.1 - self.position.1, position.2 - self.position.2);
+        let moved = delta != (0, 0, 0);
+        let rotated = (yaw, pitch) != (self.yaw, self.pitch);
+        let fits = [delta.0, delta.1, delta.2].iter().all(|delta| i16::try_from(*delta).is_ok());
+
+        self.ticks += 1;
+
+        let mut packets = vec![];
+
+        if !fits || self.ticks.is_multiple_of(TELEPORT_INTERVAL) {
+            packets.push(clientbound::Packet::TeleportEntity(TeleportEntityData {
+                entity_id: entity_id.into(),
+                x,
+                y,
+                z,
+                yaw,
+                pitch,
+                on_ground
+            }));
+        } else if moved && rotated {
+            packets.push(clientbound::Packet::UpdateEntityPositionAndRotation(UpdateEntityPositionAndRotationData {
+                entity_id: entity_id.into(),
+                delta_x: delta.0 as i16,
+                delta_y: delta.1 as i16,
+                delta_z: delta.2 as i16,
+                yaw,
+                pitch,
+                on_ground
+            }));
+        } else if moved {
+            packets.push(clientbound::Packet::UpdateEntityPosition(UpdateEntityPositionData {
+                entity_id: entity_id.into(),
+                delta_x: delta.0 as i16,
+                delta_y: delta.1 as i16,
+                delta_z: delta.2 as i16,
+                on_ground
+            }));
+        } else if rotated {
+            packets.push(clientbound::Packet::UpdateEntityRotation(UpdateEntityRotationData {
+                entity_id: entity_id.into(),
+                yaw,
+                pitch,
+                on_ground
+            }));
+        }
+
+        if head_yaw != self.head_yaw {
+            packets.push(clientbound::Packet::SetHeadRotation(SetHeadRotationData {
+                entity_id: entity_id.into(),
+                head_yaw
+            }));
+        }
+
+        (self.position, self.yaw, self.pitch, self.head_yaw) = (position, yaw, pitch, head_yaw);
+        packets
+    }
+}
+
+// which players see which entities, and what they have been told about them
+#[derive(Default)]
+pub struct EntityTracker {
+    entities: HashMap<i32, Tracked>,
+    next_entity_id: i32
+}
+
+impl EntityTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // ids are never reused, a client could still be holding on to an entity that just went away
+    pub fn next_entity_id(&mut self) -> i32 {
+        let entity_id = self.next_entity_id;
+        self.next_entity_id += 1;
+        entity_id
+    }
+
+    pub fn track(&mut self, player: &Player) {
+        self.entities.insert(player.entity_id, Tracked::new(player));
+    }
+
+    // removes the entity from everyone who sees it, and forgets what it saw itself
+    pub fn untrack(&mut self, entity_id: i32, players: &HashMap<i32, Player>) {
+        if let Some(tracked) = self.entities.remove(&entity_id) {
+            for viewer in tracked.viewers.iter().filter_map(|viewer| players.get(viewer)) {
+                viewer.send(clientbound::Packet::RemoveEntities(RemoveEntitiesData {
+                    entity_ids: vec![entity_id.into()]
+                }));
+            }
+        }
+
+        self.forget_viewer(entity_id);
+    }
+
+    // for a client that dropped its entities on its own, everything in range is spawned for it again
+    pub fn forget_viewer(&mut self, viewer: i32) {
+        for tracked in self.entities.values_mut() {
+            tracked.viewers.remove(&viewer);
+        }
+    }
+
+    pub fn viewers(&self, entity_id: i32) -> impl Iterator<Item = i32> + '_ {
+        self.entities.get(&entity_id).into_iter().flat_map(|tracked| tracked.viewers.iter().copied())
+    }
+
+    // in blocks per tick, the entity itself is told as well so a player actually gets pushed
+    pub fn set_velocity(&mut self, entity_id: i32, players: &HashMap<i32, Player>, x: f64, y: f64, z: f64) {
+        let Some(tracked) = self.entities.get(&entity_id) else {
+            return
+        };
+
+        let packet = ConnectionWriter::<Play>::frame(&clientbound::Packet::SetEntityVelocity(SetEntityVelocityData {
+            entity_id: entity_id.into(),
+            velocity_x: velocity(x),
+            velocity_y: velocity(y),
+            velocity_z: velocity(z)
+        }));
+
+        for viewer in tracked.viewers.iter().chain([&entity_id]).filter_map(|viewer| players.get(viewer)) {
+            viewer.send_frame(packet.clone());
+        }
+    }
+
+    // spawns and removes entities as they come in and out of range, and sends the others their movement
+    pub fn update(&mut self, players: &HashMap<i32, Player>) {
+        for (entity_id, tracked) in &mut self.entities {
+            let Some(player) = players.get(entity_id) else {
+                continue
+            };
+
+            let chunk = player.chunk();
+
+            let in_range: HashSet<i32> = players.values()
+                .filter(|viewer| viewer.entity_id != *entity_id && viewer.view.is_sent(chunk))
+                .filter(|viewer| {
+                    let center = viewer.view.center();
+                    (chunk.x - center.x).abs().max((chunk.z - center.z).abs()) <= PLAYER_TRACKING_RANGE
+                })
+                .map(|viewer| viewer.entity_id)
+                .collect();
+
+            for viewer in tracked.viewers.difference(&in_range).filter_map(|viewer| players.get(viewer)) {
+                viewer.send(clientbound::Packet::RemoveEntities(RemoveEntitiesData {
+                    entity_ids: vec![(*entity_id).into()]
+                }));
+            }
+
+            tracked.viewers.retain(|viewer| in_range.contains(viewer));
+
+            // encoded once for all the viewers
+            let movement: Vec<Bytes> = tracked.movement_packets(player.entity_id, (player.x, player.y, player.z), (player.yaw, player.pitch), player.on_ground).iter().map(ConnectionWriter::<Play>::frame).collect();
+
+            for viewer in tracked.viewers.iter().filter_map(|viewer| players.get(viewer)) {
+                for frame in &movement {
+                    viewer.send_frame(frame.clone());
+                }
+            }
+
+            // new viewers get the current position in the spawn packet and need none of the movement
+            for viewer in in_range.iter().filter(|viewer| !tracked.viewers.contains(viewer)).filter_map(|viewer| players.get(viewer)) {
+                for packet in tracked.spawn_packets(player) {
+                    viewer.send(packet);
+                }
+            }
+
+            tracked.viewers = in_range;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tracked() -> Tracked {
+        Tracked { position: (0, fixed(64.0), 0), yaw: 0, pitch: 0, head_yaw: 0, viewers: HashSet::new(), ticks: 0 }
+    }
+
+    #[test]
+    fn rounds_positions_to_fixed_point() {
+        assert_eq!(fixed(1.0), 4096);
+        assert_eq!(fixed(-0.5), -2048);
+        assert_eq!(fixed(0.0001), 0);
+        assert_eq!(fixed(1.0 / 8192.0), 1);
+        assert_eq!(fixed(-1.0 / 8192.0), -1);
+        assert_eq!(fixed(30_000_000.0), 122_880_000_000);
+    }
+
+    #[test]
+    fn turns_degrees_into_steps() {
+        assert_eq!(angle(0.0), 0);
+        assert_eq!(angle(45.0), 32);
+        assert_eq!(angle(90.0), 64);
+        assert_eq!(angle(180.0), 128);
+        assert_eq!(angle(-90.0), 192);
+        assert_eq!(angle(359.0), 255);
+        assert_eq!(angle(360.0), 0);
+        assert_eq!(angle(765.0), 32);
+    }
+
+    #[test]
+    fn clamps_velocity() {
+        assert_eq!(velocity(0.0), 0);
+        assert_eq!(velocity(0.1), 800);
+        assert_eq!(velocity(-0.25), -2000);
+        assert_eq!(velocity(3.9), 31200);
+        assert_eq!(velocity(10.0), 31200);
+        assert_eq!(velocity(-10.0), -31200);
+    }
+
+    #[test]
+    fn sends_small_moves_relative() {
+        let mut tracked = tracked();
+
+        assert!(tracked.movement_packets(1, (0.0, 64.0, 0.0), (0.0, 0.0), true).is_empty());
+
+        let packets = tracked.movement_packets(1, (1.0, 64.0, -0.5), (0.0, 0.0), true);
+        assert!(matches!(&packets[..], [clientbound::Packet::UpdateEntityPosition(data)] if (data.delta_x, data.delta_y, data.delta_z) == (4096, 0, -2048)));
+
+        let packets = tracked.movement_packets(1, (1.0, 64.0, -0.5), (0.0, 45.0), true);
+        assert!(matches!(&packets[..], [clientbound::Packet::UpdateEntityRotation(data)] if (data.yaw, data.pitch) == (0, 32)));
+
+        // the head turns along with the body
+        let packets = tracked.movement_packets(1, (1.0, 65.0, -0.5), (90.0, 45.0), false);
+        assert!(matches!(&packets[..], [
+            clientbound::Packet::UpdateEntityPositionAndRotation(data),
+            clientbound::Packet::SetHeadRotation(head)
+        ] if (data.delta_y, data.yaw, data.on_ground, head.head_yaw) == (4096, 64, false, 64)));
+
+        // the largest move that still fits
+        let packets = tracked.movement_packets(1, (1.0 + 32767.0 / 4096.0, 65.0, -0.5), (90.0, 45.0), false);
+        assert!(matches!(&packets[..], [clientbound::Packet::UpdateEntityPosition(data)] if data.delta_x == i16::MAX));
+    }
+
+    #[test]
+    fn teleports_when_the_move_does_not_fit() {
+        let mut tracked = tracked();
+
+        let packets = tracked.movement_packets(1, (8.0, 64.0, 0.0), (0.0, 0.0), true);
+        assert!(matches!(&packets[..], [clientbound::Packet::TeleportEntity(data)] if (data.x, data.y, data.z) == (8.0, 64.0, 0.0)));
+
+        let packets = tracked.movement_packets(1, (8.0, -100.0, 0.0), (0.0, 0.0), true);
+        assert!(matches!(&packets[..], [clientbound::Packet::TeleportEntity(data)] if data.y == -100.0));
+        assert_eq!(tracked.position, (fixed(8.0), fixed(-100.0), 0));
+
+        // and every so often anyway, so rounding does not add up
+        for _ in 2..TELEPORT_INTERVAL - 1 {
+            assert!(tracked.movement_packets(1, (8.0, -100.0, 0.0), (0.0, 0.0), true).is_empty());
+        }
+
+        let packets = tracked.movement_packets(1, (8.0, -100.0, 0.0), (0.0, 0.0), true);
+        assert!(matches!(&packets[..], [clientbound::Packet::TeleportEntity(_)]));
+    }
+}
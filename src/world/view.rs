@@ -0,0 +1,177 @@
+use std::collections::{HashSet, VecDeque};
+
+use super::chunk::ChunkPos;
+
+// the square of chunks around the center in spiral order, nearest first
+pub fn spiral(center: ChunkPos, radius: i32) -> impl Iterator<Item = ChunkPos> {
+    std::iter::once(center).chain((1..=radius).flat_map(move |ring| {
+        let side = ring * 2;
+
+        (0..side * 4).map(move |step| {
+            let (x, z) = match step / side {
+                0 => (-ring + step % side, -ring),
+                1 => (ring, -ring + step % side),
+                2 => (ring - step % side, ring),
+                _ => (-ring, ring - step % side)
+            };
+
+            ChunkPos::new(center.x + x, center.z + z)
+        })
+    }))
+}
+
+// the client asks for a view distance in its client information, it gets at least 2 chunks and at most what the server allows
+pub fn radius(requested: Option<i8>, view_distance: i32) -> i32 {
+    requested.map_or(view_distance, |requested| (requested as i32).max(2).min(view_distance))
+}
+
+// the chunks one client has, and the ones it still needs in the order they should be sent
+pub struct ChunkView {
+    center: ChunkPos,
+    radius: i32,
+    sent: HashSet<ChunkPos>,
+    queue: VecDeque<ChunkPos>
+}
+
+impl ChunkView {
+    pub fn new(center: ChunkPos, radius: i32) -> Self {
+        Self {
+            center,
+            radius,
+            sent: HashSet::new(),
+            queue: spiral(center, radius).collect()
+        }
+    }
+
+    pub fn center(&self) -> ChunkPos {
+        self.center
+    }
+
+    pub fn radius(&self) -> i32 {
+        self.radius
+    }
+
+    pub fn contains(&self, pos: ChunkPos) -> bool {
+        (pos.x - self.center.x).abs() <= self.radius && (pos.z - self.center.z).abs() <= self.radius
+    }
+
+    pub fn is_sent(&self, pos: ChunkPos) -> bool {
+        self.sent.contains(&pos)
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    // moves the view and returns the chunks the client has to forget
+    pub fn update(&mut self, center: ChunkPos, radius: i32) -> Vec<ChunkPos> {
+        if center == self.center && radius == self.radius {
+            return vec![]
+        }
+
+        self.center = center;
+        self.radius = radius;
+
+        let unloaded: Vec<ChunkPos> = self.sent.iter().copied().filter(|pos| !self.contains(*pos)).collect();
+
+        for pos in &unloaded {
+            self.sent.remove(pos);
+        }
+
+        self.queue = spiral(center, radius).filter(|pos| !self.sent.contains(pos)).collect();
+        unloaded
+    }
+
+    // forgets every chunk the client has so the whole view goes out again, returns them so the client can drop them first
+    pub fn reset(&mut self) -> Vec<ChunkPos> {
+        self.queue = spiral(self.center, self.radius).collect();
+        self.sent.drain().collect()
+    }
+
+    // the chunks still to be sent, nearest first
+    pub fn queued(&self) -> impl Iterator<Item = ChunkPos> + '_ {
+        self.queue.iter().copied()
+    }
+
+    // takes up to `count` of the chunks that are ready off the queue, counting them as sent
+    pub fn next(&mut self, count: usize, ready: impl Fn(ChunkPos) -> bool) -> Vec<ChunkPos> {
+        let mut chunks = vec![];
+
+        self.queue.retain(|pos| {
+            if chunks.len() < count && ready(*pos) {
+                chunks.push(*pos);
+                false
+            } else {
+                true
+            }
+        });
+
+        self.sent.extend(&chunks);
+        chunks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn positions(positions: &[(i32, i32)]) -> Vec<ChunkPos> {
+        positions.iter().map(|(x, z)| ChunkPos::new(*x, *z)).collect()
+    }
+
+    #[test]
+    fn spirals_out_from_the_center() {
+        let first: Vec<ChunkPos> = spiral(ChunkPos::new(5, -3), 1).collect();
+        assert_eq!(first, positions(&[(5, -3), (4, -4), (5, -4), (6, -4), (6, -3), (6, -2), (5, -2), (4, -2), (4, -3)]));
+
+        // every chunk of the square once, no ring before one nearer the center
+        let all: Vec<ChunkPos> = spiral(ChunkPos::new(5, -3), 3).collect();
+        let rings: Vec<i32> = all.iter().map(|pos| (pos.x - 5).abs().max((pos.z + 3).abs())).collect();
+
+        assert_eq!(all.len(), 49);
+        assert_eq!(all.iter().collect::<HashSet<_>>().len(), 49);
+        assert!(rings.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+
+    #[test]
+    fn unloads_what_leaves_the_view() {
+        let mut view = ChunkView::new(ChunkPos::new(0, 0), 2);
+        assert_eq!(view.next(25, |_| true).len(), 25);
+        assert!(view.is_complete());
+        assert!(view.update(ChunkPos::new(0, 0), 2).is_empty());
+
+        // a step east drops the west column and queues the new east one
+        let unloaded: HashSet<ChunkPos> = view.update(ChunkPos::new(1, 0), 2).into_iter().collect();
+        assert_eq!(unloaded, (-2..=2).map(|z| ChunkPos::new(-2, z)).collect());
+        assert_eq!(view.queued().collect::<HashSet<_>>(), (-2..=2).map(|z| ChunkPos::new(3, z)).collect());
+        assert!(!view.is_sent(ChunkPos::new(-2, 0)));
+        assert!(view.is_sent(ChunkPos::new(2, 2)));
+
+        // shrinking drops what was sent outside the inner 3x3, the east column never went out
+        let unloaded = view.update(ChunkPos::new(1, 0), 1);
+        assert_eq!(unloaded.len(), 20 - 9);
+        assert!(unloaded.iter().all(|pos| !view.contains(*pos)));
+        assert!(view.is_complete());
+    }
+
+    #[test]
+    fn sends_the_nearest_ready_chunks_first() {
+        let mut view = ChunkView::new(ChunkPos::new(0, 0), 1);
+
+        assert_eq!(view.next(3, |pos| pos.x >= 0), positions(&[(0, 0), (0, -1), (1, -1)]));
+        assert_eq!(view.next(10, |pos| pos.x >= 0), positions(&[(1, 0), (1, 1), (0, 1)]));
+        assert_eq!(view.queued().collect::<Vec<_>>(), positions(&[(-1, -1), (-1, 1), (-1, 0)]));
+        assert!(view.next(10, |_| false).is_empty());
+        assert_eq!(view.next(10, |_| true).len(), 3);
+        assert!(view.is_complete());
+    }
+
+    #[test]
+    fn caps_the_radius_the_client_asks_for() {
+        assert_eq!(radius(None, 10), 10);
+        assert_eq!(radius(Some(5), 10), 5);
+        assert_eq!(radius(Some(32), 10), 10);
+        assert_eq!(radius(Some(0), 10), 2);
+        assert_eq!(radius(Some(-1), 10), 2);
+    }
+}
@@ -0,0 +1,273 @@
+use std::{cmp::Reverse, collections::{BinaryHeap, HashMap}, sync::{mpsc, Arc, Condvar, Mutex}, thread::{self, JoinHandle}, time::Duration};
+
+use crate::error;
+
+use crate::registry::{DimensionType, RegistryCodec};
+
+use super::{chunk::{Chunk, ChunkPos}, generator::WorldGenerator, save::ChunkReader};
+
+// everything needed to come up with a chunk away from the world
+#[derive(Clone)]
+pub struct ChunkSource {
+    pub generator: Arc<dyn WorldGenerator>,
+    pub reader: ChunkReader,
+    pub registry: Arc<RegistryCodec>,
+    pub dimension: DimensionType
+}
+
+impl ChunkSource {
+    // the saved chunk, or a freshly generated one when there is none
+    // a chunk that cannot be read is generated as a placeholder, which is never saved over it
+    pub fn load(&self, pos: ChunkPos) -> Chunk {
+        let (stored, placeholder) = match self.reader.load_chunk(pos, &self.registry, &self.dimension) {
+            Ok(stored) => (stored, false),
+            Err(error) => {
+                error!("failed to read chunk <green>{} {}</>, it will not be saved\n{}", pos.x, pos.z, error);
+                (None, true)
+            }
+        };
+
+        stored.unwrap_or_else(|| {
+            let mut chunk = Chunk::new(pos, self.dimension.min_y, self.dimension.height, 0);
+            self.generator.generate(&mut chunk);
+            chunk.set_placeholder(placeholder);
+            chunk
+        })
+    }
+}
+
+#[derive(Default)]
+struct Queue {
+    // the current priority of every queued chunk, heap entries that disagree with it are stale
+    wanted: HashMap<ChunkPos, i32>,
+    heap: BinaryHeap<Reverse<(i32, i32, i32)>>,
+    stopped: bool
+}
+
+impl Queue {
+    // false when the chunk is queued with that priority already
+    fn push(&mut self, pos: ChunkPos, priority: i32) -> bool {
+        if self.wanted.insert(pos, priority) == Some(priority) {
+            return false
+        }
+
+        self.heap.push(Reverse((priority, pos.x, pos.z)));
+        true
+    }
+
+    fn remove(&mut self, pos: ChunkPos) {
+        self.wanted.remove(&pos);
+    }
+
+    fn reprioritize(&mut self, priority: impl Fn(ChunkPos) -> i32) {
+        for (pos, current) in self.wanted.iter_mut() {
+            *current = priority(*pos);
+        }
+
+        self.heap = self.wanted.iter().map(|(pos, priority)| Reverse((*priority, pos.x, pos.z))).collect();
+    }
+
+    // the wanted chunk with the lowest priority, ties go to the lowest x and then z
+    fn pop(&mut self) -> Option<ChunkPos> {
+        while let Some(Reverse((priority, x, z))) = self.heap.pop() {
+            let pos = ChunkPos::new(x, z);
+
+            if self.wanted.get(&pos) == Some(&priority) {
+                self.wanted.remove(&pos);
+                return Some(pos)
+            }
+        }
+
+        None
+    }
+}
+
+#[derive(Default)]
+struct Shared {
+    queue: Mutex<Queue>,
+    ready: Condvar
+}
+
+// loads and generates chunks on their own threads, lowest priority first
+pub struct ChunkWorkers {
+    source: ChunkSource,
+    shared: Arc<Shared>,
+    completions: mpsc::Receiver<Chunk>,
+    // chunks wait took off the channel, completed hands them out first
+    arrived: Vec<Chunk>,
+    threads: Vec<JoinHandle<()>>
+}
+
+impl ChunkWorkers {
+    pub fn new(source: ChunkSource, threads: usize) -> Self {
+        let shared = Arc::new(Shared::default());
+        let (sender, completions) = mpsc::channel();
+
+        let threads = (0..threads.max(1))
+            .map(|_| {
+                let source = source.clone();
+                let shared = shared.clone();
+                let sender = sender.clone();
+                thread::spawn(move || work(source, shared, sender))
+            })
+            .collect();
+
+        Self { source, shared, completions, arrived: vec![], threads }
+    }
+
+    // one core is left to the tick loop
+    pub fn default_threads() -> usize {
+        thread::available_parallelism().map_or(1, |threads| threads.get().saturating_sub(1).max(1))
+    }
+
+    pub fn source(&self) -> &ChunkSource {
+        &self.source
+    }
+
+    // queues the chunk, or only moves it when it already is
+    pub fn request(&self, pos: ChunkPos, priority: i32) {
+        if self.shared.queue.lock().unwrap().push(pos, priority) {
+            self.shared.ready.notify_one();
+        }
+    }
+
+    // a chunk a worker already started on still comes back
+    pub fn cancel(&self, pos: ChunkPos) {
+        self.shared.queue.lock().unwrap().remove(pos);
+    }
+
+    pub fn reprioritize(&self, priority: impl Fn(ChunkPos) -> i32) {
+        self.shared.queue.lock().unwrap().reprioritize(priority);
+    }
+
+    pub fn completed(&mut self) -> impl Iterator<Item = Chunk> + '_ {
+        self.arrived.drain(..).chain(self.completions.try_iter())
+    }
+
+    // blocks until a chunk comes back, false when none did in time
+    pub fn wait(&mut self, timeout: Duration) -> bool {
+        match self.completions.recv_timeout(timeout) {
+            Ok(chunk) => {
+                self.arrived.push(chunk);
+                true
+            }
+            Err(_) => false
+        }
+    }
+}
+
+impl Drop for ChunkWorkers {
+    fn drop(&mut self) {
+        self.shared.queue.lock().unwrap().stopped = true;
+        self.shared.ready.notify_all();
+
+        for thread in self.threads.drain(..) {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn work(source: ChunkSource, shared: Arc<Shared>, completions: mpsc::Sender<Chunk>) {
+    loop {
+        let pos = {
+            let mut queue = shared.queue.lock().unwrap();
+
+            loop {
+                if queue.stopped {
+                    return
+                }
+
+                match queue.pop() {
+                    Some(pos) => break pos,
+                    None => queue = shared.ready.wait(queue).unwrap()
+                }
+            }
+        };
+
+        if completions.send(source.load(pos)).is_err() {
+            return
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::{generator::flat::FlatGenerator, save::Saver, storage::MemoryStorage};
+
+    fn drain(queue: &mut Queue) -> Vec<(i32, i32)> {
+        std::iter::from_fn(|| queue.pop()).map(|pos| (pos.x, pos.z)).collect()
+    }
+
+    #[test]
+    fn hands_out_the_lowest_priority_first() {
+        let mut queue = Queue::default();
+
+        assert!(queue.push(ChunkPos::new(0, 0), 3));
+        assert!(queue.push(ChunkPos::new(1, 0), 1));
+        assert!(queue.push(ChunkPos::new(2, 0), 2));
+        assert!(queue.push(ChunkPos::new(-1, 5), 2));
+        assert!(!queue.push(ChunkPos::new(2, 0), 2));
+
+        assert_eq!(drain(&mut queue), [(1, 0), (-1, 5), (2, 0), (0, 0)]);
+        assert!(queue.wanted.is_empty());
+    }
+
+    #[test]
+    fn moves_requested_chunks() {
+        let mut queue = Queue::default();
+
+        for x in 0..4 {
+            queue.push(ChunkPos::new(x, 0), x);
+        }
+
+        // requested again with another priority, the old heap entry is skipped
+        queue.push(ChunkPos::new(3, 0), -1);
+        assert_eq!(queue.heap.len(), 5);
+        assert_eq!(queue.pop(), Some(ChunkPos::new(3, 0)));
+
+        queue.reprioritize(|pos| -pos.x);
+        assert_eq!(drain(&mut queue), [(2, 0), (1, 0), (0, 0)]);
+    }
+
+    #[test]
+    fn skips_cancelled_chunks() {
+        let mut queue = Queue::default();
+        queue.push(ChunkPos::new(0, 0), 0);
+        queue.push(ChunkPos::new(1, 0), 1);
+        queue.remove(ChunkPos::new(0, 0));
+
+        assert_eq!(drain(&mut queue), [(1, 0)]);
+
+        // and takes them again once they are requested again
+        queue.push(ChunkPos::new(0, 0), 0);
+        assert_eq!(drain(&mut queue), [(0, 0)]);
+    }
+
+    #[test]
+    fn brings_back_every_requested_chunk() {
+        let registry = Arc::new(RegistryCodec::vanilla());
+        let saver = Saver::new(MemoryStorage::new());
+        let mut workers = ChunkWorkers::new(ChunkSource {
+            generator: Arc::new(FlatGenerator::new("bedrock,2*dirt,grass_block", "minecraft:plains", &registry).unwrap()),
+            reader: saver.reader(),
+            dimension: registry.dimension_type("minecraft:overworld").unwrap(),
+            registry
+        }, 2);
+
+        for x in 0..6 {
+            workers.request(ChunkPos::new(x, 0), x);
+        }
+
+        let mut positions = vec![];
+
+        while positions.len() < 6 {
+            assert!(workers.wait(Duration::from_secs(30)), "the workers stopped bringing chunks back");
+            positions.extend(workers.completed().map(|chunk| chunk.pos.x));
+        }
+
+        positions.sort();
+        assert_eq!(positions, [0, 1, 2, 3, 4, 5]);
+        assert!(!workers.wait(Duration::from_millis(50)));
+    }
+}